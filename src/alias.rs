@@ -0,0 +1,115 @@
+// Copyright (C) 2023 Campbell M. Cole
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+// command aliases from the config, e.g. `lunch = "out --note 'lunch break'"`.
+// they are split like a shell would split them and expanded before the
+// arguments are parsed, so an alias can stand for any command with any
+// arguments. the built in commands can't be replaced, and aliases can't refer
+// to other aliases
+
+use std::{collections::BTreeMap, env, ffi::OsString, fs};
+
+use clap::CommandFactory;
+
+use crate::{config::parse_config, Cli};
+
+/// Replaces an alias in place of the command with what it stands for.
+pub fn expand(args: Vec<OsString>) -> Vec<OsString> {
+    let Some(pos) = command_position(&args) else {
+        return args;
+    };
+    let Some(name) = args[pos].to_str() else {
+        return args;
+    };
+    if is_command(name) {
+        return args;
+    }
+    let Some(expansion) = read_aliases(&args[..pos]).remove(name) else {
+        return args;
+    };
+    // unbalanced quotes are reported by `config check`
+    let Ok(words) = shell_words::split(&expansion) else {
+        return args;
+    };
+
+    let mut expanded = args[..pos].to_vec();
+    expanded.extend(words.into_iter().map(OsString::from));
+    expanded.extend_from_slice(&args[pos + 1..]);
+    expanded
+}
+
+/// Checks if `name` is a built in command, or one of their aliases.
+pub fn is_command(name: &str) -> bool {
+    name == "help" || Cli::command().find_subcommand(name).is_some()
+}
+
+/// The index of the first argument which isn't an option or the value of one.
+fn command_position(args: &[OsString]) -> Option<usize> {
+    let command = Cli::command();
+    let takes_value = |arg: &str| {
+        command
+            .get_arguments()
+            .filter(|a| a.get_action().takes_values())
+            .any(|a| {
+                a.get_long().is_some_and(|long| arg == format!("--{long}"))
+                    || a.get_short()
+                        .is_some_and(|short| arg == format!("-{short}"))
+            })
+    };
+
+    let mut i = 1;
+    while i < args.len() {
+        let arg = args[i].to_str()?;
+        if !arg.starts_with('-') {
+            return Some(i);
+        }
+        // the value is only a separate argument if it isn't attached, e.g. `-d=x`
+        i += if takes_value(arg) { 2 } else { 1 };
+    }
+    None
+}
+
+/// Reads the aliases from the config in the data folder given by `options`.
+fn read_aliases(options: &[OsString]) -> BTreeMap<String, String> {
+    let data_folder = data_folder_option(options)
+        .or_else(|| env::var_os("PUNCHCARD_DATA_FOLDER"))
+        .map_or_else(crate::default_data_folder, Into::into);
+    // a broken config is reported by the commands which read it
+    fs::read_to_string(data_folder.join("config.toml"))
+        .ok()
+        .and_then(|contents| parse_config(&contents).ok())
+        .map(|config| config.aliases)
+        .unwrap_or_default()
+}
+
+/// The value of '--data-folder' in `options`, in any of the forms clap accepts.
+fn data_folder_option(options: &[OsString]) -> Option<OsString> {
+    let mut options = options.iter();
+    while let Some(arg) = options.next() {
+        if arg == "-d" || arg == "--data-folder" {
+            return options.next().cloned();
+        }
+        let Some(arg) = arg.to_str() else {
+            continue;
+        };
+        if let Some(value) = arg.strip_prefix("--data-folder=") {
+            return Some(value.into());
+        }
+        if let Some(value) = arg.strip_prefix("-d") {
+            return Some(value.strip_prefix('=').unwrap_or(value).into());
+        }
+    }
+    None
+}
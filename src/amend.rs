@@ -0,0 +1,151 @@
+// Copyright (C) 2023 Campbell M. Cole
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+// when the kiosk has a supervisor PIN, changes to the hours of a kiosk user
+// made with '--user' are only proposed, and the data file is left alone until
+// a supervisor approves them with 'punchcard approve'. only clocking in or out
+// at the current time is written right away
+
+use std::fs;
+
+use crate::prelude::*;
+
+/// Entries this many seconds from the current time are punches, not backfills.
+const PUNCH_TOLERANCE_SECS: i64 = 60;
+
+/// A change to the data file of a kiosk user, stored in `amendments.json` in
+/// their data folder.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Amendment {
+    pub id: u32,
+    pub proposed_at: DateTime<Local>,
+    /// The command line which proposed the change
+    pub command: String,
+    pub removed: Vec<Entry>,
+    pub added: Vec<Entry>,
+    /// `None` while waiting for a supervisor
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resolution: Option<Resolution>,
+}
+
+/// What a supervisor decided. Resolved amendments are kept as a record of
+/// every change made to the data file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Resolution {
+    pub approved: bool,
+    pub at: DateTime<Local>,
+}
+
+impl Amendment {
+    pub fn is_pending(&self) -> bool {
+        self.resolution.is_none()
+    }
+
+    /// A short summary such as `+2 -1`.
+    pub fn changes(&self) -> String {
+        match (self.added.len(), self.removed.len()) {
+            (added, 0) => format!("+{added}"),
+            (0, removed) => format!("-{removed}"),
+            (added, removed) => format!("+{added} -{removed}"),
+        }
+    }
+
+    /// Applies the change to the given entries.
+    ///
+    /// Fails if an entry it removes is gone, since the entries were changed
+    /// some other way after the amendment was proposed.
+    pub fn apply(&self, entries: &mut Vec<Entry>) -> Result<()> {
+        for removed in &self.removed {
+            let Some(index) = entries.iter().position(|e| e == removed) else {
+                return Err(eyre!(
+                    "The clock-{} at {} was changed after amendment {} was proposed",
+                    removed.entry_type,
+                    removed.timestamp.format(SLIM_DATETIME),
+                    self.id
+                ))
+                .suggestion("Reject the amendment and make the change again");
+            };
+            entries.remove(index);
+        }
+        entries.extend(self.added.iter().cloned());
+        entries.sort_by_key(|e| e.timestamp);
+        Ok(())
+    }
+}
+
+pub fn read_amendments(cli_args: &Cli) -> Result<Vec<Amendment>> {
+    read_json_file(&cli_args.get_amendments_file())
+}
+
+pub fn write_amendments(cli_args: &Cli, amendments: &[Amendment]) -> Result<()> {
+    write_json_file(&cli_args.get_amendments_file(), &amendments)
+}
+
+/// Whether adding the entry must wait for a supervisor.
+pub fn needs_approval(cli_args: &Cli, entry: &Entry) -> bool {
+    cli_args.supervised
+        && (entry.timestamp - Local::now()).num_seconds().abs() > PUNCH_TOLERANCE_SECS
+}
+
+/// Stores a change for a supervisor to approve instead of making it.
+pub fn propose(cli_args: &Cli, removed: Vec<Entry>, added: Vec<Entry>) -> Result<()> {
+    use owo_colors::OwoColorize;
+
+    if removed.is_empty() && added.is_empty() {
+        return Ok(());
+    }
+
+    fs::create_dir_all(&cli_args.data_folder)
+        .wrap_err("Failed to create the data folder")
+        .suggestion(SUGG_PROPER_PERMS(&cli_args.data_folder))?;
+
+    let mut amendments = read_amendments(cli_args)?;
+    let amendment = Amendment {
+        id: amendments.iter().map(|a| a.id).max().unwrap_or_default() + 1,
+        proposed_at: Local::now(),
+        command: command_line(),
+        removed,
+        added,
+        resolution: None,
+    };
+    println!(
+        "{}",
+        format!(
+            "The change ({}) was saved as amendment {} and needs to be approved by a supervisor",
+            amendment.changes(),
+            amendment.id
+        )
+        .yellow()
+    );
+    amendments.push(amendment);
+    write_amendments(cli_args, &amendments)
+}
+
+/// Proposes replacing `old` with `new`, which only records the entries that differ.
+pub fn propose_rewrite(cli_args: &Cli, old: Vec<Entry>, new: &[Entry]) -> Result<()> {
+    let mut added = new.to_vec();
+    let mut removed = Vec::new();
+    for entry in old {
+        match added.iter().position(|e| *e == entry) {
+            Some(index) => {
+                added.remove(index);
+            }
+            None => removed.push(entry),
+        }
+    }
+    propose(cli_args, removed, added)
+}
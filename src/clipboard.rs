@@ -0,0 +1,76 @@
+// Copyright (C) 2023 Campbell M. Cole
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::{
+    io::{ErrorKind, Write},
+    process::{Command, Stdio},
+};
+
+use crate::prelude::*;
+
+/// The clipboard tools of each platform with their arguments, in the order
+/// they are tried. On Linux, which one works depends on the display server.
+fn clipboard_tools() -> &'static [(&'static str, &'static [&'static str])] {
+    if cfg!(target_os = "macos") {
+        &[("pbcopy", &[])]
+    } else if cfg!(windows) {
+        &[("clip", &[])]
+    } else {
+        &[
+            ("wl-copy", &[]),
+            ("xclip", &["-selection", "clipboard"]),
+            ("xsel", &["--clipboard", "--input"]),
+        ]
+    }
+}
+
+/// Copies text to the clipboard using the clipboard tool of the platform.
+pub fn copy_to_clipboard(text: &str) -> Result<()> {
+    for (program, args) in clipboard_tools() {
+        let mut child = match Command::new(program)
+            .args(*args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(err) if err.kind() == ErrorKind::NotFound => continue,
+            Err(err) => return Err(err).wrap_err(format!("Failed to run {program}")),
+        };
+
+        let mut stdin = child.stdin.take().expect("stdin was piped above");
+        stdin
+            .write_all(text.as_bytes())
+            .wrap_err_with(|| format!("Failed to write to {program}"))?;
+        drop(stdin);
+
+        let status = child
+            .wait()
+            .wrap_err_with(|| format!("Failed to run {program}"))?;
+        if !status.success() {
+            return Err(eyre!("{program} exited with {status}"));
+        }
+        return Ok(());
+    }
+
+    Err(eyre!("No clipboard tool was found")).suggestion(if cfg!(target_os = "macos") {
+        "Ensure `pbcopy` is available"
+    } else if cfg!(windows) {
+        "Ensure `clip` is available"
+    } else {
+        "Install `wl-clipboard` on Wayland, or `xclip` or `xsel` on X11"
+    })
+}
@@ -0,0 +1,222 @@
+// Copyright (C) 2023 Campbell M. Cole
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+// absences such as vacations are kept in their own file, like the on-call
+// periods. reports mark the days and weeks they fall on, and leave those days
+// out of the scheduled time, so a week of vacation doesn't count as a week of
+// missing hours
+
+use std::fmt::Display;
+
+use chrono::NaiveDate;
+use clap::ValueEnum;
+
+use crate::{
+    prelude::*,
+    table::{settings::TableSettings, table_config, DataFrameDisplay, StringTable},
+};
+
+const RES_FROM: &str = "From";
+const RES_TO: &str = "To";
+const RES_DAYS: &str = "Days";
+const RES_KIND: &str = "Kind";
+
+#[derive(Debug, Args)]
+pub struct AbsenceArgs {
+    #[clap(subcommand)]
+    pub operation: AbsenceOperation,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum AbsenceOperation {
+    /// Record days you were or will be away
+    Add {
+        /// The days, e.g. `2024-07-01..2024-07-14`, or `2024-07-01` for one day
+        days: DateRange,
+        /// What kind of absence it is
+        #[clap(short, long, value_enum, default_value_t = AbsenceKind::Vacation)]
+        kind: AbsenceKind,
+    },
+    /// Remove the absences on any of the given days
+    Remove {
+        /// The days, e.g. `2024-07-01..2024-07-14`, or `2024-07-01` for one day
+        days: DateRange,
+    },
+    /// List the recorded absences
+    List {
+        #[clap(flatten)]
+        table_settings: TableSettings,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AbsenceKind {
+    Vacation,
+    Sick,
+    Holiday,
+    /// Any other leave, e.g. parental leave
+    Leave,
+}
+
+impl Display for AbsenceKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AbsenceKind::Vacation => write!(f, "vacation"),
+            AbsenceKind::Sick => write!(f, "sick"),
+            AbsenceKind::Holiday => write!(f, "holiday"),
+            AbsenceKind::Leave => write!(f, "leave"),
+        }
+    }
+}
+
+/// Days away from work, stored in `absences.json` in the data folder.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Absence {
+    #[serde(flatten)]
+    pub days: DateRange,
+    pub kind: AbsenceKind,
+}
+
+pub fn read_absences(cli_args: &Cli) -> Result<Vec<Absence>> {
+    read_json_file(&cli_args.get_absences_file())
+}
+
+/// Whether there is an absence on the given day.
+pub fn is_absent(absences: &[Absence], date: NaiveDate) -> bool {
+    absences.iter().any(|a| a.days.contains(date))
+}
+
+/// Marks a period of a report with the kinds of the absences in it, e.g.
+/// ` (vacation)`. `to` is the first day after the period.
+pub fn absence_marker(absences: &[Absence], from: NaiveDate, to: NaiveDate) -> Option<String> {
+    let Some(last) = to.pred_opt() else {
+        return None;
+    };
+    let period = DateRange { from, to: last };
+    let mut kinds = absences
+        .iter()
+        .filter(|a| a.days.overlaps(&period))
+        .map(|a| a.kind)
+        .collect::<Vec<_>>();
+    kinds.sort();
+    kinds.dedup();
+    (!kinds.is_empty()).then(|| {
+        let kinds = kinds.iter().map(|k| k.to_string()).collect::<Vec<_>>();
+        format!(" ({})", kinds.join(", "))
+    })
+}
+
+#[instrument]
+pub fn manage_absences(cli_args: &Cli, AbsenceArgs { operation }: &AbsenceArgs) -> Result<()> {
+    use owo_colors::OwoColorize;
+
+    let mut absences = read_absences(cli_args)?;
+
+    match operation {
+        AbsenceOperation::Add { days, kind } => {
+            if let Some(existing) = absences.iter().find(|a| a.days.overlaps(days)) {
+                return Err(eyre!(
+                    "The {} on {} overlaps the {} on {}",
+                    kind,
+                    days,
+                    existing.kind,
+                    existing.days
+                ))
+                .suggestion("Use 'punchcard absence remove' to remove it first");
+            }
+            absences.push(Absence {
+                days: *days,
+                kind: *kind,
+            });
+            absences.sort_by_key(|a| a.days.from);
+            println!(
+                "{} {} {} {}",
+                "Added".green(),
+                kind.bold(),
+                "on".bright_black(),
+                days.magenta()
+            );
+        }
+        AbsenceOperation::Remove { days } => {
+            let before = absences.len();
+            absences.retain(|a| !a.days.overlaps(days));
+            if absences.len() == before {
+                return Err(ErrorCode::NotFound.report(format!("There are no absences on {days}")))
+                    .suggestion("Use 'punchcard absence list' to list them");
+            }
+            println!(
+                "{} {} {}",
+                "Removed".green(),
+                (before - absences.len()).bold(),
+                "absence(s)".bright_black()
+            );
+        }
+        AbsenceOperation::List { table_settings } => {
+            return list_absences(cli_args, &absences, table_settings)
+        }
+    }
+
+    write_json_file(&cli_args.get_absences_file(), &absences)
+}
+
+fn list_absences(
+    cli_args: &Cli,
+    absences: &[Absence],
+    table_settings: &TableSettings,
+) -> Result<()> {
+    let date = |d: NaiveDate| d.format("%d %B %Y").to_string();
+    let table = StringTable::new()
+        .with_column(
+            RES_FROM,
+            "str",
+            absences.iter().map(|a| date(a.days.from)).collect(),
+        )
+        .with_column(
+            RES_TO,
+            "str",
+            absences.iter().map(|a| date(a.days.to)).collect(),
+        )
+        .with_column(
+            RES_DAYS,
+            "u32",
+            absences.iter().map(|a| a.days.days().to_string()).collect(),
+        )
+        .with_column(
+            RES_KIND,
+            "str",
+            absences.iter().map(|a| a.kind.to_string()).collect(),
+        );
+    println!(
+        "{}",
+        DataFrameDisplay::new(
+            &table,
+            table_settings,
+            table_config(cli_args),
+            cli_args.accessible
+        )
+        .with_caption("all absences".to_string())
+    );
+
+    if absences.is_empty() {
+        use owo_colors::OwoColorize;
+        println!(
+            "{}",
+            "There are no absences yet. Use 'punchcard absence add' to record one.".yellow()
+        );
+    }
+
+    Ok(())
+}
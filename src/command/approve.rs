@@ -0,0 +1,191 @@
+// Copyright (C) 2023 Campbell M. Cole
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use crate::{
+    amend::{read_amendments, write_amendments, Amendment, Resolution},
+    csv::{read_entries, write_entries, Revision},
+    prelude::*,
+    table::{settings::TableSettings, table_config, DataFrameDisplay, StringTable},
+};
+
+use super::kiosk::{hash_pin, prompt_hidden, read_kiosk, user_cli, Kiosk};
+
+const RES_USER: &str = "User";
+const RES_AMENDMENT: &str = "Amendment";
+const RES_PROPOSED: &str = "Proposed";
+const RES_COMMAND: &str = "Command";
+const RES_CHANGES: &str = "Changes";
+
+#[derive(Debug, Args)]
+pub struct ApproveArgs {
+    /// The ID of the kiosk user whose amendments to approve.
+    /// Without one, the pending amendments of every user are listed.
+    pub user: Option<String>,
+    /// The amendment to approve. Without one, all of the user's pending
+    /// amendments are approved.
+    pub amendment: Option<u32>,
+    /// Reject the amendments instead, leaving the data file as it is
+    #[clap(long, default_value_t = false)]
+    pub reject: bool,
+    #[clap(flatten)]
+    pub table_settings: TableSettings,
+}
+
+#[instrument]
+pub fn approve(cli_args: &Cli, args: &ApproveArgs) -> Result<()> {
+    if cli_args.supervised {
+        return Err(eyre!("Only a supervisor can approve amendments"))
+            .suggestion("Run the command without '--user'");
+    }
+
+    let kiosk = read_kiosk(cli_args)?;
+
+    let Some(id) = &args.user else {
+        return list_pending(cli_args, &kiosk, &args.table_settings);
+    };
+    if !kiosk.users.contains_key(id) {
+        return Err(
+            ErrorCode::NotFound.report(format!("There is no kiosk user with the ID '{id}'"))
+        );
+    }
+
+    let user = user_cli(cli_args, id);
+    let mut amendments = read_amendments(&user)?;
+    let selected = amendments
+        .iter()
+        .enumerate()
+        .filter(|(_, a)| a.is_pending() && args.amendment.map_or(true, |n| a.id == n))
+        .map(|(index, _)| index)
+        .collect::<Vec<_>>();
+    if selected.is_empty() {
+        return Err(ErrorCode::NotFound.report(match args.amendment {
+            Some(n) => format!("User '{id}' has no pending amendment {n}"),
+            None => format!("User '{id}' has no pending amendments"),
+        }));
+    }
+
+    check_pin(&kiosk)?;
+
+    let now = Local::now();
+    if !args.reject {
+        // every amendment is applied before anything is written, so a
+        // conflict leaves both the data file and the amendments untouched
+        let revision = Revision::current(&user)?;
+        let mut entries = match revision {
+            Some(_) => read_entries(&user)?,
+            None => Vec::new(),
+        };
+        for &index in &selected {
+            amendments[index].apply(&mut entries)?;
+        }
+        write_entries(&user, &entries, revision.as_ref())?;
+    }
+    for &index in &selected {
+        amendments[index].resolution = Some(Resolution {
+            approved: !args.reject,
+            at: now,
+        });
+    }
+    write_amendments(&user, &amendments)?;
+
+    {
+        use owo_colors::OwoColorize;
+        println!(
+            "{} {} of {}",
+            match args.reject {
+                true => "Rejected".red().bold().to_string(),
+                false => "Approved".green().bold().to_string(),
+            },
+            match selected.len() {
+                1 => format!("amendment {}", amendments[selected[0]].id),
+                n => format!("{n} amendments"),
+            },
+            kiosk.users[id].name.cyan().bold()
+        );
+    }
+
+    Ok(())
+}
+
+fn check_pin(kiosk: &Kiosk) -> Result<()> {
+    let Some(expected) = &kiosk.supervisor_pin else {
+        return Err(eyre!("There is no supervisor PIN"))
+            .suggestion("Set one with 'punchcard kiosk pin'");
+    };
+    if hash_pin(&prompt_hidden("Supervisor PIN: ")?) != *expected {
+        return Err(eyre!("Wrong PIN"));
+    }
+    Ok(())
+}
+
+fn list_pending(cli_args: &Cli, kiosk: &Kiosk, table_settings: &TableSettings) -> Result<()> {
+    let mut pending: Vec<(&str, Amendment)> = Vec::new();
+    for id in kiosk.users.keys() {
+        let amendments = read_amendments(&user_cli(cli_args, id))?;
+        pending.extend(
+            amendments
+                .into_iter()
+                .filter(Amendment::is_pending)
+                .map(|a| (id.as_str(), a)),
+        );
+    }
+
+    if pending.is_empty() {
+        use owo_colors::OwoColorize;
+        println!("{}", "There are no pending amendments".green());
+        return Ok(());
+    }
+
+    let table = StringTable::new()
+        .with_column(
+            RES_USER,
+            "str",
+            pending.iter().map(|(id, _)| id.to_string()).collect(),
+        )
+        .with_column(
+            RES_AMENDMENT,
+            "u32",
+            pending.iter().map(|(_, a)| a.id.to_string()).collect(),
+        )
+        .with_column(
+            RES_PROPOSED,
+            "str",
+            pending
+                .iter()
+                .map(|(_, a)| a.proposed_at.format(SLIM_DATETIME).to_string())
+                .collect(),
+        )
+        .with_column(
+            RES_COMMAND,
+            "str",
+            pending.iter().map(|(_, a)| a.command.clone()).collect(),
+        )
+        .with_column(
+            RES_CHANGES,
+            "str",
+            pending.iter().map(|(_, a)| a.changes()).collect(),
+        );
+
+    let display = DataFrameDisplay::new(
+        &table,
+        table_settings,
+        table_config(cli_args),
+        cli_args.accessible,
+    )
+    .with_caption("pending amendments");
+    println!("{display}");
+
+    Ok(())
+}
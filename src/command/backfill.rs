@@ -0,0 +1,219 @@
+// Copyright (C) 2023 Campbell M. Cole
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+// entering the hours of a whole week at once, for weeks which were tracked on
+// paper. the shifts are added like a batch, so either all of them fit between
+// the existing entries or nothing is added
+
+use std::{
+    fs,
+    io::{self, IsTerminal, Write},
+    path::PathBuf,
+};
+
+use chrono::{Datelike, Duration, NaiveDate, NaiveTime, Weekday};
+
+use crate::prelude::*;
+
+use super::{batch::add_entries, clock::EntryMetadataArgs};
+
+#[derive(Debug, Args)]
+pub struct BackfillArgs {
+    /// The week to enter the hours of
+    ///
+    /// Either `last`, `current`, or a date in the week, e.g. `2024-03-04`.
+    #[clap(short, long, default_value = "last")]
+    pub week: Week,
+    /// Read the hours from this file instead of asking for them
+    ///
+    /// Each line is a weekday followed by the shifts worked on it, e.g.
+    /// `mon 09:00-12:00 13:00-17:30`. Blank lines and lines starting with
+    /// `#` are ignored.
+    #[clap(long)]
+    pub from_file: Option<PathBuf>,
+    #[clap(flatten)]
+    pub metadata: EntryMetadataArgs,
+    /// Print the entries without changing the data file
+    #[clap(long, default_value_t = false)]
+    pub dry_run: bool,
+}
+
+/// The start and end times of the shifts worked on a day.
+type DayShifts = Vec<(NaiveTime, NaiveTime)>;
+
+/// Parses the shifts of a day, e.g. `09:00-12:00 13:00-17:30`.
+fn parse_shifts(s: &str) -> Result<DayShifts> {
+    s.split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|shift| !shift.is_empty())
+        .map(|shift| {
+            let Some((start, end)) = shift.split_once('-') else {
+                return Err(eyre!(
+                    "Expected a shift such as '09:00-17:00', found '{shift}'"
+                ));
+            };
+            let parse = |time: &str| {
+                parse_time_of_day(time.trim()).wrap_err(format!("Invalid time '{time}'"))
+            };
+            Ok((parse(start)?, parse(end)?))
+        })
+        .collect()
+}
+
+/// Parses one line of a file, e.g. `mon 09:00-17:00`.
+///
+/// Returns `None` for blank lines and comments (lines starting with `#`).
+fn parse_line(line: &str) -> Result<Option<(Weekday, DayShifts)>> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+    let (day, shifts) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+    let Ok(day) = day.parse::<Weekday>() else {
+        return Err(eyre!("Unknown weekday '{day}'"))
+            .suggestion("Start the line with a weekday, e.g. 'mon' or 'monday'");
+    };
+    Ok(Some((day, parse_shifts(shifts)?)))
+}
+
+#[instrument]
+pub fn backfill(cli_args: &Cli, args: &BackfillArgs) -> Result<()> {
+    use owo_colors::OwoColorize;
+
+    let today = Local::now().date_naive();
+    let monday = args.week.monday(today);
+    // the current week is only entered up to today
+    let days = (0..7)
+        .map(|n| monday + Duration::days(n))
+        .filter(|date| *date <= today)
+        .collect::<Vec<_>>();
+    if days.is_empty() {
+        return Err(eyre!(
+            "The week of {} hasn't started yet",
+            monday.format("%d %B %Y")
+        ));
+    }
+
+    let shifts = match &args.from_file {
+        Some(path) => read_file(path, &days)?,
+        None => ask_for_shifts(monday, &days)?,
+    };
+
+    let mut batch = Vec::new();
+    for (date, start, end) in shifts {
+        // a shift which ends before it starts goes past midnight
+        let end_date = if end <= start {
+            date + Duration::days(1)
+        } else {
+            date
+        };
+        for (entry_type, date, time) in [
+            (EntryType::ClockIn, date, start),
+            (EntryType::ClockOut, end_date, end),
+        ] {
+            let Some(timestamp) = Local.from_local_datetime(&date.and_time(time)).single() else {
+                return Err(eyre!(
+                    "{} on {} doesn't exist in the local timezone",
+                    time.format("%H:%M"),
+                    date.format("%d %B %Y")
+                ));
+            };
+            let mut entry = Entry::new(entry_type, timestamp, EntrySource::Backfill);
+            args.metadata.apply(&mut entry);
+            batch.push(entry);
+        }
+    }
+
+    if batch.is_empty() {
+        println!("{}", "No shifts were entered, nothing was added.".yellow());
+        return Ok(());
+    }
+
+    add_entries(cli_args, batch, args.dry_run)
+}
+
+/// Reads the shifts of the given days from a file, failing if any line is invalid.
+fn read_file(path: &PathBuf, days: &[NaiveDate]) -> Result<Vec<(NaiveDate, NaiveTime, NaiveTime)>> {
+    let contents =
+        fs::read_to_string(path).wrap_err(format!("Failed to read '{}'", path.display()))?;
+
+    let mut shifts = Vec::new();
+    let mut errors = Vec::new();
+    for (idx, line) in contents.lines().enumerate() {
+        let (day, day_shifts) = match parse_line(line) {
+            Ok(Some(parsed)) => parsed,
+            Ok(None) => continue,
+            Err(e) => {
+                errors.push(format!("line {}: {e}", idx + 1));
+                continue;
+            }
+        };
+        match days.iter().find(|date| date.weekday() == day) {
+            Some(date) => shifts.extend(day_shifts.into_iter().map(|(s, e)| (*date, s, e))),
+            None => errors.push(format!("line {}: {day} is later than today", idx + 1)),
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(eyre!(
+            "The file contains invalid lines, nothing was added:\n{}",
+            errors.join("\n")
+        ));
+    }
+    Ok(shifts)
+}
+
+/// Asks for the shifts of each day, asking again until the answer can be parsed.
+fn ask_for_shifts(
+    monday: NaiveDate,
+    days: &[NaiveDate],
+) -> Result<Vec<(NaiveDate, NaiveTime, NaiveTime)>> {
+    use owo_colors::OwoColorize;
+
+    if !io::stdin().is_terminal() {
+        return Err(eyre!("The hours can only be asked for in a terminal"))
+            .suggestion("Use '--from-file' to read them from a file instead");
+    }
+
+    println!(
+        "{} {}",
+        format!(
+            "Enter the shifts of the week of {},",
+            monday.format("%d %B %Y")
+        )
+        .yellow(),
+        "e.g. '09:00-17:00' or '09:00-12:00 13:00-17:30', or nothing for a day off".bright_black()
+    );
+
+    let mut shifts = Vec::new();
+    for date in days {
+        loop {
+            print!("{} ", date.format("%a %d %b:").cyan().bold());
+            io::stdout().flush().wrap_err("Failed to write to stdout")?;
+            let mut answer = String::new();
+            io::stdin()
+                .read_line(&mut answer)
+                .wrap_err("Failed to read the shifts")?;
+            match parse_shifts(&answer) {
+                Ok(day_shifts) => {
+                    shifts.extend(day_shifts.into_iter().map(|(s, e)| (*date, s, e)));
+                    break;
+                }
+                Err(e) => println!("{}", format!("{e:#}").red()),
+            }
+        }
+    }
+
+    Ok(shifts)
+}
@@ -0,0 +1,197 @@
+// Copyright (C) 2023 Campbell M. Cole
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::io::{self, BufRead};
+
+use crate::{
+    config::read_config,
+    csv::{read_entries, write_entries, Revision},
+    location::add_device_tag,
+    prelude::*,
+};
+
+use super::clock::format_metadata;
+
+#[derive(Debug, Args)]
+pub struct BatchArgs {
+    /// Validate the batch and print the entries without changing the data file
+    #[clap(long, default_value_t = false)]
+    pub dry_run: bool,
+}
+
+/// Parses one line of a batch, e.g. `in 2024-03-01T09:00 acme #meeting`.
+///
+/// Returns `None` for blank lines and comments (lines starting with `#`).
+pub(crate) fn parse_line(line: &str) -> Result<Option<Entry>> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+
+    let mut words = line.split_whitespace();
+
+    let entry_type = match words.next() {
+        Some("in") => EntryType::ClockIn,
+        Some("out") => EntryType::ClockOut,
+        Some(other) => return Err(eyre!("Unknown operation '{other}', expected 'in' or 'out'")),
+        None => unreachable!("the line is not empty"),
+    };
+
+    let Some(timestamp) = words.next() else {
+        return Err(eyre!("Missing timestamp"));
+    };
+    let Some(timestamp) = parse_local_datetime(timestamp) else {
+        return Err(eyre!("Invalid timestamp '{timestamp}'"))
+            .suggestion("Use a format like 2024-03-01T09:00 or 2024-03-01T09:00:00-08:00");
+    };
+
+    let mut entry = Entry::new(entry_type, timestamp, EntrySource::Batch);
+    let mut tags = Vec::new();
+    for word in words {
+        if let Some(tag) = word.strip_prefix('#') {
+            tags.push(tag.to_string());
+        } else if entry.project.is_none() {
+            entry.project = Some(word.to_string());
+        } else {
+            return Err(eyre!(
+                "Unexpected '{word}', an entry can only have one project"
+            ));
+        }
+    }
+    entry.tags = Tags(tags);
+
+    Ok(Some(entry))
+}
+
+/// Reads entries from stdin and adds all of them, or none of them if any are invalid.
+///
+/// Each line is `<in|out> <timestamp> [project] [#tag...]`. Unlike the `in` and
+/// `out` commands, entries can be placed before existing entries, as long as
+/// every clock-in is still followed by a clock-out.
+#[instrument]
+pub fn apply_batch(cli_args: &Cli, BatchArgs { dry_run }: &BatchArgs) -> Result<()> {
+    use owo_colors::OwoColorize;
+
+    let mut batch = Vec::new();
+    let mut errors = Vec::new();
+    for (idx, line) in io::stdin().lock().lines().enumerate() {
+        let line = line.wrap_err("Failed to read from stdin")?;
+        match parse_line(&line) {
+            Ok(Some(entry)) => batch.push(entry),
+            Ok(None) => {}
+            Err(e) => errors.push(format!("line {}: {e}", idx + 1)),
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(eyre!(
+            "The batch contains invalid lines, nothing was added:\n{}",
+            errors.join("\n")
+        ));
+    }
+
+    if batch.is_empty() {
+        println!("{}", "The batch is empty, nothing was added.".yellow());
+        return Ok(());
+    }
+
+    add_entries(cli_args, batch, *dry_run)
+}
+
+/// Adds the entries to the data file, or none of them if any clock-in wouldn't
+/// be followed by a clock-out. The entries don't have to be sorted.
+pub(crate) fn add_entries(cli_args: &Cli, mut batch: Vec<Entry>, dry_run: bool) -> Result<()> {
+    use owo_colors::OwoColorize;
+
+    // only the device is recorded, the entries may have been worked anywhere
+    if read_config(cli_args)?.record_device {
+        batch.iter_mut().for_each(add_device_tag);
+    }
+
+    let mut errors = Vec::new();
+    let revision = Revision::current(cli_args)?;
+    let existing = if cli_args.get_output_file().exists() {
+        read_entries(cli_args)?
+    } else {
+        Vec::new()
+    };
+
+    // keep track of which entries came from the batch so only the
+    // problems it introduces are reported
+    let mut merged = existing
+        .into_iter()
+        .map(|e| (e, false))
+        .chain(batch.iter().cloned().map(|e| (e, true)))
+        .collect::<Vec<_>>();
+    // a shift can end at the same instant the next one starts, in which case
+    // the clock-out has to come first
+    merged.sort_by_key(|(e, _)| (e.timestamp, e.entry_type == EntryType::ClockIn));
+
+    let mut previous: Option<&(Entry, bool)> = None;
+    for current in &merged {
+        let (entry, from_batch) = current;
+        let conflict = match previous {
+            Some((prev, prev_from_batch)) => {
+                (*from_batch || *prev_from_batch) && prev.entry_type == entry.entry_type
+            }
+            None => *from_batch && entry.entry_type == EntryType::ClockOut,
+        };
+        if conflict {
+            errors.push(format!(
+                "clocking {} at {} would follow another clock {}",
+                entry.entry_type,
+                entry.timestamp.format(SLIM_DATETIME),
+                previous
+                    .map(|(prev, _)| prev.entry_type.to_string())
+                    .unwrap_or_else(|| "out".to_string()),
+            ));
+        }
+        previous = Some(current);
+    }
+
+    if !errors.is_empty() {
+        return Err(ErrorCode::Continuity.report(format!(
+            "The new entries would violate continuity, nothing was added:\n{}",
+            errors.join("\n")
+        )))
+        .suggestion("Every clock in must be followed by a clock out and vice versa");
+    }
+
+    // the reports need entries to be strictly ordered, so ties are broken the
+    // same way `check_entry` does it, by moving the later entry by a millisecond
+    for idx in 1..merged.len() {
+        let previous = merged[idx - 1].0.timestamp;
+        if merged[idx].0.timestamp <= previous {
+            merged[idx].0.timestamp = previous + chrono::Duration::milliseconds(1);
+        }
+    }
+
+    for (entry, _) in merged.iter().filter(|(_, from_batch)| *from_batch) {
+        println!(
+            "{} {} {}{}",
+            if dry_run { "Would add" } else { "Added" }.green(),
+            entry.entry_type.colored(cli_args.accessible).bold(),
+            entry.timestamp.format(SLIM_DATETIME).magenta(),
+            format_metadata(entry),
+        );
+    }
+
+    if !dry_run {
+        let entries = merged.into_iter().map(|(e, _)| e).collect::<Vec<_>>();
+        write_entries(cli_args, &entries, revision.as_ref())?;
+    }
+
+    Ok(())
+}
@@ -13,79 +13,195 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
-use std::fs::File;
+use std::{
+    fs,
+    io::{self, IsTerminal, Write},
+};
 
+use chrono::Datelike;
 use chrono_tz::OffsetName;
 
-use crate::prelude::*;
+use crate::{
+    config::read_config,
+    csv::append_entry,
+    location::{add_configured_tags, location_tag},
+    notify::send_notification,
+    prelude::*,
+    shift::{read_shifts, start_of_day, total_between},
+};
 
-use super::status::{get_clock_status_inner, ClockStatus, ClockStatusType};
+use super::{
+    open::run_editor,
+    project::{budget_usage, read_projects},
+    report::{generate_report, parse_report_args},
+    status::{get_clock_status_inner, ClockStatus, ClockStatusType},
+    timer::{schedule_clock_out, spawn_timer},
+};
 
 #[derive(Debug, Args)]
 pub struct ClockEntryArgs {
     /// The offset from the current time to use as the clock in/out time
     #[clap(short, long)]
     pub offset_from_now: Option<BiDuration>,
+    #[clap(flatten)]
+    pub metadata: EntryMetadataArgs,
+    /// A note on what was worked on, e.g. when clocking out
+    #[clap(long)]
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct ClockInArgs {
+    #[clap(flatten)]
+    pub entry: ClockEntryArgs,
+    /// Timebox this session. A desktop notification is shown once this
+    /// much time has passed, unless you have clocked out already.
+    #[clap(long = "for")]
+    pub timebox: Option<BiDuration>,
+    /// Clock out automatically at the end of the timebox
+    #[clap(long, requires = "timebox", default_value_t = false)]
+    pub auto_out: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct ClockToggleArgs {
+    #[clap(flatten)]
+    pub entry: ClockEntryArgs,
+    /// Show a desktop notification with the new status and today's total.
+    /// Useful when toggling from a hotkey where there is no terminal.
+    #[clap(long, default_value_t = false)]
+    pub notify: bool,
+}
+
+#[derive(Debug, Clone, Default, Args)]
+pub struct EntryMetadataArgs {
+    /// The project this entry belongs to
+    #[clap(short, long)]
+    pub project: Option<String>,
+    /// A tag to attach to this entry. Can be applied multiple times.
+    #[clap(long = "tag", action = clap::ArgAction::Append)]
+    pub tags: Vec<String>,
+    /// The site this entry was made at, e.g. `site-a`. Recorded as a
+    /// `location:` tag so reports can be grouped with '-g location'.
+    #[clap(long)]
+    pub location: Option<String>,
+    /// Record this shift as travel time, which reports total separately.
+    /// Recorded as a `travel` tag.
+    #[clap(long, default_value_t = false)]
+    pub travel: bool,
+}
+
+impl EntryMetadataArgs {
+    pub fn apply(&self, entry: &mut Entry) {
+        entry.project = self.project.clone();
+        entry.tags = self.tags();
+    }
+
+    /// The tags, including the one for the location.
+    pub fn tags(&self) -> Tags {
+        let mut tags = self.tags.clone();
+        tags.extend(self.location.as_deref().map(location_tag));
+        if self.travel && !tags.iter().any(|t| t == TRAVEL_TAG) {
+            tags.push(TRAVEL_TAG.to_string());
+        }
+        Tags(tags)
+    }
 }
 
 #[instrument]
 pub fn add_entry(cli_args: &Cli, entry_type: EntryType, args: &ClockEntryArgs) -> Result<()> {
-    let status = get_clock_status_inner(cli_args, args.offset_from_now.relative_to_now())?;
-    add_entry_inner(cli_args, entry_type, args, status)
+    let timestamp = args.offset_from_now.relative_to_now();
+    let status = get_clock_status_inner(cli_args, timestamp)?;
+    let mut entry = Entry::new(entry_type, timestamp, EntrySource::Cli);
+    args.metadata.apply(&mut entry);
+    entry.note = args.note.clone();
+    prompt_for_note(cli_args, &mut entry, &status)?;
+    add_entry_inner(cli_args, entry, &args.offset_from_now, status)?;
+    Ok(())
 }
 
 #[instrument]
-fn add_entry_inner(
-    cli_args: &Cli,
-    entry_type: EntryType,
-    ClockEntryArgs { offset_from_now }: &ClockEntryArgs,
-    status: ClockStatus,
-) -> Result<()> {
-    let timestamp = offset_from_now.relative_to_now();
+pub fn clock_in(cli_args: &Cli, args: &ClockInArgs) -> Result<()> {
+    let ClockInArgs {
+        entry: entry_args,
+        timebox,
+        auto_out,
+    } = args;
 
-    // currently cannot allow entries before the latest entry
-    // because that would add a lot of complexity to the code.
-    // basically trying to avoid interpreting the entire file
-    // to make sure that every in has a matching out. this
-    // logic provides the same guarantee but is much simpler.
-    if let Some(until) = status.until {
-        return Err(eyre!(
-            "Adding this entry would violate continuity! There is an entry after the given time.\nTime given: {}\nNext entry: {}",
-            timestamp.format(SLIM_DATETIME),
-            until.format(SLIM_DATETIME),
-        ));
+    if matches!(timebox, Some(d) if **d <= chrono::Duration::zero()) {
+        return Err(eyre!("The timebox duration must be positive"));
     }
 
-    let data_file = cli_args.get_output_file();
+    let timestamp = entry_args.offset_from_now.relative_to_now();
+    let status = get_clock_status_inner(cli_args, timestamp)?;
+    let mut entry = Entry::new(EntryType::ClockIn, timestamp, EntrySource::Cli);
+    entry_args.metadata.apply(&mut entry);
+    entry.note = entry_args.note.clone();
+    // the entry may have been moved to keep it in order, and the timers
+    // below compare against the clock-in that was actually written
+    let since = add_entry_inner(cli_args, entry, &entry_args.offset_from_now, status)?;
 
-    let last_op = match status.status_type {
-        ClockStatusType::Entry(entry_type) => Some(entry_type),
-        _ => None,
+    let Some(timebox) = timebox else {
+        return Ok(());
     };
 
-    if matches!(last_op, Some(op) if op == entry_type) {
-        return Err(eyre!("Already clocked {entry_type}"));
+    let end = timestamp + **timebox;
+    let length = timebox.to_friendly_absolute_string();
+
+    if *auto_out {
+        let mut entry = Entry::new(EntryType::ClockOut, end, EntrySource::AutoClockOut);
+        entry_args.metadata.apply(&mut entry);
+        let offset = entry_args
+            .offset_from_now
+            .as_ref()
+            .map(|offset| BiDuration::new(**offset + **timebox))
+            .unwrap_or_else(|| timebox.clone());
+        let message = format!("Your {length} session is over. You have been clocked out.");
+        return schedule_clock_out(cli_args, entry, since, &Some(offset), &message);
     }
 
-    let entry = Entry {
-        entry_type,
-        timestamp,
-    };
+    // there's no point in notifying about something that already happened
+    if end > Local::now() {
+        let message = format!("Your {length} session is over. Don't forget to clock out!");
+        spawn_timer(cli_args, end, &message, Some(since))?;
+    }
+
+    Ok(())
+}
+
+/// Adds `entry` and returns the time it was written at, which can be slightly
+/// later than requested if it had to be moved after the last entry
+#[instrument]
+pub(crate) fn add_entry_inner(
+    cli_args: &Cli,
+    mut entry: Entry,
+    offset_from_now: &Option<BiDuration>,
+    status: ClockStatus,
+) -> Result<DateTime<Local>> {
+    super::git::add_repo_tags(&mut entry);
+    add_configured_tags(&read_config(cli_args)?, &mut entry);
+    super::oncall::add_incident_tag(cli_args, &mut entry)?;
+    check_entry(&mut entry, &status)?;
+
+    let written = append_entry(cli_args, &entry, status.revision.as_ref())?;
 
     {
         // this is in a block because owo_colors adds functions to almost every type
         // and it's super annoying to have it in scope all the time
         use owo_colors::{DynColors, OwoColorize};
-        // print this before saving because we have to move it
-        // and I'm trying to avoid unnecessary cloning
         let gray = DynColors::Rgb(128, 128, 128);
         let oparen = "(".color(gray);
         let cparen = ")".color(gray);
 
+        // a supervised kiosk user's entry only counts once it's approved
+        let clocked = match written {
+            true => "Clocked".color(gray).to_string(),
+            false => "Waiting for approval to clock".yellow().to_string(),
+        };
         println!(
-            "{} {} {} {}{}",
-            "Clocked".color(gray),
-            entry.entry_type.colored().bold(),
+            "{} {} {} {}{}{}",
+            clocked,
+            entry.entry_type.colored(cli_args.accessible).bold(),
             "@".color(gray),
             entry.timestamp.format(&format!(
                 "{} {}{}{} {} {}",
@@ -112,32 +228,125 @@ fn add_entry_inner(
             } else {
                 String::new()
             },
+            format_metadata(&entry),
         );
     }
 
-    let has_headers = !data_file.exists();
+    if !written {
+        return Ok(entry.timestamp);
+    }
+
+    // the entry was already added, so these can't fail the command
+    let result = match entry.entry_type {
+        EntryType::ClockIn => schedule_budget_alerts(cli_args, &entry),
+        EntryType::ClockOut => print_weekly_summary(cli_args, entry.timestamp),
+    };
+    if let Err(e) = result {
+        use owo_colors::OwoColorize;
+        eprintln!("{}", format!("{e:#}").yellow());
+    }
+
+    Ok(entry.timestamp)
+}
+
+/// Checks that `entry` can be added after the clock status it was read with,
+/// moving it forward if it would tie with the latest entry.
+pub(crate) fn check_entry(entry: &mut Entry, status: &ClockStatus) -> Result<()> {
+    let Entry {
+        entry_type,
+        timestamp,
+        ..
+    } = *entry;
+
+    // currently cannot allow entries before the latest entry
+    // because that would add a lot of complexity to the code.
+    // basically trying to avoid interpreting the entire file
+    // to make sure that every in has a matching out. this
+    // logic provides the same guarantee but is much simpler.
+    if let Some(until) = status.until {
+        return Err(ErrorCode::Continuity.report(format!(
+            "Adding this entry would violate continuity! There is an entry after the given time.\nTime given: {}\nNext entry: {}",
+            timestamp.format(SLIM_DATETIME),
+            until.format(SLIM_DATETIME),
+        )));
+    }
+
+    // the reports compute durations from the difference between consecutive
+    // entries, so two entries at the same instant (e.g. from a double press of
+    // a toggle hotkey) must still be strictly ordered
+    if status.since == Some(timestamp) {
+        entry.timestamp = timestamp + chrono::Duration::milliseconds(1);
+    }
+
+    let last_op = match status.status_type {
+        ClockStatusType::Entry(entry_type) => Some(entry_type),
+        _ => None,
+    };
+
+    if matches!(last_op, Some(op) if op == entry_type) {
+        return Err(ErrorCode::AlreadyClocked.report(format!("Already clocked {entry_type}")));
+    }
 
-    let file = File::options()
-        .create(true)
-        .append(true)
-        .open(&data_file)
-        .wrap_err(ERR_OPEN_CSV(&data_file))
-        .suggestion(SUGG_PROPER_PERMS(&data_file))?;
+    Ok(())
+}
 
-    let mut writer = csv::WriterBuilder::default()
-        .has_headers(has_headers)
-        .from_writer(file);
+/// Schedules a notification for every budget threshold of the project that
+/// will be reached during the shift started by `entry`.
+///
+/// The notifications are canceled by clocking out, like reminders.
+fn schedule_budget_alerts(cli_args: &Cli, entry: &Entry) -> Result<()> {
+    let Some(project) = &entry.project else {
+        return Ok(());
+    };
+    let now = Local::now();
+    let projects = read_projects(cli_args)?;
+    let shifts = read_shifts(cli_args)?;
+    let alerts = read_config(cli_args)?.budget_alerts();
 
-    writer
-        .serialize(entry)
-        .wrap_err(ERR_WRITE_CSV(&data_file))
-        .suggestion(SUGG_PROPER_PERMS(&data_file))?;
+    for usage in budget_usage(&projects, &shifts, project, entry.timestamp) {
+        for percent in &alerts {
+            let at = entry.timestamp + usage.until_percent(*percent);
+            // a daily cap starts over at midnight
+            if at <= now || (usage.daily && at.date_naive() != entry.timestamp.date_naive()) {
+                continue;
+            }
+            let message = format!(
+                "You have used {percent}% of the {} {} of {project}",
+                BiDuration::new(usage.limit).to_compact_string(),
+                usage.kind,
+            );
+            spawn_timer(cli_args, at, &message, Some(entry.timestamp))?;
+        }
+    }
 
     Ok(())
 }
 
+/// Prints the configured report when clocking out on the last workday of the week.
+fn print_weekly_summary(cli_args: &Cli, clocked_out_at: DateTime<Local>) -> Result<()> {
+    let config = read_config(cli_args)?;
+    let Some(summary) = &config.weekly_summary else {
+        return Ok(());
+    };
+    // clock-outs scheduled ahead of time (e.g. by '--auto-out') don't count
+    if clocked_out_at > Local::now() || clocked_out_at.weekday() != config.last_workday() {
+        return Ok(());
+    }
+
+    let settings = parse_report_args(&summary.report_args)
+        .suggestion("Check 'weekly-summary.report-args' in the config file")?;
+    println!();
+    generate_report(cli_args, &settings).wrap_err("Failed to generate weekly summary")
+}
+
 #[instrument]
-pub fn toggle_clock(cli_args: &Cli, args: &ClockEntryArgs) -> Result<()> {
+pub fn toggle_clock(
+    cli_args: &Cli,
+    ClockToggleArgs {
+        entry: args,
+        notify,
+    }: &ClockToggleArgs,
+) -> Result<()> {
     let timestamp = args.offset_from_now.relative_to_now();
 
     let status = get_clock_status_inner(cli_args, timestamp)?;
@@ -147,5 +356,98 @@ pub fn toggle_clock(cli_args: &Cli, args: &ClockEntryArgs) -> Result<()> {
         _ => EntryType::ClockIn,
     };
 
-    add_entry_inner(cli_args, next_op, args, status)
+    let mut entry = Entry::new(next_op, timestamp, EntrySource::Cli);
+    args.metadata.apply(&mut entry);
+    entry.note = args.note.clone();
+    prompt_for_note(cli_args, &mut entry, &status)?;
+
+    add_entry_inner(cli_args, entry, &args.offset_from_now, status)?;
+
+    if *notify {
+        let now = Local::now();
+        let shifts = read_shifts(cli_args)?;
+        let today = total_between(&shifts, start_of_day(now), now, now);
+        send_notification(
+            &format!("Clocked {next_op}"),
+            &format!(
+                "{} today",
+                BiDuration::new(today).to_friendly_absolute_string()
+            ),
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Asks what was worked on when clocking out from a terminal, if the config
+/// asks for it and no note was given already.
+fn prompt_for_note(cli_args: &Cli, entry: &mut Entry, status: &ClockStatus) -> Result<()> {
+    use owo_colors::OwoColorize;
+
+    if entry.entry_type != EntryType::ClockOut
+        || entry.note.is_some()
+        || !io::stdin().is_terminal()
+        || !read_config(cli_args)?.note_prompt
+    {
+        return Ok(());
+    }
+    // don't ask for a note which couldn't be saved anyway
+    check_entry(&mut entry.clone(), status)?;
+
+    print!(
+        "{} {} ",
+        "What did you work on?".yellow(),
+        "(enter to skip, 'e' for your editor)".bright_black()
+    );
+    io::stdout().flush().wrap_err("Failed to write to stdout")?;
+    let mut answer = String::new();
+    io::stdin()
+        .read_line(&mut answer)
+        .wrap_err("Failed to read the note")?;
+    let answer = match answer.trim() {
+        "e" => write_note_in_editor(cli_args)?,
+        answer => answer.to_string(),
+    };
+
+    entry.note = Some(answer).filter(|note| !note.is_empty());
+    Ok(())
+}
+
+/// Lets the user write a longer note in their editor.
+fn write_note_in_editor(cli_args: &Cli) -> Result<String> {
+    const INSTRUCTIONS: &str =
+        "# Write what you worked on. Lines starting with '#' are ignored, and an empty note is skipped.\n";
+
+    let path = cli_args.data_folder.join("note.txt");
+    fs::write(&path, INSTRUCTIONS)
+        .wrap_err("Failed to create the note file")
+        .suggestion(SUGG_PROPER_PERMS(&path))?;
+    let note = run_editor(&path)
+        .and_then(|_| fs::read_to_string(&path).wrap_err("Failed to read the note file"));
+    let _ = fs::remove_file(&path);
+
+    Ok(note?
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string())
+}
+
+/// Formats the project and tags of an entry for display after a clock operation.
+pub(crate) fn format_metadata(entry: &Entry) -> String {
+    use owo_colors::OwoColorize;
+
+    let mut s = String::new();
+    if let Some(project) = &entry.project {
+        s.push_str(&format!(" {}", project.bright_blue().bold()));
+    }
+    for tag in entry.tags.iter() {
+        s.push_str(&format!(" {}", format!("#{tag}").bright_black()));
+    }
+    if let Some(note) = &entry.note {
+        s.push_str(&format!(" {}", format!("\"{note}\"").italic()));
+    }
+    s
 }
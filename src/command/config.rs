@@ -0,0 +1,236 @@
+// Copyright (C) 2023 Campbell M. Cole
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::{fs, path::Path};
+
+use toml_edit::{Document, InlineTable, Item, Table, Value};
+
+use crate::{config::parse_config, prelude::*};
+
+#[derive(Debug, Args)]
+pub struct ConfigArgs {
+    #[clap(subcommand)]
+    pub operation: ConfigOperation,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ConfigOperation {
+    /// Check the config file for typos and invalid values
+    Check,
+    /// Print the value of a setting
+    ///
+    /// Nested settings are separated by dots, e.g. `schedule.start`.
+    /// Strings are printed as-is, everything else is printed as TOML.
+    Get {
+        /// The setting to print
+        key: String,
+    },
+    /// Change the value of a setting
+    ///
+    /// The value is read as TOML if possible, and as a string otherwise,
+    /// so `config set schedule.start 09:00` and
+    /// `config set budget-alerts '[50, 100]'` both work. The config is
+    /// checked before it is saved, and its comments and formatting are kept.
+    Set {
+        /// The setting to change, e.g. `schedule.start`
+        key: String,
+        /// The new value
+        value: String,
+    },
+    /// Remove a setting, so it uses its default value again
+    Unset {
+        /// The setting to remove, e.g. `weekly-summary`
+        key: String,
+    },
+}
+
+#[instrument]
+pub fn manage_config(cli_args: &Cli, ConfigArgs { operation }: &ConfigArgs) -> Result<()> {
+    match operation {
+        ConfigOperation::Check => check_config(cli_args),
+        ConfigOperation::Get { key } => get_setting(cli_args, key),
+        ConfigOperation::Set { key, value } => {
+            let value = value
+                .parse::<Value>()
+                .unwrap_or_else(|_| Value::from(value.as_str()));
+            set_setting(cli_args, key, Some(value))
+        }
+        ConfigOperation::Unset { key } => set_setting(cli_args, key, None),
+    }
+}
+
+/// Reads the config as a document which keeps its comments and formatting.
+fn read_document(path: &Path) -> Result<Document> {
+    if !path.exists() {
+        return Ok(Document::new());
+    }
+    fs::read_to_string(path)
+        .wrap_err(ERR_READ_TOML(path))
+        .suggestion(SUGG_PROPER_PERMS(path))?
+        .parse()
+        .wrap_err(ERR_READ_TOML(path))
+        .suggestion("Run 'punchcard config check' to find the problem")
+}
+
+fn get_setting(cli_args: &Cli, key: &str) -> Result<()> {
+    let config = read_document(&cli_args.get_config_file())?;
+
+    let setting = key
+        .split('.')
+        .try_fold(config.as_item(), |item, part| item.get(part));
+    match setting {
+        Some(Item::Value(Value::String(value))) => println!("{}", value.value()),
+        Some(Item::Value(value)) => println!("{}", value.clone().decorated("", "")),
+        Some(item) => println!("{}", item.to_string().trim()),
+        None => {
+            // setting an unknown key is rejected, a known one isn't
+            let mut probe = config.clone();
+            insert(&mut probe, key, Some(Value::from("")))?;
+            if let Err(problem) = parse_config(&probe.to_string()) {
+                if problem.contains("unknown field") {
+                    return Err(ErrorCode::NotFound.report(problem))
+                        .wrap_err(format!("There is no setting named '{key}'"));
+                }
+            }
+            return Err(ErrorCode::NotFound
+                .report(format!("'{key}' is not set, so it uses its default value")));
+        }
+    }
+
+    Ok(())
+}
+
+fn set_setting(cli_args: &Cli, key: &str, value: Option<Value>) -> Result<()> {
+    use owo_colors::OwoColorize;
+
+    let path = cli_args.get_config_file();
+    let mut config = read_document(&path)?;
+
+    let removing = value.is_none();
+    if !insert(&mut config, key, value)? && removing {
+        return Err(ErrorCode::NotFound.report(format!("'{key}' is not set")));
+    }
+
+    let contents = config.to_string();
+    let parsed = parse_config(&contents)
+        .map_err(|problem| eyre!(problem))
+        .wrap_err("The config would be invalid, so it was not changed")?;
+    let problems = parsed.problems();
+    if !problems.is_empty() {
+        return Err(eyre!(problems.join("\n")))
+            .wrap_err("The config would be invalid, so it was not changed");
+    }
+
+    fs::write(&path, contents)
+        .wrap_err(ERR_WRITE_TOML(&path))
+        .suggestion(SUGG_PROPER_PERMS(&path))?;
+
+    if removing {
+        println!("Removed {}", key.bold().cyan());
+    } else {
+        println!("Saved {}", key.bold().cyan());
+    }
+
+    Ok(())
+}
+
+/// Sets or removes the value at the dotted `key`, creating the tables
+/// above it as needed. Returns whether there was a value before.
+fn insert(config: &mut Document, key: &str, value: Option<Value>) -> Result<bool> {
+    let mut parts = key.split('.').collect::<Vec<_>>();
+    let last = parts.pop().expect("split always yields at least one part");
+
+    // a new table is set apart from what comes before it
+    let mut new_table = Table::new();
+    if !config.as_table().is_empty() {
+        new_table.decor_mut().set_prefix("\n");
+    }
+
+    let mut current = config.as_item_mut();
+    for (i, part) in parts.iter().enumerate() {
+        // an inline table can only hold values, so the tables in it are inline too
+        let inline = current.is_inline_table();
+        let Some(table) = current.as_table_like_mut() else {
+            return Err(eyre!(
+                "'{}' is not a group of settings",
+                parts[..i].join(".")
+            ));
+        };
+        current = table.entry(part).or_insert_with(|| match inline {
+            true => Item::Value(Value::InlineTable(InlineTable::new())),
+            false => Item::Table(new_table.clone()),
+        });
+    }
+
+    let Some(table) = current.as_table_like_mut() else {
+        return Err(eyre!("'{}' is not a group of settings", parts.join(".")));
+    };
+    Ok(match value {
+        Some(mut value) => {
+            // keep e.g. a comment after the old value
+            if let Some(Item::Value(old)) = table.get(last) {
+                *value.decor_mut() = old.decor().clone();
+            }
+            table.insert(last, Item::Value(value)).is_some()
+        }
+        None => table.remove(last).is_some(),
+    })
+}
+
+fn check_config(cli_args: &Cli) -> Result<()> {
+    use owo_colors::OwoColorize;
+
+    let path = cli_args.get_config_file();
+    if !path.exists() {
+        println!(
+            "{}",
+            "There is no config file yet, every setting uses its default value.".green()
+        );
+        return Ok(());
+    }
+
+    let contents = std::fs::read_to_string(&path)
+        .wrap_err(ERR_READ_TOML(&path))
+        .suggestion(SUGG_PROPER_PERMS(&path))?;
+
+    let config = match parse_config(&contents) {
+        Ok(config) => {
+            println!("{} Every setting can be read", "✓".green());
+            config
+        }
+        Err(problem) => {
+            println!("{} {}", "✗".red(), "Every setting can be read".bold());
+            println!("    {}", problem.bright_black());
+            return Err(eyre!("The config file can't be read"))
+                .suggestion("Fix the problem above, then run 'punchcard config check' again");
+        }
+    };
+
+    let problems = config.problems();
+    if problems.is_empty() {
+        println!("{} Every setting has a valid value", "✓".green());
+        return Ok(());
+    }
+
+    println!("{} {}", "✗".red(), "Every setting has a valid value".bold());
+    for problem in &problems {
+        println!("    {}", problem.bright_black());
+    }
+    Err(eyre!(
+        "Found {} problem(s) in the config file",
+        problems.len()
+    ))
+    .suggestion(format!("The config file is {}", path.display()))
+}
@@ -0,0 +1,305 @@
+// Copyright (C) 2023 Campbell M. Cole
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::{
+    cmp::Ordering,
+    fs::{self, File},
+    io::Write,
+};
+
+use crate::{
+    csv::{
+        ends_with_newline, header_state, malformed_rows, migrate_data_file, read_entries,
+        read_valid_entries, write_entries, HeaderState, Revision,
+    },
+    location::device_of,
+    prelude::*,
+};
+
+#[derive(Debug, Args)]
+pub struct DoctorArgs {
+    /// Repair the problems which can be fixed automatically
+    #[clap(long, default_value_t = false)]
+    pub fix: bool,
+    /// Only keep the rows which can be parsed, moving the data file to `quarantine/`
+    #[clap(long, default_value_t = false, conflicts_with = "fix")]
+    pub recover: bool,
+}
+
+struct Check {
+    /// What a healthy data file looks like
+    name: &'static str,
+    /// Describes every problem found
+    find: fn(&Cli) -> Result<Vec<String>>,
+    /// Repairs the problems, if that can be done without guessing
+    fix: Option<fn(&Cli) -> Result<()>>,
+}
+
+const CHECKS: &[Check] = &[
+    Check {
+        name: "The data file starts with the current header",
+        find: find_header_problems,
+        fix: Some(migrate_data_file),
+    },
+    Check {
+        name: "The data file ends with a line break",
+        find: find_missing_newline,
+        fix: Some(add_newline),
+    },
+    Check {
+        name: "Every row can be parsed",
+        find: find_malformed_rows,
+        fix: None,
+    },
+    Check {
+        name: "Every entry is later than the one before it",
+        find: find_unordered_entries,
+        fix: Some(separate_ties),
+    },
+];
+
+#[instrument]
+pub fn run_doctor(cli_args: &Cli, DoctorArgs { fix, recover }: &DoctorArgs) -> Result<()> {
+    use owo_colors::OwoColorize;
+
+    if !cli_args.get_output_file().exists() {
+        println!(
+            "{}",
+            "The data file doesn't exist yet, there is nothing to check.".green()
+        );
+        return Ok(());
+    }
+    if *recover {
+        return recover_rows(cli_args);
+    }
+
+    let mut remaining = 0;
+    let mut fixable = 0;
+
+    for check in CHECKS {
+        let mut problems = (check.find)(cli_args)?;
+
+        if let (true, Some(repair), false) = (fix, check.fix, problems.is_empty()) {
+            repair(cli_args).wrap_err_with(|| format!("Failed to fix '{}'", check.name))?;
+            problems = (check.find)(cli_args)?;
+            if problems.is_empty() {
+                println!("{} {} {}", "✓".green(), check.name, "(fixed)".yellow());
+                continue;
+            }
+        }
+
+        if problems.is_empty() {
+            println!("{} {}", "✓".green(), check.name);
+            continue;
+        }
+
+        println!("{} {}", "✗".red(), check.name.bold());
+        for problem in &problems {
+            println!("    {}", problem.bright_black());
+        }
+        remaining += problems.len();
+        if check.fix.is_some() {
+            fixable += problems.len();
+        }
+    }
+
+    if remaining > 0 {
+        let err = Err(eyre!("Found {remaining} problem(s) in the data file"));
+        return if fixable > 0 && !fix {
+            err.suggestion("Run 'punchcard doctor --fix' to repair the ones marked as fixable")
+        } else {
+            err.suggestion("The remaining problems must be fixed by editing the data file")
+        };
+    }
+
+    Ok(())
+}
+
+/// Replaces the data file with the rows which can be parsed. The original is
+/// kept in the quarantine folder, so the lost rows can still be fixed by hand.
+fn recover_rows(cli_args: &Cli) -> Result<()> {
+    use owo_colors::OwoColorize;
+
+    let lost = malformed_rows(cli_args)?;
+    if lost.is_empty() {
+        println!(
+            "{}",
+            "Every row can be parsed, there is nothing to recover.".green()
+        );
+        return Ok(());
+    }
+    let revision = Revision::current(cli_args)?;
+    let entries = read_valid_entries(cli_args)?;
+
+    let data_file = cli_args.get_output_file();
+    let folder = cli_args.get_quarantine_folder();
+    let quarantined = folder.join(format!(
+        "hours-{}.csv",
+        Local::now().format("%Y%m%d-%H%M%S")
+    ));
+    fs::create_dir_all(&folder)
+        .and_then(|_| fs::copy(&data_file, &quarantined))
+        .wrap_err_with(|| format!("Failed to quarantine the data file in {}", folder.display()))
+        .suggestion(SUGG_PROPER_PERMS(&folder))?;
+    write_entries(cli_args, &entries, revision.as_ref())?;
+
+    println!(
+        "{} {} entries, the original data file was copied to {}",
+        "Recovered".green(),
+        entries.len(),
+        quarantined.display()
+    );
+    println!(
+        "{} {} row(s) which can't be parsed, their lines are in the copy:",
+        "Lost".red(),
+        lost.len()
+    );
+    for err in &lost {
+        println!("    {}", err.to_string().bright_black());
+    }
+    println!(
+        "{}",
+        "Run 'punchcard doctor' to check the recovered entries".bright_black()
+    );
+
+    Ok(())
+}
+
+/// Counts the problems found by every check, without fixing any of them.
+pub fn count_problems(cli_args: &Cli) -> Result<usize> {
+    if !cli_args.get_output_file().exists() {
+        return Ok(0);
+    }
+    CHECKS
+        .iter()
+        .map(|check| (check.find)(cli_args).map(|problems| problems.len()))
+        .sum()
+}
+
+/// Repairs the problems which can be fixed automatically, and describes the rest.
+pub fn fix_problems(cli_args: &Cli) -> Result<Vec<String>> {
+    let mut remaining = Vec::new();
+    for check in CHECKS {
+        let mut problems = (check.find)(cli_args)?;
+        if let (Some(repair), false) = (check.fix, problems.is_empty()) {
+            // a repair can fail because of a problem found by a later check,
+            // e.g. the header can't be migrated while a row is malformed, so
+            // the failure is reported along with the problems
+            match repair(cli_args) {
+                Ok(()) => problems = (check.find)(cli_args)?,
+                Err(e) => problems.push(format!("Failed to fix '{}': {e}", check.name)),
+            }
+        }
+        remaining.extend(problems);
+    }
+    Ok(remaining)
+}
+
+fn find_header_problems(cli_args: &Cli) -> Result<Vec<String>> {
+    Ok(match header_state(cli_args)? {
+        HeaderState::Blank | HeaderState::Current => Vec::new(),
+        HeaderState::Outdated => {
+            vec!["The header was written by an older version (fixable)".into()]
+        }
+        HeaderState::Missing => {
+            vec!["The first row is an entry instead of the header (fixable)".into()]
+        }
+    })
+}
+
+fn find_missing_newline(cli_args: &Cli) -> Result<Vec<String>> {
+    Ok(if ends_with_newline(cli_args)? {
+        Vec::new()
+    } else {
+        vec!["The last row would be joined with the next entry (fixable)".into()]
+    })
+}
+
+fn add_newline(cli_args: &Cli) -> Result<()> {
+    let data_file = cli_args.get_output_file();
+    File::options()
+        .append(true)
+        .open(&data_file)
+        .and_then(|mut file| file.write_all(b"\n"))
+        .wrap_err(ERR_WRITE_CSV(&data_file))
+        .suggestion(SUGG_PROPER_PERMS(&data_file))
+}
+
+fn find_malformed_rows(cli_args: &Cli) -> Result<Vec<String>> {
+    Ok(malformed_rows(cli_args)?
+        .into_iter()
+        .map(|err| err.to_string())
+        .collect())
+}
+
+fn find_unordered_entries(cli_args: &Cli) -> Result<Vec<String>> {
+    // the rows must be parseable to be compared, which is checked above
+    if !malformed_rows(cli_args)?.is_empty() {
+        return Ok(Vec::new());
+    }
+    let entries = read_entries(cli_args)?;
+    Ok(entries
+        .windows(2)
+        .filter_map(|pair| {
+            let (previous, entry) = (&pair[0], &pair[1]);
+            let timestamp = entry.timestamp.format(SLIM_DATETIME);
+            let devices = devices(previous, entry);
+            match entry.timestamp.cmp(&previous.timestamp) {
+                Ordering::Equal => Some(format!(
+                    "The clock {} at {timestamp} is at the same time as the entry before it{devices} (fixable)",
+                    entry.entry_type
+                )),
+                Ordering::Less => Some(format!(
+                    "The clock {} at {timestamp} is earlier than the entry before it{devices}",
+                    entry.entry_type
+                )),
+                Ordering::Greater => None,
+            }
+        })
+        .collect())
+}
+
+/// Describes which devices two entries were made on, if that was recorded,
+/// to help tell which copy of a synced data file they came from.
+fn devices(previous: &Entry, entry: &Entry) -> String {
+    match (device_of(&previous.tags), device_of(&entry.tags)) {
+        (Some(previous), Some(device)) if previous != device => {
+            format!(" (made on {device}, the one before on {previous})")
+        }
+        (_, Some(device)) => format!(" (made on {device})"),
+        _ => String::new(),
+    }
+}
+
+/// Moves entries which are at the same time as the entry before them 1ms later,
+/// as long as that doesn't move them past the entry after them.
+fn separate_ties(cli_args: &Cli) -> Result<()> {
+    let revision = Revision::current(cli_args)?;
+    let mut entries = read_entries(cli_args)?;
+    for i in 1..entries.len() {
+        if entries[i].timestamp != entries[i - 1].timestamp {
+            continue;
+        }
+        let moved = entries[i].timestamp + chrono::Duration::milliseconds(1);
+        if entries
+            .get(i + 1)
+            .map(|next| moved < next.timestamp)
+            .unwrap_or(true)
+        {
+            entries[i].timestamp = moved;
+        }
+    }
+    write_entries(cli_args, &entries, revision.as_ref())
+}
@@ -0,0 +1,187 @@
+// Copyright (C) 2023 Campbell M. Cole
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+// prints the changes to the data file and the amendments as JSON lines, so
+// other programs can react to clocking in and out. with --follow, both files
+// are checked for changes until punchcard is stopped
+
+use std::{
+    io::{self, Write},
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use crate::{
+    amend::{read_amendments, Amendment},
+    csv::{read_entries, Revision},
+    prelude::*,
+};
+
+/// How often the files are checked for changes with --follow
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Args)]
+pub struct EventsArgs {
+    /// Keep running and print the changes as they happen
+    #[clap(short, long, default_value_t = false)]
+    pub follow: bool,
+    /// Start with the latest N entries. Defaults to all of them, or none with --follow
+    #[clap(short = 'n', long, value_name = "N")]
+    pub last: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+enum Event<'a> {
+    /// An entry was added to the data file
+    Added(EntryEvent<'a>),
+    /// An entry was removed from the data file, e.g. by editing it
+    Removed(EntryEvent<'a>),
+    /// A change to the hours of a supervised kiosk user is waiting for approval
+    AmendmentProposed {
+        id: u32,
+        command: &'a str,
+    },
+    AmendmentApproved {
+        id: u32,
+    },
+    AmendmentRejected {
+        id: u32,
+    },
+}
+
+#[derive(Debug, Serialize)]
+struct EntryEvent<'a> {
+    #[serde(rename = "type")]
+    entry_type: String,
+    timestamp: String,
+    project: Option<&'a str>,
+    tags: &'a [String],
+    source: String,
+    note: Option<&'a str>,
+}
+
+impl<'a> From<&'a Entry> for EntryEvent<'a> {
+    fn from(entry: &'a Entry) -> Self {
+        Self {
+            entry_type: entry.entry_type.to_string(),
+            timestamp: entry.timestamp.to_rfc3339(),
+            project: entry.project.as_deref(),
+            tags: &entry.tags.0,
+            note: entry.note.as_deref(),
+            source: entry.source.to_string(),
+        }
+    }
+}
+
+#[instrument]
+pub fn print_events(cli_args: &Cli, EventsArgs { follow, last }: &EventsArgs) -> Result<()> {
+    let mut revision = Revision::current(cli_args)?;
+    let mut entries = read_all_entries(cli_args, revision)?;
+    let mut amendments_modified = modified(&cli_args.get_amendments_file());
+    let mut amendments = read_amendments(cli_args)?;
+
+    let last = last.unwrap_or(if *follow { 0 } else { entries.len() });
+    for entry in &entries[entries.len().saturating_sub(last)..] {
+        emit(&Event::Added(entry.into()))?;
+    }
+
+    if !follow {
+        return Ok(());
+    }
+
+    loop {
+        thread::sleep(POLL_INTERVAL);
+
+        let current = Revision::current(cli_args)?;
+        if current != revision {
+            revision = current;
+            let new = read_all_entries(cli_args, revision)?;
+            for entry in difference(&entries, &new) {
+                emit(&Event::Removed(entry.into()))?;
+            }
+            for entry in difference(&new, &entries) {
+                emit(&Event::Added(entry.into()))?;
+            }
+            entries = new;
+        }
+
+        let current = modified(&cli_args.get_amendments_file());
+        if current != amendments_modified {
+            amendments_modified = current;
+            let new = read_amendments(cli_args)?;
+            for event in amendment_events(&amendments, &new) {
+                emit(&event)?;
+            }
+            amendments = new;
+        }
+    }
+}
+
+fn read_all_entries(cli_args: &Cli, revision: Option<Revision>) -> Result<Vec<Entry>> {
+    match revision {
+        Some(_) => read_entries(cli_args),
+        None => Ok(Vec::new()),
+    }
+}
+
+fn modified(path: &std::path::Path) -> Option<SystemTime> {
+    path.metadata().and_then(|m| m.modified()).ok()
+}
+
+/// The entries of `a` which aren't in `b`, counting duplicates.
+fn difference<'a>(a: &'a [Entry], b: &[Entry]) -> Vec<&'a Entry> {
+    let mut remaining = b.iter().collect::<Vec<_>>();
+    a.iter()
+        .filter(|entry| match remaining.iter().position(|e| e == entry) {
+            Some(idx) => {
+                remaining.swap_remove(idx);
+                false
+            }
+            None => true,
+        })
+        .collect()
+}
+
+fn amendment_events<'a>(old: &[Amendment], new: &'a [Amendment]) -> Vec<Event<'a>> {
+    let mut events = Vec::new();
+    for amendment in new {
+        let previous = old.iter().find(|a| a.id == amendment.id);
+        if previous.is_none() {
+            events.push(Event::AmendmentProposed {
+                id: amendment.id,
+                command: &amendment.command,
+            });
+        }
+        let was_pending = previous.map_or(true, Amendment::is_pending);
+        match &amendment.resolution {
+            Some(resolution) if was_pending && resolution.approved => {
+                events.push(Event::AmendmentApproved { id: amendment.id })
+            }
+            Some(_) if was_pending => events.push(Event::AmendmentRejected { id: amendment.id }),
+            _ => {}
+        }
+    }
+    events
+}
+
+fn emit(event: &Event) -> Result<()> {
+    let line = serde_json::to_string(event).wrap_err("Failed to serialize event")?;
+    // flush every line, since the reader is usually waiting for it
+    let mut stdout = io::stdout().lock();
+    writeln!(stdout, "{line}")
+        .and_then(|_| stdout.flush())
+        .wrap_err("Failed to write to stdout")
+}
@@ -0,0 +1,536 @@
+// Copyright (C) 2023 Campbell M. Cole
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+// exports for the import formats of payroll systems, where each preset is one
+// row per employee and day with the regular and overtime hours worked, and
+// for other time trackers
+
+use std::{
+    collections::BTreeMap,
+    io::{self, Write},
+    path::PathBuf,
+};
+
+use chrono::{Datelike, Duration, Months, NaiveDate};
+use clap::ValueEnum;
+use csv::WriterBuilder;
+
+use crate::{
+    prelude::*,
+    receipt::{record_receipt, HashingWriter},
+    shift::{read_shifts, start_of_date, total_between, Shift},
+};
+
+use super::{
+    import::org::{format_clock, NO_PROJECT_HEADING},
+    kiosk::{read_kiosk, user_cli},
+};
+
+/// The mapping key of the hours in the data folder itself, as opposed to a kiosk user.
+const OWN_HOURS_ID: &str = "me";
+/// The timedot account of the hours without a project.
+const NO_PROJECT_ACCOUNT: &str = "unassigned";
+
+#[derive(Debug, Args)]
+pub struct ExportArgs {
+    #[clap(subcommand)]
+    pub operation: ExportOperation,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ExportOperation {
+    /// Export the hours worked in the layout a payroll system imports
+    ///
+    /// Every kiosk user with hours in the period is included, as well as the
+    /// hours of the data folder itself. The IDs of the employees in the payroll
+    /// system are read from a mapping file, by default `payroll.json` in the
+    /// data folder, e.g. `{ "me": "1001", "42": "1042" }`.
+    Payroll(PayrollArgs),
+    /// Export the shifts as org-mode clock lines
+    ///
+    /// Every day is a heading, with a heading for each project below it
+    /// whose logbook has the shifts. 'punchcard import --format org' reads
+    /// the file back in.
+    Org(OrgArgs),
+    /// Export the hours as an hledger timedot file
+    ///
+    /// Every day lists the hours worked on each project, which become the
+    /// accounts. 'hledger -f hours.timedot balance' then sums them up.
+    Timedot(TimedotArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct OrgArgs {
+    /// Only export the shifts starting on or after this day, e.g. `2024-03-01`
+    #[clap(long)]
+    pub from: Option<NaiveDate>,
+    /// Only export the shifts starting on or before this day
+    #[clap(long)]
+    pub to: Option<NaiveDate>,
+    /// Save the export to a file, or '-' for stdout
+    #[clap(short = 'o', long, default_value = "-")]
+    pub output_file: Destination,
+    /// Print the SHA-256 of the export and keep it as a receipt,
+    /// so 'punchcard verify' can later show the file wasn't changed
+    #[clap(long, default_value_t = false)]
+    pub receipt: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct PayrollArgs {
+    /// The payroll system to export for
+    #[clap(long, value_enum)]
+    pub preset: PayrollPreset,
+    /// The month to export
+    ///
+    /// Accepts a month name (e.g. `January`) or a number (e.g. `1`)
+    /// or `current`, `previous`, or `next`
+    #[clap(short, long, default_value_t = Month::Previous, conflicts_with_all = ["from", "to"])]
+    pub month: Month,
+    /// The first day to export, e.g. `2024-03-01`, for pay periods which aren't months
+    #[clap(long, requires = "to")]
+    pub from: Option<NaiveDate>,
+    /// The last day to export
+    #[clap(long, requires = "from")]
+    pub to: Option<NaiveDate>,
+    /// Hours worked in a week (Monday to Sunday) beyond this are overtime
+    #[clap(long, default_value = "40h")]
+    pub overtime_after: BiDuration,
+    /// The file mapping kiosk user IDs (and `me`) to employee IDs
+    #[clap(long)]
+    pub mapping: Option<PathBuf>,
+    /// Save the export to a file, or '-' for stdout
+    #[clap(short = 'o', long, default_value = "-")]
+    pub output_file: Destination,
+    /// Print the SHA-256 of the export and keep it as a receipt,
+    /// so 'punchcard verify' can later show the file wasn't changed
+    #[clap(long, default_value_t = false)]
+    pub receipt: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct TimedotArgs {
+    /// The first day to export, e.g. `2024-03-01`. Defaults to the first shift
+    #[clap(long)]
+    pub from: Option<NaiveDate>,
+    /// The last day to export. Defaults to the last shift
+    #[clap(long)]
+    pub to: Option<NaiveDate>,
+    /// Put the project accounts below this account, e.g. `time`
+    #[clap(long)]
+    pub prefix: Option<String>,
+    /// Save the export to a file, or '-' for stdout
+    #[clap(short = 'o', long, default_value = "-")]
+    pub output_file: Destination,
+    /// Print the SHA-256 of the export and keep it as a receipt,
+    /// so 'punchcard verify' can later show the file wasn't changed
+    #[clap(long, default_value_t = false)]
+    pub receipt: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum PayrollPreset {
+    /// ADP: `File #`, `Date`, `Reg Hours`, `O/T Hours`
+    Adp,
+    /// Gusto: `Employee ID`, `Date`, `Regular Hours`, `Overtime Hours`
+    Gusto,
+    /// QuickBooks: one row per payroll item, `Employee`, `Date`, `Payroll Item`, `Hours`
+    Quickbooks,
+}
+
+impl PayrollPreset {
+    /// The header of the export. The travel column is only added if there was
+    /// travel time, so imports set up before it existed keep working.
+    fn header(&self, travel: bool) -> Vec<&'static str> {
+        let mut header = match self {
+            Self::Adp => vec!["File #", "Date", "Reg Hours", "O/T Hours"],
+            Self::Gusto => vec!["Employee ID", "Date", "Regular Hours", "Overtime Hours"],
+            Self::Quickbooks => return vec!["Employee", "Date", "Payroll Item", "Hours"],
+        };
+        if travel {
+            header.push("Travel Hours");
+        }
+        header
+    }
+
+    fn date_format(&self) -> &'static str {
+        match self {
+            Self::Adp | Self::Quickbooks => "%m/%d/%Y",
+            Self::Gusto => "%Y-%m-%d",
+        }
+    }
+
+    fn rows(&self, employee: &str, day: &PayrollDay, travel: bool) -> Vec<Vec<String>> {
+        let date = day.date.format(self.date_format()).to_string();
+        let regular = format_hours(day.regular);
+        let overtime = format_hours(day.overtime);
+        match self {
+            Self::Adp | Self::Gusto => {
+                let mut row = vec![employee.into(), date, regular, overtime];
+                if travel {
+                    row.push(format_hours(day.travel));
+                }
+                vec![row]
+            }
+            Self::Quickbooks => {
+                let mut rows = vec![vec![
+                    employee.into(),
+                    date.clone(),
+                    "Regular Pay".into(),
+                    regular,
+                ]];
+                if day.overtime > Duration::zero() {
+                    rows.push(vec![
+                        employee.into(),
+                        date.clone(),
+                        "Overtime Pay".into(),
+                        overtime,
+                    ]);
+                }
+                // a payroll item of its own, so it can have its own rate
+                if day.travel > Duration::zero() {
+                    rows.push(vec![
+                        employee.into(),
+                        date,
+                        "Travel Pay".into(),
+                        format_hours(day.travel),
+                    ]);
+                }
+                rows
+            }
+        }
+    }
+}
+
+/// The hours worked on one day, split at the weekly overtime threshold.
+///
+/// Travel time is taken out of the regular hours. Travel beyond the threshold
+/// stays overtime, since that's usually paid at the higher rate either way.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PayrollDay {
+    pub date: NaiveDate,
+    pub regular: Duration,
+    pub overtime: Duration,
+    pub travel: Duration,
+}
+
+fn format_hours(duration: Duration) -> String {
+    format!("{:.2}", duration.num_seconds() as f64 / 3600.0)
+}
+
+#[instrument]
+pub fn export(cli_args: &Cli, ExportArgs { operation }: &ExportArgs) -> Result<()> {
+    match operation {
+        ExportOperation::Payroll(args) => export_payroll(cli_args, args),
+        ExportOperation::Org(args) => export_org(cli_args, args),
+        ExportOperation::Timedot(args) => export_timedot(cli_args, args),
+    }
+}
+
+fn export_payroll(cli_args: &Cli, args: &PayrollArgs) -> Result<()> {
+    if cli_args.user.is_some() {
+        return Err(eyre!("The payroll export includes every kiosk user"))
+            .suggestion("Run the command without '--user'");
+    }
+
+    let (from, to) = match (args.from, args.to) {
+        (Some(from), Some(to)) if from <= to => (from, to),
+        (Some(_), Some(_)) => return Err(eyre!("'--from' must not be after '--to'")),
+        _ => {
+            let Some(start) = args.month.as_date() else {
+                return Err(eyre!("The payroll export needs a month")).suggestion(
+                    "Pass a month with '--month', or a period with '--from' and '--to'",
+                );
+            };
+            let start = start.date_naive();
+            let end = start
+                .checked_add_months(Months::new(1))
+                .expect("the next month of a valid date always exists");
+            (start, end.pred_opt().unwrap())
+        }
+    };
+
+    let mapping_file = args
+        .mapping
+        .clone()
+        .unwrap_or_else(|| cli_args.get_payroll_file());
+    let mapping: BTreeMap<String, String> = read_json_file(&mapping_file)?;
+
+    let mut workers = vec![(OWN_HOURS_ID.to_string(), read_shifts(cli_args)?)];
+    for id in read_kiosk(cli_args)?.users.into_keys() {
+        let shifts = read_shifts(&user_cli(cli_args, &id))?;
+        workers.push((id, shifts));
+    }
+
+    let now = Local::now();
+    let mut days = Vec::new();
+    let mut unmapped = Vec::new();
+    for (id, shifts) in &workers {
+        let worked = payroll_days(shifts, from, to, *args.overtime_after, now);
+        if worked.is_empty() {
+            continue;
+        }
+        let Some(employee) = mapping.get(id) else {
+            unmapped.push(id.as_str());
+            continue;
+        };
+        days.extend(worked.into_iter().map(|day| (employee, day)));
+    }
+    let travel = days.iter().any(|(_, day)| day.travel > Duration::zero());
+    let rows = days
+        .iter()
+        .flat_map(|(employee, day)| args.preset.rows(employee, day, travel));
+
+    if !unmapped.is_empty() {
+        return Err(eyre!(
+            "There is no employee ID for {}",
+            unmapped
+                .iter()
+                .map(|id| format!("'{id}'"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ))
+        .suggestion(format!(
+            "Add them to {}, e.g. {{ \"{}\": \"<employee id>\" }}",
+            mapping_file.display(),
+            unmapped[0]
+        ));
+    }
+
+    let path = match &args.output_file {
+        Destination::Stdout => PathBuf::from("stdout"),
+        Destination::File(path) => path.clone(),
+    };
+    let writer = args
+        .output_file
+        .to_writer()
+        .wrap_err_with(|| ERR_OPEN_CSV(&path))
+        .with_suggestion(|| SUGG_PROPER_PERMS(&path))?;
+    let mut hashing = HashingWriter::new(writer);
+    {
+        let mut writer = WriterBuilder::default().from_writer(&mut hashing);
+        writer
+            .write_record(args.preset.header(travel))
+            .wrap_err_with(|| ERR_WRITE_CSV(&path))?;
+        for row in rows {
+            writer
+                .write_record(row)
+                .wrap_err_with(|| ERR_WRITE_CSV(&path))?;
+        }
+        writer.flush().wrap_err_with(|| ERR_WRITE_CSV(&path))?;
+    }
+
+    if args.receipt {
+        record_receipt(cli_args, &args.output_file, hashing.hex_digest())?;
+    }
+    #[cfg(feature = "signing")]
+    crate::sign::sign_export(cli_args, &args.output_file)?;
+
+    Ok(())
+}
+
+/// The shifts of each day, by project in the order they were first worked on that day.
+type OrgDays = BTreeMap<NaiveDate, Vec<(Option<String>, Vec<Shift>)>>;
+
+fn export_org(cli_args: &Cli, args: &OrgArgs) -> Result<()> {
+    let mut days = OrgDays::new();
+    for shift in read_shifts(cli_args)? {
+        let date = shift.start.timestamp.date_naive();
+        if args.from.is_some_and(|from| date < from) || args.to.is_some_and(|to| date > to) {
+            continue;
+        }
+        let projects = days.entry(date).or_default();
+        match projects.iter_mut().find(|(p, _)| *p == shift.start.project) {
+            Some((_, shifts)) => shifts.push(shift),
+            None => projects.push((shift.start.project.clone(), vec![shift])),
+        }
+    }
+
+    save_export(cli_args, &args.output_file, args.receipt, |writer| {
+        write_org(writer, &days)
+    })
+}
+
+/// Writes a text export, then keeps a receipt if asked to and signs it if configured.
+fn save_export(
+    cli_args: &Cli,
+    output_file: &Destination,
+    receipt: bool,
+    write: impl FnOnce(&mut dyn Write) -> io::Result<()>,
+) -> Result<()> {
+    let path = match output_file {
+        Destination::Stdout => PathBuf::from("stdout"),
+        Destination::File(path) => path.clone(),
+    };
+    let writer = output_file
+        .to_writer()
+        .wrap_err_with(|| format!("Failed to open {}", path.display()))
+        .with_suggestion(|| SUGG_PROPER_PERMS(&path))?;
+    let mut hashing = HashingWriter::new(writer);
+    write(&mut hashing)
+        .and_then(|_| hashing.flush())
+        .wrap_err_with(|| format!("Failed to write {}", path.display()))
+        .with_suggestion(|| SUGG_PROPER_PERMS(&path))?;
+
+    if receipt {
+        record_receipt(cli_args, output_file, hashing.hex_digest())?;
+    }
+    #[cfg(feature = "signing")]
+    crate::sign::sign_export(cli_args, output_file)?;
+
+    Ok(())
+}
+
+fn write_org(writer: &mut dyn Write, days: &OrgDays) -> io::Result<()> {
+    for (date, projects) in days {
+        writeln!(writer, "* [{}]", date.format("%Y-%m-%d %a"))?;
+        for (project, shifts) in projects {
+            writeln!(
+                writer,
+                "** {}",
+                project.as_deref().unwrap_or(NO_PROJECT_HEADING)
+            )?;
+            writeln!(writer, ":LOGBOOK:")?;
+            // org-mode lists the latest clock first
+            for shift in shifts.iter().rev() {
+                let end = shift.end.as_ref().map(|e| e.timestamp);
+                writeln!(writer, "{}", format_clock(shift.start.timestamp, end))?;
+            }
+            writeln!(writer, ":END:")?;
+        }
+    }
+    Ok(())
+}
+
+fn export_timedot(cli_args: &Cli, args: &TimedotArgs) -> Result<()> {
+    let now = Local::now();
+    let shifts = read_shifts(cli_args)?;
+    let mut projects: BTreeMap<Option<String>, Vec<Shift>> = BTreeMap::new();
+    for shift in &shifts {
+        projects
+            .entry(shift.start.project.clone())
+            .or_default()
+            .push(shift.clone());
+    }
+
+    let first = shifts.first().map(|s| s.start.timestamp.date_naive());
+    let last = shifts.iter().map(|s| s.end_or(now).date_naive()).max();
+    let (Some(from), Some(to)) = (args.from.or(first), args.to.or(last)) else {
+        // there are no shifts, so there's nothing to export
+        return save_export(cli_args, &args.output_file, args.receipt, |_| Ok(()));
+    };
+
+    let account = |project: &Option<String>| {
+        // two spaces separate the account from the hours
+        let name = project
+            .as_deref()
+            .map(|p| p.split_whitespace().collect::<Vec<_>>().join(" "))
+            .unwrap_or_else(|| NO_PROJECT_ACCOUNT.to_string());
+        match &args.prefix {
+            Some(prefix) => format!("{}:{name}", prefix.trim_end_matches(':')),
+            None => name,
+        }
+    };
+
+    let mut days = Vec::new();
+    let mut date = from;
+    while date <= to {
+        let next = date.succ_opt().unwrap_or(date);
+        let (Some(start), Some(end)) = (start_of_date(date), start_of_date(next)) else {
+            date = next;
+            continue;
+        };
+        let hours = projects
+            .iter()
+            .map(|(project, shifts)| (account(project), total_between(shifts, start, end, now)))
+            .filter(|(_, worked)| *worked > Duration::zero())
+            .collect::<Vec<_>>();
+        if !hours.is_empty() {
+            days.push((date, hours));
+        }
+        date = next;
+    }
+
+    save_export(cli_args, &args.output_file, args.receipt, |writer| {
+        let width = days
+            .iter()
+            .flat_map(|(_, hours)| hours.iter().map(|(account, _)| account.len()))
+            .max()
+            .unwrap_or_default();
+        for (idx, (date, hours)) in days.iter().enumerate() {
+            if idx > 0 {
+                writeln!(writer)?;
+            }
+            writeln!(writer, "{}", date.format("%Y-%m-%d"))?;
+            for (account, worked) in hours {
+                writeln!(writer, "{account:width$}  {}", format_hours(*worked))?;
+            }
+        }
+        Ok(())
+    })
+}
+
+/// The hours worked on each day from `from` to `to` (inclusive), leaving out
+/// the days without any.
+///
+/// Hours beyond `overtime_after` in a week are overtime, counted from the
+/// start of the week even if it is before `from`.
+pub fn payroll_days(
+    shifts: &[Shift],
+    from: NaiveDate,
+    to: NaiveDate,
+    overtime_after: Duration,
+    now: DateTime<Local>,
+) -> Vec<PayrollDay> {
+    let travel = shifts
+        .iter()
+        .filter(|s| s.is_travel())
+        .cloned()
+        .collect::<Vec<_>>();
+    let mut days = Vec::new();
+    let mut date = from - Duration::days(from.weekday().num_days_from_monday() as i64);
+    let mut week_total = Duration::zero();
+
+    while date <= to {
+        if date.weekday() == chrono::Weekday::Mon {
+            week_total = Duration::zero();
+        }
+        let next = date.succ_opt().unwrap_or(date);
+        let (worked, travelled) = match (start_of_date(date), start_of_date(next)) {
+            (Some(start), Some(end)) => (
+                total_between(shifts, start, end, now),
+                total_between(&travel, start, end, now),
+            ),
+            _ => (Duration::zero(), Duration::zero()),
+        };
+
+        let regular_left = (overtime_after - week_total).max(Duration::zero());
+        let regular = worked.min(regular_left);
+        let travelled = travelled.min(regular);
+        week_total = week_total + worked;
+
+        if date >= from && worked > Duration::zero() {
+            days.push(PayrollDay {
+                date,
+                regular: regular - travelled,
+                overtime: worked - regular,
+                travel: travelled,
+            });
+        }
+        date = next;
+    }
+
+    days
+}
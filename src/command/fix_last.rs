@@ -0,0 +1,141 @@
+// Copyright (C) 2023 Campbell M. Cole
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+// a shortcut for the most common correction, moving the latest entry a little
+// because the clock-in or out was forgotten until later. anything else needs
+// 'punchcard open --editor'
+
+use std::io::{self, IsTerminal, Write};
+
+use crate::{
+    csv::{read_entries, write_entries, Revision},
+    prelude::*,
+};
+
+use super::clock::format_metadata;
+
+#[derive(Debug, Args)]
+pub struct FixLastArgs {
+    /// How to change the time of the last entry
+    ///
+    /// Either '+15m' or '-1h' to move it later or earlier, a time such as
+    /// '14:30' to move it to that time on the same day, or a timestamp such
+    /// as '2024-03-01T09:00'. Asked for if not given.
+    #[clap(allow_hyphen_values = true)]
+    pub correction: Option<Correction>,
+}
+
+#[instrument]
+pub fn fix_last(cli_args: &Cli, FixLastArgs { correction }: &FixLastArgs) -> Result<()> {
+    use owo_colors::OwoColorize;
+
+    let revision = Revision::current(cli_args)?;
+    let mut entries = if cli_args.get_output_file().exists() {
+        read_entries(cli_args)?
+    } else {
+        Vec::new()
+    };
+    let Some(last) = entries.last() else {
+        return Err(ErrorCode::NotFound.report("There are no entries to fix"));
+    };
+
+    println!(
+        "{} {} {}{}",
+        "Last entry:".bright_black(),
+        last.entry_type.colored(cli_args.accessible).bold(),
+        last.timestamp.format(SLIM_DATETIME).magenta(),
+        format_metadata(last),
+    );
+
+    let correction = match correction {
+        Some(correction) => correction.clone(),
+        None => match prompt_for_correction()? {
+            Some(correction) => correction,
+            None => {
+                println!("{}", "The entry was left as it is".bright_black());
+                return Ok(());
+            }
+        },
+    };
+
+    let old = last.timestamp;
+    let Some(new) = correction.apply(old) else {
+        return Err(eyre!(
+            "The corrected time doesn't exist in the local timezone"
+        ))
+        .suggestion("Give the exact time with a timestamp, e.g. '2024-03-01T09:00'");
+    };
+
+    // the entries before it aren't touched, so only the one before the last
+    // entry can be out of order afterwards
+    if let Some(previous) = entries.iter().rev().nth(1) {
+        if new <= previous.timestamp {
+            return Err(ErrorCode::Continuity.report(format!(
+                "The last entry must stay after the clock-{} before it.\nTime given: {}\nPrevious entry: {}",
+                previous.entry_type,
+                new.format(SLIM_DATETIME),
+                previous.timestamp.format(SLIM_DATETIME),
+            )))
+            .suggestion("Use 'punchcard open --editor' to change the earlier entries too");
+        }
+    }
+
+    let last = entries.last_mut().expect("checked above");
+    last.timestamp = new;
+    let entry_type = last.entry_type;
+    write_entries(cli_args, &entries, revision.as_ref())?;
+
+    // a supervised kiosk user's change only happens once it's approved
+    let moved = match cli_args.supervised {
+        true => "Waiting for approval to move".yellow().to_string(),
+        false => "Moved".green().to_string(),
+    };
+    println!(
+        "{} {} {} {} {}",
+        moved,
+        entry_type.colored(cli_args.accessible).bold(),
+        old.format(SLIM_DATETIME).bright_black(),
+        "→".bright_black(),
+        new.format(SLIM_DATETIME).magenta(),
+    );
+
+    Ok(())
+}
+
+/// Asks how to change the entry, or returns `None` if the answer was empty.
+fn prompt_for_correction() -> Result<Option<Correction>> {
+    use owo_colors::OwoColorize;
+
+    if !io::stdin().is_terminal() {
+        return Err(eyre!("No correction was given"))
+            .suggestion("Give it as an argument, e.g. 'punchcard fix-last -15m'");
+    }
+
+    print!(
+        "{} {} ",
+        "How should it change?".yellow(),
+        "(e.g. '+15m', '-1h', or '14:30', enter to keep it)".bright_black()
+    );
+    io::stdout().flush().wrap_err("Failed to write to stdout")?;
+    let mut answer = String::new();
+    io::stdin()
+        .read_line(&mut answer)
+        .wrap_err("Failed to read the correction")?;
+
+    match answer.trim() {
+        "" => Ok(None),
+        answer => answer.parse().map(Some).wrap_err("Invalid correction"),
+    }
+}
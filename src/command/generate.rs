@@ -14,10 +14,11 @@
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
 use chrono::Duration;
+use indicatif::ProgressIterator;
 use rand::prelude::*;
 use std::io::{BufWriter, Write};
 
-use crate::prelude::*;
+use crate::{prelude::*, progress};
 
 #[derive(Debug, Args)]
 pub struct GenerateDataArgs {
@@ -54,7 +55,9 @@ pub fn generate_test_entries(
         .write_all(b"entry_type,timestamp\n")
         .wrap_err("Failed to write CSV header")?;
 
-    for x in 0..count.unwrap_or(10_000) {
+    let count = count.unwrap_or(10_000);
+    let bar = progress::rows(count as u64, "Generating entries");
+    for x in (0..count).progress_with(bar) {
         let entry_type = if x % 2 == 0 { "in" } else { "out" };
 
         let timestamp = if x == 0 {
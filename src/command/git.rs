@@ -0,0 +1,253 @@
+// Copyright (C) 2023 Campbell M. Cole
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+// clocking in from within a repository with the hook installed tags the entry
+// with the repository and branch, and the post-checkout hook starts a new
+// shift when the branch changes, so reports can group the hours by either tag
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use crate::prelude::*;
+
+use super::{
+    clock::add_entry_inner,
+    status::{get_clock_status_inner, ClockStatusType},
+};
+
+const REPO_TAG_PREFIX: &str = "repo:";
+const BRANCH_TAG_PREFIX: &str = "branch:";
+/// Identifies hooks which punchcard installed, so others are never overwritten
+const HOOK_MARKER: &str = "# installed by 'punchcard git hook install'";
+
+#[derive(Debug, Args)]
+pub struct GitArgs {
+    #[clap(subcommand)]
+    pub operation: GitOperation,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum GitOperation {
+    /// Manage the hook of the repository in the current directory
+    Hook {
+        #[clap(subcommand)]
+        operation: HookOperation,
+    },
+    /// Run by the post-checkout hook when a branch is checked out
+    #[command(hide = true)]
+    CheckoutHook,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum HookOperation {
+    /// Tag clock-ins with the repository and branch, and start a new shift
+    /// when switching branches while clocked in
+    Install,
+    /// Remove the hook again
+    Uninstall,
+}
+
+/// The repository in the current directory.
+#[derive(Debug)]
+struct Repo {
+    name: String,
+    /// `None` if no branch is checked out
+    branch: Option<String>,
+    hook_file: PathBuf,
+}
+
+impl Repo {
+    fn current() -> Option<Self> {
+        let root = PathBuf::from(git(&["rev-parse", "--show-toplevel"])?);
+        let hooks = PathBuf::from(git(&["rev-parse", "--git-path", "hooks"])?);
+        let branch = git(&["rev-parse", "--abbrev-ref", "HEAD"]).filter(|b| b != "HEAD");
+        Some(Self {
+            name: root.file_name()?.to_string_lossy().into_owned(),
+            branch,
+            // relative to the current directory, like every path git prints
+            hook_file: hooks.join("post-checkout"),
+        })
+    }
+
+    fn has_hook(&self) -> bool {
+        is_our_hook(&self.hook_file)
+    }
+
+    fn tags(&self) -> Vec<String> {
+        // tags are separated by semicolons in the data file
+        let clean = |s: &str| s.replace(';', "_");
+        let mut tags = vec![format!("{REPO_TAG_PREFIX}{}", clean(&self.name))];
+        if let Some(branch) = &self.branch {
+            tags.push(format!("{BRANCH_TAG_PREFIX}{}", clean(branch)));
+        }
+        tags
+    }
+}
+
+/// Runs git in the current directory, returning its trimmed output if it succeeded.
+fn git(args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn is_our_hook(path: &Path) -> bool {
+    fs::read_to_string(path).is_ok_and(|contents| contents.contains(HOOK_MARKER))
+}
+
+/// Replaces the repository and branch tags of a clock-in with those of the
+/// repository in the current directory, if its hook is installed.
+pub fn add_repo_tags(entry: &mut Entry) {
+    if entry.entry_type != EntryType::ClockIn {
+        return;
+    }
+    let Some(repo) = Repo::current().filter(Repo::has_hook) else {
+        return;
+    };
+    entry
+        .tags
+        .0
+        .retain(|t| !t.starts_with(REPO_TAG_PREFIX) && !t.starts_with(BRANCH_TAG_PREFIX));
+    entry.tags.0.extend(repo.tags());
+}
+
+#[instrument]
+pub fn manage_git(cli_args: &Cli, GitArgs { operation }: &GitArgs) -> Result<()> {
+    match operation {
+        GitOperation::Hook { operation } => {
+            let Some(repo) = Repo::current() else {
+                return Err(eyre!("The current directory isn't in a git repository"));
+            };
+            match operation {
+                HookOperation::Install => install_hook(cli_args, &repo),
+                HookOperation::Uninstall => uninstall_hook(&repo),
+            }
+        }
+        GitOperation::CheckoutHook => switch_branch(cli_args),
+    }
+}
+
+fn install_hook(cli_args: &Cli, repo: &Repo) -> Result<()> {
+    use owo_colors::OwoColorize;
+
+    let path = &repo.hook_file;
+    if path.exists() && !repo.has_hook() {
+        return Err(eyre!("The repository already has a post-checkout hook")).suggestion(format!(
+            "Add 'punchcard git checkout-hook' to {} when its third argument is 1",
+            path.display()
+        ));
+    }
+
+    // the hook runs with the environment of git, so it gets the full paths
+    // of this binary and data folder
+    let quote = |p: &Path| format!("'{}'", p.display().to_string().replace('\'', "'\\''"));
+    let exe = std::env::current_exe().wrap_err("Failed to find the punchcard binary")?;
+    let data_folder =
+        fs::canonicalize(&cli_args.data_folder).unwrap_or_else(|_| cli_args.data_folder.clone());
+    let script = format!(
+        "#!/bin/sh\n\
+         {HOOK_MARKER}\n\
+         # the third argument is 1 when a branch was checked out, and 0 for files\n\
+         [ \"$3\" = 1 ] || exit 0\n\
+         {} -d {} git checkout-hook || true\n",
+        quote(&exe),
+        quote(&data_folder),
+    );
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .wrap_err("Failed to create the hooks folder")
+            .suggestion(SUGG_PROPER_PERMS(parent))?;
+    }
+    fs::write(path, script)
+        .wrap_err("Failed to write the hook")
+        .suggestion(SUGG_PROPER_PERMS(path))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o755))
+            .wrap_err("Failed to make the hook executable")
+            .suggestion(SUGG_PROPER_PERMS(path))?;
+    }
+
+    println!(
+        "Installed the hook in {}. Clock-ins in this repository are now tagged {}",
+        repo.name.bold().cyan(),
+        repo.tags()
+            .iter()
+            .map(|t| format!("#{t}"))
+            .collect::<Vec<_>>()
+            .join(" ")
+            .bright_black()
+    );
+    Ok(())
+}
+
+fn uninstall_hook(repo: &Repo) -> Result<()> {
+    if !repo.has_hook() {
+        return Err(ErrorCode::NotFound.report("punchcard's hook isn't installed"));
+    }
+    fs::remove_file(&repo.hook_file)
+        .wrap_err("Failed to remove the hook")
+        .suggestion(SUGG_PROPER_PERMS(&repo.hook_file))?;
+    println!("Removed the hook");
+    Ok(())
+}
+
+/// Starts a new shift on the checked out branch, if clocked in on another
+/// branch of the same repository.
+fn switch_branch(cli_args: &Cli) -> Result<()> {
+    let Some(repo) = Repo::current().filter(Repo::has_hook) else {
+        return Ok(());
+    };
+
+    let now = Local::now();
+    let status = get_clock_status_inner(cli_args, now)?;
+    if !matches!(
+        status.status_type,
+        ClockStatusType::Entry(EntryType::ClockIn)
+    ) {
+        return Ok(());
+    }
+    let Some(clock_in) = crate::csv::read_entries(cli_args)?
+        .into_iter()
+        .filter(|e| e.entry_type == EntryType::ClockIn && Some(e.timestamp) == status.since)
+        .last()
+    else {
+        return Ok(());
+    };
+
+    let tags = repo.tags();
+    let in_repo = clock_in.tags.0.contains(&tags[0]);
+    let same_branch = tags.iter().all(|t| clock_in.tags.0.contains(t));
+    if !in_repo || same_branch {
+        return Ok(());
+    }
+
+    let clock_out = Entry::new(EntryType::ClockOut, now, EntrySource::Cli);
+    add_entry_inner(cli_args, clock_out, &None, status)?;
+
+    let mut entry = Entry::new(EntryType::ClockIn, now, EntrySource::Cli);
+    entry.project = clock_in.project;
+    entry.tags = clock_in.tags;
+    let status = get_clock_status_inner(cli_args, now)?;
+    add_entry_inner(cli_args, entry, &None, status)?;
+    Ok(())
+}
@@ -0,0 +1,127 @@
+// Copyright (C) 2023 Campbell M. Cole
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+// imports the hours tracked with other tools. every format is parsed into
+// entries which are then added like a batch, so nothing is imported unless
+// all of them fit between the existing entries
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use clap::ValueEnum;
+
+use crate::prelude::*;
+
+use super::batch::add_entries;
+
+pub mod org;
+pub mod timewarrior;
+pub mod watson;
+
+#[derive(Debug, Args)]
+pub struct ImportArgs {
+    /// The format of the file
+    #[clap(long, value_enum)]
+    pub format: ImportFormat,
+    /// The file to import. For timewarrior, this can also be its data folder
+    pub file: PathBuf,
+    /// Use this project for every imported shift instead of the one in the file
+    #[clap(short, long)]
+    pub project: Option<String>,
+    /// Validate the file and print the entries without changing the data file
+    #[clap(long, default_value_t = false)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ImportFormat {
+    /// Emacs org-mode files. Every CLOCK line is a shift on the heading it is under
+    Org,
+    /// timewarrior data files. The first tag of each interval is the project
+    Timewarrior,
+    /// Watson's `frames` file
+    Watson,
+}
+
+impl ImportFormat {
+    /// The source of the imported entries, e.g. `import:org`.
+    fn source(&self) -> EntrySource {
+        let name = match self {
+            Self::Org => "org",
+            Self::Timewarrior => "timewarrior",
+            Self::Watson => "watson",
+        };
+        EntrySource::Other(format!("import:{name}"))
+    }
+}
+
+#[instrument]
+pub fn import(cli_args: &Cli, args: &ImportArgs) -> Result<()> {
+    use owo_colors::OwoColorize;
+
+    let mut entries = Vec::new();
+    for path in input_files(&args.file, args.format)? {
+        let contents = fs::read_to_string(&path)
+            .wrap_err_with(|| format!("Failed to read {}", path.display()))
+            .suggestion(SUGG_PROPER_PERMS(&path))?;
+        let parsed = match args.format {
+            ImportFormat::Org => org::parse(&contents),
+            ImportFormat::Timewarrior => timewarrior::parse(&contents),
+            ImportFormat::Watson => watson::parse(&contents),
+        };
+        entries.extend(parsed.wrap_err_with(|| format!("Failed to import {}", path.display()))?);
+    }
+    if entries.is_empty() {
+        println!("{}", "The file has no shifts, nothing was added.".yellow());
+        return Ok(());
+    }
+
+    for entry in &mut entries {
+        entry.source = args.format.source();
+        if let (EntryType::ClockIn, Some(project)) = (entry.entry_type, &args.project) {
+            entry.project = Some(project.clone());
+        }
+    }
+
+    add_entries(cli_args, entries, args.dry_run)
+}
+
+/// The file to import, or every month of a timewarrior data folder.
+fn input_files(path: &Path, format: ImportFormat) -> Result<Vec<PathBuf>> {
+    if !path.is_dir() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+    if format != ImportFormat::Timewarrior {
+        return Err(eyre!("{} is a folder", path.display()));
+    }
+
+    let mut files = fs::read_dir(path)
+        .and_then(|dir| {
+            dir.map(|e| e.map(|e| e.path()))
+                .collect::<io::Result<Vec<_>>>()
+        })
+        .wrap_err_with(|| format!("Failed to read {}", path.display()))
+        .suggestion(SUGG_PROPER_PERMS(path))?;
+    // the months are named like 2024-03.data, so they sort by date
+    files.retain(|f| f.extension().is_some_and(|ext| ext == "data"));
+    files.sort();
+    if files.is_empty() {
+        return Err(eyre!("There are no .data files in {}", path.display()))
+            .suggestion("Pass the 'data' folder of timewarrior, e.g. ~/.timewarrior/data");
+    }
+    Ok(files)
+}
@@ -0,0 +1,184 @@
+// Copyright (C) 2023 Campbell M. Cole
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+// org-mode keeps the time clocked into a heading as CLOCK lines in its
+// logbook, e.g. `CLOCK: [2024-03-01 Fri 09:00]--[2024-03-01 Fri 12:30] =>  3:30`.
+// the heading is the project, and its tags (including inherited ones) are the tags
+
+use chrono::{Duration, NaiveDate, NaiveTime};
+
+use crate::prelude::*;
+
+/// The heading of the shifts without a project in exported files.
+pub const NO_PROJECT_HEADING: &str = "No project";
+
+/// Keywords which org-mode shows in front of the title of a heading.
+const TODO_KEYWORDS: &[&str] = &[
+    "TODO",
+    "NEXT",
+    "STARTED",
+    "WAITING",
+    "HOLD",
+    "DONE",
+    "CANCELLED",
+    "CANCELED",
+];
+
+struct Heading {
+    level: usize,
+    title: String,
+    tags: Vec<String>,
+}
+
+/// Parses every CLOCK line into a clock-in, and a clock-out unless the clock is running.
+pub fn parse(contents: &str) -> Result<Vec<Entry>> {
+    let mut headings: Vec<Heading> = Vec::new();
+    let mut entries = Vec::new();
+    let mut errors = Vec::new();
+
+    for (idx, line) in contents.lines().enumerate() {
+        if let Some(heading) = parse_heading(line) {
+            headings.retain(|h| h.level < heading.level);
+            headings.push(heading);
+            continue;
+        }
+        let Some(clock) = line.trim().strip_prefix("CLOCK:") else {
+            continue;
+        };
+
+        let (start, end) = match parse_clock(clock) {
+            Ok(times) => times,
+            Err(e) => {
+                errors.push(format!("line {}: {e}", idx + 1));
+                continue;
+            }
+        };
+
+        let mut clock_in = Entry::new(EntryType::ClockIn, start, EntrySource::default());
+        if let Some(heading) = headings.last() {
+            clock_in.project = Some(heading.title.clone()).filter(|t| t != NO_PROJECT_HEADING);
+        }
+        clock_in.tags = Tags(headings.iter().flat_map(|h| h.tags.clone()).collect());
+        entries.push(clock_in);
+        if let Some(end) = end {
+            entries.push(Entry::new(EntryType::ClockOut, end, EntrySource::default()));
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(eyre!(
+            "The file contains invalid clock lines, nothing was added:\n{}",
+            errors.join("\n")
+        ));
+    }
+    Ok(entries)
+}
+
+/// Parses a heading like `** TODO [#A] Write the report  :work:urgent:`.
+fn parse_heading(line: &str) -> Option<Heading> {
+    let level = line.chars().take_while(|c| *c == '*').count();
+    if level == 0 {
+        return None;
+    }
+    let rest = line[level..].strip_prefix(' ')?;
+
+    let mut title = rest.trim();
+    let mut tags = Vec::new();
+    if let Some((before, last)) = title.rsplit_once(char::is_whitespace) {
+        if last.len() > 1 && last.starts_with(':') && last.ends_with(':') {
+            tags = last
+                .split(':')
+                .filter(|t| !t.is_empty())
+                .map(String::from)
+                .collect();
+            title = before.trim_end();
+        }
+    }
+
+    let mut words = title.splitn(2, ' ');
+    if let (Some(first), Some(rest)) = (words.next(), words.next()) {
+        if TODO_KEYWORDS.contains(&first) {
+            title = rest.trim_start();
+        }
+    }
+    if title.starts_with("[#") && title.get(3..4) == Some("]") {
+        title = title[4..].trim_start();
+    }
+
+    Some(Heading {
+        level,
+        title: title.to_string(),
+        tags,
+    })
+}
+
+/// Parses `[start]--[end] => H:MM`, or just `[start]` for a running clock.
+fn parse_clock(clock: &str) -> Result<(DateTime<Local>, Option<DateTime<Local>>)> {
+    let clock = clock.split("=>").next().unwrap_or_default().trim();
+    let (start, end) = match clock.split_once("--") {
+        Some((start, end)) => (start, Some(end)),
+        None => (clock, None),
+    };
+    let start = parse_timestamp(start)?;
+    let end = end.map(parse_timestamp).transpose()?;
+    if end.is_some_and(|end| end < start) {
+        return Err(eyre!("The clock ends before it starts"));
+    }
+    Ok((start, end))
+}
+
+/// Parses an org timestamp like `[2024-03-01 Fri 09:00]`.
+fn parse_timestamp(timestamp: &str) -> Result<DateTime<Local>> {
+    let timestamp = timestamp.trim();
+    let inner = timestamp
+        .strip_prefix('[')
+        .and_then(|t| t.strip_suffix(']'))
+        .or_else(|| timestamp.strip_prefix('<')?.strip_suffix('>'))
+        .ok_or_else(|| eyre!("Invalid timestamp '{timestamp}'"))?;
+
+    let mut words = inner.split_whitespace();
+    let date = words
+        .next()
+        .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok());
+    let time = words
+        .last()
+        .and_then(|t| NaiveTime::parse_from_str(t, "%H:%M").ok());
+    let (Some(date), Some(time)) = (date, time) else {
+        return Err(eyre!("Invalid timestamp '{timestamp}'"))
+            .suggestion("Use a format like [2024-03-01 Fri 09:00]");
+    };
+    Local
+        .from_local_datetime(&date.and_time(time))
+        .earliest()
+        .ok_or_else(|| eyre!("'{timestamp}' doesn't exist in the local timezone"))
+}
+
+/// Formats a shift as a CLOCK line, the way org-mode writes them.
+pub fn format_clock(start: DateTime<Local>, end: Option<DateTime<Local>>) -> String {
+    const FORMAT: &str = "%Y-%m-%d %a %H:%M";
+    let Some(end) = end else {
+        return format!("CLOCK: [{}]", start.format(FORMAT));
+    };
+    // org-mode only keeps the minutes, and so does the duration it shows
+    let minutes = |t: DateTime<Local>| t.timestamp() / 60;
+    let duration = Duration::minutes(minutes(end) - minutes(start));
+    format!(
+        "CLOCK: [{}]--[{}] => {:>2}:{:02}",
+        start.format(FORMAT),
+        end.format(FORMAT),
+        duration.num_hours(),
+        duration.num_minutes() % 60
+    )
+}
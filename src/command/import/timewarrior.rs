@@ -0,0 +1,115 @@
+// Copyright (C) 2023 Campbell M. Cole
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+// timewarrior keeps one file per month in its data folder, with one interval
+// per line, e.g. `inc 20240301T090000Z - 20240301T123000Z # acme meeting`.
+// it has no projects, so the first tag of an interval is its project
+
+use chrono::NaiveDateTime;
+
+use crate::prelude::*;
+
+/// Parses every interval into a clock-in, and a clock-out unless it is still open.
+pub fn parse(contents: &str) -> Result<Vec<Entry>> {
+    let mut entries = Vec::new();
+    let mut errors = Vec::new();
+
+    for (idx, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        match parse_interval(line) {
+            Ok(interval) => entries.extend(interval),
+            Err(e) => errors.push(format!("line {}: {e}", idx + 1)),
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(eyre!(
+            "The file contains invalid intervals, nothing was added:\n{}",
+            errors.join("\n")
+        ));
+    }
+    Ok(entries)
+}
+
+fn parse_interval(line: &str) -> Result<Vec<Entry>> {
+    let Some(line) = line.strip_prefix("inc ") else {
+        return Err(eyre!("Expected an interval starting with 'inc'"));
+    };
+    let (times, tags) = match line.split_once(" # ") {
+        Some((times, rest)) => (times, Some(rest)),
+        None => (line.trim_end_matches(" #"), None),
+    };
+
+    let (start, end) = match times.split_once(" - ") {
+        Some((start, end)) => (start, Some(end)),
+        None => (times, None),
+    };
+    let start = parse_timestamp(start)?;
+    let end = end.map(parse_timestamp).transpose()?;
+    if end.is_some_and(|end| end < start) {
+        return Err(eyre!("The interval ends before it starts"));
+    }
+
+    // the annotation follows the tags after another '#'
+    let mut tags = tags.map(split_tags).unwrap_or_default();
+    let project = (!tags.is_empty()).then(|| tags.remove(0));
+
+    let mut clock_in = Entry::new(EntryType::ClockIn, start, EntrySource::default());
+    clock_in.project = project;
+    clock_in.tags = Tags(tags);
+    let mut entries = vec![clock_in];
+    if let Some(end) = end {
+        entries.push(Entry::new(EntryType::ClockOut, end, EntrySource::default()));
+    }
+    Ok(entries)
+}
+
+/// Parses a timestamp like `20240301T090000Z`, which is always in UTC.
+fn parse_timestamp(timestamp: &str) -> Result<DateTime<Local>> {
+    let timestamp = timestamp.trim();
+    NaiveDateTime::parse_from_str(timestamp, "%Y%m%dT%H%M%SZ")
+        .map(|naive| Utc.from_utc_datetime(&naive).with_timezone(&Local))
+        .map_err(|_| eyre!("Invalid timestamp '{timestamp}'"))
+}
+
+/// Splits the tags of an interval, which are quoted if they contain spaces,
+/// stopping at the '#' in front of the annotation.
+fn split_tags(tags: &str) -> Vec<String> {
+    let mut result = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    let mut chars = tags.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if quoted => current.extend(chars.next()),
+            '"' => quoted = !quoted,
+            '#' if !quoted && current.is_empty() => break,
+            c if c.is_whitespace() && !quoted => {
+                if !current.is_empty() {
+                    result.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        result.push(current);
+    }
+    result
+}
@@ -0,0 +1,67 @@
+// Copyright (C) 2023 Campbell M. Cole
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+// watson keeps its history in the `frames` file of its config folder, a JSON
+// array with one array per frame: start, stop, project, id, tags, and the time
+// it was last edited, where the times are unix timestamps
+
+use serde::de::IgnoredAny;
+
+use crate::prelude::*;
+
+#[derive(Debug, Deserialize)]
+struct Frame(
+    i64,
+    i64,
+    String,
+    IgnoredAny,
+    #[serde(default)] Vec<String>,
+    #[serde(default)] Option<IgnoredAny>,
+);
+
+/// Parses every frame into a clock-in and a clock-out.
+pub fn parse(contents: &str) -> Result<Vec<Entry>> {
+    let frames: Vec<Frame> = serde_json::from_str(contents)
+        .wrap_err("The file isn't a list of Watson frames")
+        .suggestion("Import the 'frames' file in the config folder of Watson")?;
+
+    let mut entries = Vec::new();
+    for Frame(start, stop, project, _, tags, _) in frames {
+        let timestamp = |secs: i64| {
+            Local
+                .timestamp_opt(secs, 0)
+                .single()
+                .ok_or_else(|| eyre!("Invalid timestamp {secs}"))
+        };
+        let (start, stop) = (timestamp(start)?, timestamp(stop)?);
+        if stop < start {
+            return Err(eyre!(
+                "The frame starting at {} ends before it starts",
+                start.format(SLIM_DATETIME)
+            ));
+        }
+
+        let mut clock_in = Entry::new(EntryType::ClockIn, start, EntrySource::default());
+        clock_in.project = Some(project).filter(|p| !p.is_empty());
+        clock_in.tags = Tags(tags);
+        entries.push(clock_in);
+        entries.push(Entry::new(
+            EntryType::ClockOut,
+            stop,
+            EntrySource::default(),
+        ));
+    }
+    Ok(entries)
+}
@@ -0,0 +1,104 @@
+// Copyright (C) 2023 Campbell M. Cole
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use crate::{
+    csv::{header_state, read_valid_entries, HeaderState},
+    prelude::*,
+};
+
+use super::doctor::count_problems;
+
+#[instrument]
+pub fn print_info(cli_args: &Cli) -> Result<()> {
+    use owo_colors::OwoColorize;
+
+    let data_file = cli_args.get_output_file();
+    let label = |label: &str| {
+        format!("{:<10}", format!("{label}:"))
+            .bright_black()
+            .to_string()
+    };
+
+    println!("{} {}", label("Data file"), data_file.display().cyan());
+
+    if !data_file.exists() {
+        println!("{}", HINT_NO_ENTRIES.yellow());
+        return Ok(());
+    }
+
+    let size = data_file
+        .metadata()
+        .wrap_err(ERR_READ_CSV(&data_file))?
+        .len();
+    println!("{} {}", label("Size"), format_size(size).magenta());
+
+    let entries = read_valid_entries(cli_args)?;
+    println!("{} {}", label("Entries"), entries.len().magenta());
+
+    let first = entries.iter().map(|e| e.timestamp).min();
+    let last = entries.iter().map(|e| e.timestamp).max();
+    if let (Some(first), Some(last)) = (first, last) {
+        println!(
+            "{} {} {} {}",
+            label("Covers"),
+            first.format(PRETTY_DATE).magenta(),
+            "to".bright_black(),
+            last.format(PRETTY_DATE).magenta()
+        );
+    }
+
+    let header = match header_state(cli_args)? {
+        HeaderState::Blank => "none (the file is empty)".yellow().to_string(),
+        HeaderState::Current => "current".green().to_string(),
+        HeaderState::Outdated => "outdated".yellow().to_string(),
+        HeaderState::Missing => "missing".red().to_string(),
+    };
+    println!("{} {header}", label("Header"));
+
+    let problems = count_problems(cli_args)?;
+    if problems == 0 {
+        println!("{} {}", label("Problems"), "none".green());
+    } else {
+        println!(
+            "{} {} {}",
+            label("Problems"),
+            problems.red().bold(),
+            "(run 'punchcard doctor' for details)".bright_black()
+        );
+    }
+
+    println!(
+        "{} {}",
+        label("Backups"),
+        "none, punchcard doesn't keep copies of the data file".yellow()
+    );
+
+    Ok(())
+}
+
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{size:.1} {}", UNITS[unit])
+    }
+}
@@ -0,0 +1,813 @@
+// Copyright (C) 2023 Campbell M. Cole
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+// the kiosk lets several workers share one terminal. each worker has a data
+// folder of their own under `users/`, so every other command works on their
+// hours with '--user'
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, IsTerminal, Write},
+    time::{Duration as StdDuration, Instant},
+};
+
+use crossterm::{
+    cursor::MoveTo,
+    event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    execute, queue,
+    terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    config::read_config,
+    csv::{append_entry, read_entries, write_entries, Revision},
+    location::add_configured_tags,
+    prelude::*,
+    shift::{read_shifts, start_of_day, total_between},
+    table::{settings::TableSettings, table_config, DataFrameDisplay, StringTable},
+    Operation,
+};
+
+use super::{
+    clock::check_entry,
+    status::{get_clock_status_inner, ClockStatusType},
+};
+
+const RES_ID: &str = "ID";
+const RES_NAME: &str = "Name";
+const RES_STATUS: &str = "Status";
+const RES_TODAY: &str = "Today";
+
+/// Typed instead of an ID to unlock supervisor mode
+const SUPERVISOR_INPUT: &str = "*";
+/// How long a message stays on the kiosk screen
+const MESSAGE_DURATION: StdDuration = StdDuration::from_secs(8);
+/// How often the kiosk checks who is clocked in, to notice changes made elsewhere
+const REFRESH_INTERVAL: StdDuration = StdDuration::from_secs(30);
+/// How long supervisor mode stays unlocked without any input
+const SUPERVISOR_TIMEOUT: StdDuration = StdDuration::from_secs(60);
+/// Scans of the same badge within this long of each other are ignored, since
+/// readers often pick a card up twice while it is held near them
+const SCAN_DEBOUNCE: StdDuration = StdDuration::from_secs(5);
+
+#[derive(Debug, Args)]
+pub struct KioskArgs {
+    #[clap(subcommand)]
+    pub operation: Option<KioskOperation>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum KioskOperation {
+    /// Add a worker who can clock in and out at the kiosk
+    Add {
+        /// The ID the worker types at the kiosk, e.g. their employee number
+        id: String,
+        /// The name shown when the worker clocks in or out
+        name: String,
+    },
+    /// Remove a worker from the kiosk
+    ///
+    /// Their hours are kept in the data folder.
+    Remove {
+        /// The ID of the worker
+        id: String,
+    },
+    /// List the workers with their clock status and hours today
+    Users {
+        #[clap(flatten)]
+        table_settings: TableSettings,
+    },
+    /// Set the PIN which unlocks supervisor mode at the kiosk
+    Pin,
+    /// Link a badge to a worker, or unlink it if no ID is given
+    ///
+    /// Badge readers which act as a keyboard type the UID of the card followed
+    /// by enter, so a worker can scan their badge instead of typing their ID.
+    Card {
+        /// The UID of the card, as typed by the reader
+        uid: String,
+        /// The ID of the worker the card belongs to
+        id: Option<String>,
+    },
+}
+
+/// The workers of the kiosk, stored in `kiosk.json` in the data folder.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Kiosk {
+    /// The hash of the PIN which unlocks supervisor mode
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub supervisor_pin: Option<String>,
+    pub users: BTreeMap<String, KioskUser>,
+    /// The IDs of the workers, by the UID of their badge
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub cards: BTreeMap<String, String>,
+}
+
+impl Kiosk {
+    /// The ID of the worker a badge belongs to, and whether the input was a badge.
+    fn resolve(&self, input: &str) -> Option<(&str, bool)> {
+        if let Some((id, _)) = self.users.get_key_value(input) {
+            return Some((id, false));
+        }
+        self.cards
+            .get(&normalize_uid(input))
+            .map(|id| (id.as_str(), true))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KioskUser {
+    pub name: String,
+}
+
+pub fn read_kiosk(cli_args: &Cli) -> Result<Kiosk> {
+    read_json_file(&cli_args.get_kiosk_file())
+}
+
+/// Points the data folder at the hours of the worker given with '--user'.
+pub fn select_user(cli_args: &mut Cli) -> Result<()> {
+    let Some(id) = cli_args.user.clone() else {
+        return Ok(());
+    };
+    let kiosk = read_kiosk(cli_args)?;
+    if !kiosk.users.contains_key(&id) {
+        return Err(
+            ErrorCode::NotFound.report(format!("There is no kiosk user with the ID '{id}'"))
+        )
+        .suggestion("Use 'punchcard kiosk users' to list them");
+    }
+    cli_args.data_folder = cli_args.get_user_folder(&id);
+    cli_args.supervised = kiosk.supervisor_pin.is_some();
+    Ok(())
+}
+
+/// The arguments to read and write the hours of one worker with.
+pub(crate) fn user_cli(cli_args: &Cli, id: &str) -> Cli {
+    Cli {
+        data_folder: cli_args.get_user_folder(id),
+        timezone: cli_args.timezone,
+        output: cli_args.output,
+        accessible: cli_args.accessible,
+        user: None,
+        // the kiosk only adds entries at the current time, or in supervisor mode
+        supervised: false,
+        // never looked at, only the data folder and timezone are
+        operation: Operation::Info,
+    }
+}
+
+fn is_valid_id(id: &str) -> bool {
+    !id.is_empty()
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Readers differ in whether they type hex UIDs in upper or lower case.
+fn normalize_uid(uid: &str) -> String {
+    uid.trim().to_ascii_uppercase()
+}
+
+/// Hashes the supervisor PIN so that it isn't stored as plain text.
+///
+/// Anyone who can read the kiosk file can edit the data files directly,
+/// so this only keeps the PIN itself from being read off the disk.
+pub(crate) fn hash_pin(pin: &str) -> String {
+    format!("{:x}", Sha256::digest(format!("punchcard kiosk:{pin}")))
+}
+
+#[instrument]
+pub fn manage_kiosk(cli_args: &Cli, KioskArgs { operation }: &KioskArgs) -> Result<()> {
+    use owo_colors::OwoColorize;
+
+    let mut kiosk = read_kiosk(cli_args)?;
+
+    match operation {
+        None => return run_kiosk(cli_args, &kiosk),
+        Some(KioskOperation::Users { table_settings }) => {
+            return list_users(cli_args, &kiosk, table_settings);
+        }
+        Some(KioskOperation::Add { id, name }) => {
+            if !is_valid_id(id) {
+                return Err(eyre!("Invalid ID '{id}'"))
+                    .suggestion("IDs can only contain letters, numbers, '-', and '_'");
+            }
+            if let Some(user) = kiosk.users.get(id) {
+                return Err(eyre!("The ID '{id}' already belongs to {}", user.name));
+            }
+            if kiosk.cards.contains_key(&normalize_uid(id)) {
+                return Err(eyre!("The ID '{id}' is also the UID of a card"));
+            }
+            let folder = cli_args.get_user_folder(id);
+            fs::create_dir_all(&folder)
+                .wrap_err("Failed to create the data folder of the user")
+                .suggestion(SUGG_PROPER_PERMS(&folder))?;
+            kiosk
+                .users
+                .insert(id.clone(), KioskUser { name: name.clone() });
+            println!("Added {} with the ID {}", name.bold().cyan(), id.bold());
+        }
+        Some(KioskOperation::Remove { id }) => {
+            let Some(user) = kiosk.users.remove(id) else {
+                return Err(ErrorCode::NotFound
+                    .report(format!("There is no kiosk user with the ID '{id}'")));
+            };
+            kiosk.cards.retain(|_, card_id| card_id != id);
+            println!(
+                "Removed {} from the kiosk {}",
+                user.name.bold().cyan(),
+                format!(
+                    "(their hours are kept in {})",
+                    cli_args.get_user_folder(id).display()
+                )
+                .bright_black()
+            );
+        }
+        Some(KioskOperation::Pin) => {
+            let pin = prompt_hidden("New supervisor PIN: ")?;
+            if pin.chars().count() < 4 {
+                return Err(eyre!("The PIN must be at least 4 characters long"));
+            }
+            if prompt_hidden("Repeat the PIN: ")? != pin {
+                return Err(eyre!("The PINs don't match"));
+            }
+            kiosk.supervisor_pin = Some(hash_pin(&pin));
+            println!("Set the supervisor PIN");
+        }
+        Some(KioskOperation::Card { uid, id: None }) => {
+            let uid = normalize_uid(uid);
+            if kiosk.cards.remove(&uid).is_none() {
+                return Err(ErrorCode::NotFound.report(format!("There is no card '{uid}'")));
+            }
+            println!("Unlinked the card {}", uid.bold());
+        }
+        Some(KioskOperation::Card { uid, id: Some(id) }) => {
+            let uid = normalize_uid(uid);
+            if uid.is_empty() || uid.contains(char::is_whitespace) {
+                return Err(eyre!("Invalid card UID '{uid}'"));
+            }
+            if kiosk.users.contains_key(&uid) {
+                return Err(eyre!("The card UID '{uid}' is also the ID of a kiosk user"))
+                    .suggestion("The kiosk couldn't tell the two apart when it is scanned");
+            }
+            let Some(user) = kiosk.users.get(id) else {
+                return Err(ErrorCode::NotFound
+                    .report(format!("There is no kiosk user with the ID '{id}'")));
+            };
+            println!(
+                "Linked the card {} to {}",
+                uid.bold(),
+                user.name.bold().cyan()
+            );
+            kiosk.cards.insert(uid, id.clone());
+        }
+    }
+
+    write_json_file(&cli_args.get_kiosk_file(), &kiosk)
+}
+
+fn list_users(cli_args: &Cli, kiosk: &Kiosk, table_settings: &TableSettings) -> Result<()> {
+    let now = Local::now();
+    let mut statuses = Vec::with_capacity(kiosk.users.len());
+    let mut totals = Vec::with_capacity(kiosk.users.len());
+    for id in kiosk.users.keys() {
+        let user = user_cli(cli_args, id);
+        statuses.push(match clocked_in_since(&user, now)? {
+            Some(since) => format!("in since {}", since.format(PRETTY_TIME)),
+            None => "out".to_string(),
+        });
+        let shifts = read_shifts(&user)?;
+        let today = total_between(&shifts, start_of_day(now), now, now);
+        totals.push(BiDuration::new(today).to_compact_string());
+    }
+
+    let table = StringTable::new()
+        .with_column(RES_ID, "str", kiosk.users.keys().cloned().collect())
+        .with_column(
+            RES_NAME,
+            "str",
+            kiosk.users.values().map(|u| u.name.clone()).collect(),
+        )
+        .with_column(RES_STATUS, "str", statuses)
+        .with_column(RES_TODAY, "str", totals);
+
+    let display = DataFrameDisplay::new(
+        &table,
+        table_settings,
+        table_config(cli_args),
+        cli_args.accessible,
+    )
+    .with_caption("kiosk users");
+    println!("{display}");
+
+    if kiosk.users.is_empty() {
+        use owo_colors::OwoColorize;
+        println!(
+            "{}",
+            "There are no kiosk users yet. Use 'punchcard kiosk add <id> <name>' to add one."
+                .yellow()
+        );
+    }
+
+    Ok(())
+}
+
+/// When the worker clocked in, or `None` if they are clocked out.
+fn clocked_in_since(user: &Cli, now: DateTime<Local>) -> Result<Option<DateTime<Local>>> {
+    let status = get_clock_status_inner(user, now)?;
+    Ok(match status.status_type {
+        ClockStatusType::Entry(EntryType::ClockIn) => status.since,
+        _ => None,
+    })
+}
+
+/// Adds an entry for a worker with the same checks as 'punchcard in' and 'punchcard out'.
+///
+/// Without an entry type, the worker's clock is toggled.
+fn add_user_entry(
+    user: &Cli,
+    entry_type: Option<EntryType>,
+    timestamp: DateTime<Local>,
+) -> Result<Entry> {
+    let status = get_clock_status_inner(user, timestamp)?;
+    let entry_type = entry_type.unwrap_or(match status.status_type {
+        ClockStatusType::Entry(EntryType::ClockIn) => EntryType::ClockOut,
+        _ => EntryType::ClockIn,
+    });
+    let mut entry = Entry::new(entry_type, timestamp, EntrySource::Kiosk);
+    add_configured_tags(&read_config(user)?, &mut entry);
+    check_entry(&mut entry, &status)?;
+    fs::create_dir_all(&user.data_folder)
+        .wrap_err("Failed to create the data folder of the user")
+        .suggestion(SUGG_PROPER_PERMS(&user.data_folder))?;
+    append_entry(user, &entry, status.revision.as_ref())?;
+    Ok(entry)
+}
+
+/// Removes the latest entry of a worker, returning it.
+fn remove_last_entry(user: &Cli) -> Result<Option<Entry>> {
+    if !user.get_output_file().exists() {
+        return Ok(None);
+    }
+    let revision = Revision::current(user)?;
+    let mut entries = read_entries(user)?;
+    entries.sort_by_key(|e| e.timestamp);
+    let last = entries.pop();
+    if last.is_some() {
+        write_entries(user, &entries, revision.as_ref())?;
+    }
+    Ok(last)
+}
+
+/// Reads a line from the terminal without showing what is typed.
+pub(crate) fn prompt_hidden(prompt: &str) -> Result<String> {
+    if !io::stdin().is_terminal() {
+        return Err(eyre!("The PIN must be typed in a terminal"));
+    }
+    print!("{prompt}");
+    io::stdout().flush()?;
+
+    terminal::enable_raw_mode()?;
+    let result = read_hidden_line();
+    terminal::disable_raw_mode()?;
+    println!();
+    result
+}
+
+fn read_hidden_line() -> Result<String> {
+    let mut line = String::new();
+    loop {
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        match key.code {
+            KeyCode::Enter => return Ok(line),
+            KeyCode::Esc => return Err(eyre!("Canceled")),
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                return Err(eyre!("Canceled"))
+            }
+            KeyCode::Char(c) => line.push(c),
+            KeyCode::Backspace => {
+                line.pop();
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Runs the kiosk full screen until a supervisor closes it.
+fn run_kiosk(cli_args: &Cli, kiosk: &Kiosk) -> Result<()> {
+    if kiosk.users.is_empty() {
+        return Err(eyre!("There are no kiosk users yet"))
+            .suggestion("Use 'punchcard kiosk add <id> <name>' to add one");
+    }
+    if !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+        return Err(eyre!("The kiosk must be run in a terminal"));
+    }
+
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen)?;
+    terminal::enable_raw_mode()?;
+
+    let result = KioskScreen::new(cli_args, kiosk).run(&mut stdout);
+
+    // restore the terminal even if the kiosk failed, so the error is readable
+    let _ = terminal::disable_raw_mode();
+    let _ = execute!(stdout, LeaveAlternateScreen);
+    result
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    /// Waiting for a worker to type their ID
+    Scan,
+    /// Waiting for the supervisor PIN
+    Pin,
+    /// Unlocked until escape is pressed or nothing is typed for a while
+    Supervisor,
+}
+
+struct KioskScreen<'a> {
+    cli_args: &'a Cli,
+    kiosk: &'a Kiosk,
+    mode: Mode,
+    input: String,
+    /// The result of the latest input, and when it was shown
+    message: Option<(Vec<String>, Instant)>,
+    /// The names of the workers who are clocked in, and since when
+    clocked_in: Vec<(&'a str, DateTime<Local>)>,
+    refreshed_at: Option<Instant>,
+    last_input: Instant,
+    /// The worker whose badge was scanned last, to ignore double scans
+    last_scan: Option<(&'a str, Instant)>,
+    /// The latest confirmation banner of each worker, shown again on a double scan
+    banners: BTreeMap<&'a str, Vec<String>>,
+}
+
+impl<'a> KioskScreen<'a> {
+    fn new(cli_args: &'a Cli, kiosk: &'a Kiosk) -> Self {
+        Self {
+            cli_args,
+            kiosk,
+            mode: Mode::Scan,
+            input: String::new(),
+            message: None,
+            clocked_in: Vec::new(),
+            refreshed_at: None,
+            last_input: Instant::now(),
+            last_scan: None,
+            banners: BTreeMap::new(),
+        }
+    }
+
+    fn run(&mut self, out: &mut impl Write) -> Result<()> {
+        loop {
+            if self
+                .refreshed_at
+                .map_or(true, |at| at.elapsed() >= REFRESH_INTERVAL)
+            {
+                self.refresh();
+            }
+            if self.mode == Mode::Supervisor && self.last_input.elapsed() >= SUPERVISOR_TIMEOUT {
+                self.mode = Mode::Scan;
+            }
+            self.draw(out)?;
+
+            // wake up every second to keep the clock on screen current
+            if !event::poll(StdDuration::from_secs(1))? {
+                continue;
+            }
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            self.last_input = Instant::now();
+
+            match key.code {
+                // raw mode turns ctrl-c into a key press, so workers can't close the kiosk
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    use owo_colors::OwoColorize;
+                    self.input.clear();
+                    self.show("Ask a supervisor to close the kiosk".yellow().to_string());
+                }
+                KeyCode::Char(c) => self.input.push(c),
+                KeyCode::Backspace => {
+                    self.input.pop();
+                }
+                KeyCode::Esc => {
+                    self.input.clear();
+                    self.mode = Mode::Scan;
+                }
+                KeyCode::Enter => {
+                    let input = std::mem::take(&mut self.input);
+                    if self.submit(input.trim()) {
+                        return Ok(());
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn show(&mut self, message: String) {
+        self.message = Some((vec![message], Instant::now()));
+    }
+
+    fn show_error(&mut self, error: color_eyre::Report) {
+        use owo_colors::OwoColorize;
+        self.show(format!("{error:#}").red().to_string());
+    }
+
+    /// Rereads who is clocked in.
+    fn refresh(&mut self) {
+        let now = Local::now();
+        let kiosk = self.kiosk;
+        self.refreshed_at = Some(Instant::now());
+        self.clocked_in.clear();
+        for (id, user) in &kiosk.users {
+            match clocked_in_since(&user_cli(self.cli_args, id), now) {
+                Ok(Some(since)) => self.clocked_in.push((&user.name, since)),
+                Ok(None) => {}
+                Err(e) => self
+                    .show_error(e.wrap_err(format!("Failed to read the hours of {}", user.name))),
+            }
+        }
+        self.clocked_in.sort_by_key(|(_, since)| *since);
+    }
+
+    /// Handles a line of input, returning whether the kiosk should close.
+    fn submit(&mut self, input: &str) -> bool {
+        use owo_colors::OwoColorize;
+
+        if input.is_empty() {
+            return false;
+        }
+
+        match self.mode {
+            Mode::Scan if input == SUPERVISOR_INPUT => match self.kiosk.supervisor_pin {
+                Some(_) => self.mode = Mode::Pin,
+                None => self.show(
+                    "There is no supervisor PIN yet, set one with 'punchcard kiosk pin'"
+                        .yellow()
+                        .to_string(),
+                ),
+            },
+            Mode::Scan => {
+                if let Err(e) = self.scan(input) {
+                    self.show_error(e);
+                }
+            }
+            Mode::Pin => {
+                if self.kiosk.supervisor_pin.as_deref() == Some(&hash_pin(input)) {
+                    self.mode = Mode::Supervisor;
+                    self.message = None;
+                } else {
+                    self.mode = Mode::Scan;
+                    self.show("Wrong PIN".red().to_string());
+                }
+            }
+            Mode::Supervisor => match self.supervise(input) {
+                Ok(quit) => return quit,
+                Err(e) => self.show_error(e),
+            },
+        }
+
+        false
+    }
+
+    /// Clocks in or out the worker with the ID or badge given.
+    fn scan(&mut self, input: &str) -> Result<()> {
+        let kiosk = self.kiosk;
+        let Some((id, is_card)) = kiosk.resolve(input) else {
+            return Err(ErrorCode::NotFound.report(format!("Unknown ID '{input}'")));
+        };
+
+        if is_card {
+            let double_scan = self
+                .last_scan
+                .is_some_and(|(last, at)| last == id && at.elapsed() < SCAN_DEBOUNCE);
+            self.last_scan = Some((id, Instant::now()));
+            if double_scan {
+                if let Some(banner) = self.banners.get(id) {
+                    self.message = Some((banner.clone(), Instant::now()));
+                }
+                return Ok(());
+            }
+        }
+
+        self.toggle(id)
+    }
+
+    fn user_name(&self, id: &str) -> Result<&'a str> {
+        let kiosk = self.kiosk;
+        kiosk
+            .users
+            .get(id)
+            .map(|user| user.name.as_str())
+            .ok_or_else(|| ErrorCode::NotFound.report(format!("Unknown ID '{id}'")))
+    }
+
+    /// Clocks a worker in or out, and shows a banner with their hours today.
+    fn toggle(&mut self, id: &'a str) -> Result<()> {
+        use owo_colors::OwoColorize;
+
+        let name = self.user_name(id)?;
+        let user = user_cli(self.cli_args, id);
+        let entry = add_user_entry(&user, None, Local::now())?;
+
+        let now = Local::now();
+        let shifts = read_shifts(&user)?;
+        let today = total_between(&shifts, start_of_day(now), now, now);
+
+        let mut banner = big_text(&entry.entry_type.to_string())
+            .into_iter()
+            .map(|line| {
+                line.color(crate::palette::entry_color(
+                    entry.entry_type,
+                    self.cli_args.accessible,
+                ))
+                .bold()
+                .to_string()
+            })
+            .collect::<Vec<_>>();
+        banner.push(String::new());
+        banner.push(format!(
+            "{} clocked {} at {}",
+            name.bold().cyan(),
+            entry.entry_type.colored(self.cli_args.accessible).bold(),
+            entry.timestamp.format(PRETTY_TIME).magenta(),
+        ));
+        banner.push(format!(
+            "{} today",
+            BiDuration::new(today).to_compact_string().bold()
+        ));
+
+        self.message = Some((banner.clone(), Instant::now()));
+        self.banners.insert(id, banner);
+        self.refresh();
+        Ok(())
+    }
+
+    /// Runs a supervisor command, returning whether the kiosk should close.
+    fn supervise(&mut self, line: &str) -> Result<bool> {
+        use owo_colors::OwoColorize;
+
+        let mut words = line.split_whitespace();
+        let command = words.next().unwrap_or_default();
+        let id = words.next();
+        let offset = words.collect::<Vec<_>>().join(" ");
+
+        match (command, id) {
+            ("quit", None) => return Ok(true),
+            ("in" | "out", Some(id)) => {
+                let name = self.user_name(id)?;
+                let entry_type = match command {
+                    "in" => EntryType::ClockIn,
+                    _ => EntryType::ClockOut,
+                };
+                let timestamp = match offset.is_empty() {
+                    true => Local::now(),
+                    false => offset
+                        .parse::<BiDuration>()
+                        .wrap_err_with(|| format!("Invalid offset '{offset}'"))?
+                        .relative_to_now(),
+                };
+                let entry =
+                    add_user_entry(&user_cli(self.cli_args, id), Some(entry_type), timestamp)?;
+                self.show(format!(
+                    "Clocked {} {} at {}",
+                    name.bold().cyan(),
+                    entry.entry_type.colored(self.cli_args.accessible).bold(),
+                    entry.timestamp.format(SLIM_DATETIME).magenta(),
+                ));
+            }
+            ("undo", Some(id)) => {
+                let name = self.user_name(id)?;
+                match remove_last_entry(&user_cli(self.cli_args, id))? {
+                    Some(entry) => self.show(format!(
+                        "Removed the clock-{} of {} at {}",
+                        entry.entry_type,
+                        name.bold().cyan(),
+                        entry.timestamp.format(SLIM_DATETIME).magenta(),
+                    )),
+                    None => self.show(format!("{} has no entries", name.bold().cyan())),
+                }
+            }
+            _ => return Err(eyre!("Unknown command '{line}'")),
+        }
+
+        self.refresh();
+        Ok(false)
+    }
+
+    fn draw(&self, out: &mut impl Write) -> Result<()> {
+        use owo_colors::{DynColors, OwoColorize};
+        let gray = DynColors::Rgb(128, 128, 128);
+
+        let now = Local::now();
+        let mut lines = vec![
+            format!(
+                "{} {}",
+                "punchcard kiosk".bold(),
+                now.format(PRETTY_DATETIME).color(gray)
+            ),
+            String::new(),
+        ];
+
+        if self.clocked_in.is_empty() {
+            lines.push("Nobody is clocked in".color(gray).to_string());
+        } else {
+            lines.push("Clocked in:".bold().to_string());
+            lines.extend(self.clocked_in.iter().map(|(name, since)| {
+                format!(
+                    "  {} {}",
+                    name.cyan(),
+                    format!("since {}", since.format(PRETTY_TIME)).color(gray)
+                )
+            }));
+        }
+        lines.push(String::new());
+
+        match &self.message {
+            Some((message, shown_at)) if shown_at.elapsed() < MESSAGE_DURATION => {
+                lines.extend(message.iter().cloned())
+            }
+            _ => lines.push(String::new()),
+        }
+        lines.push(String::new());
+
+        let (instructions, input) = match self.mode {
+            Mode::Scan => (
+                format!(
+                    "Scan your badge, or type your ID and press enter {}",
+                    format!("({SUPERVISOR_INPUT} for supervisor mode)").color(gray)
+                ),
+                self.input.clone(),
+            ),
+            Mode::Pin => (
+                "Supervisor PIN (escape to cancel)".to_string(),
+                "*".repeat(self.input.chars().count()),
+            ),
+            Mode::Supervisor => (
+                format!(
+                    "{} in <id> [offset], out <id> [offset], undo <id>, or quit {}",
+                    "Supervisor:".yellow().bold(),
+                    "(escape to lock)".color(gray)
+                ),
+                self.input.clone(),
+            ),
+        };
+        lines.push(instructions);
+        lines.push(format!("> {input}"));
+
+        queue!(out, MoveTo(0, 0), Clear(ClearType::All))?;
+        // raw mode doesn't move back to the start of the line on a newline
+        write!(out, "{}", lines.join("\r\n"))?;
+        out.flush()?;
+        Ok(())
+    }
+}
+
+/// Renders the text in letters five lines tall, so it can be read from a distance.
+///
+/// Only the letters of "IN" and "OUT" are drawn.
+fn big_text(text: &str) -> [String; 5] {
+    let mut lines: [String; 5] = Default::default();
+    for c in text.chars() {
+        let glyph = match c.to_ascii_uppercase() {
+            'I' => ["███", " █ ", " █ ", " █ ", "███"],
+            'N' => ["█   █", "██  █", "█ █ █", "█  ██", "█   █"],
+            'O' => [" ███ ", "█   █", "█   █", "█   █", " ███ "],
+            'U' => ["█   █", "█   █", "█   █", "█   █", " ███ "],
+            'T' => ["█████", "  █  ", "  █  ", "  █  ", "  █  "],
+            _ => ["   "; 5],
+        };
+        for (line, row) in lines.iter_mut().zip(glyph) {
+            if !line.is_empty() {
+                line.push_str("  ");
+            }
+            line.push_str(row);
+        }
+    }
+    lines
+}
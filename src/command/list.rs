@@ -0,0 +1,142 @@
+// Copyright (C) 2023 Campbell M. Cole
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use chrono::Datelike;
+
+use crate::{
+    command::project::{project_color, read_projects},
+    csv::read_entries,
+    prelude::*,
+    table::{settings::TableSettings, table_config, DataFrameDisplay, StringTable},
+};
+
+const RES_TYPE: &str = "Type";
+const RES_TIMESTAMP: &str = "Timestamp";
+const RES_PROJECT: &str = "Project";
+const RES_TAGS: &str = "Tags";
+const RES_SOURCE: &str = "Source";
+const RES_NOTE: &str = "Note";
+
+#[derive(Debug, Args)]
+pub struct ListArgs {
+    /// Only list entries from this month
+    ///
+    /// Accepts a month name (e.g. `January`) or a number (e.g. `1`)
+    /// or `current`, `previous`, `next`, or `all`
+    #[clap(short, long, default_value_t = Month::All)]
+    pub month: Month,
+    /// Only list entries created this way (e.g. `cli`, `template`, `recur`, `auto-clockout`)
+    #[clap(long)]
+    pub source: Option<EntrySource>,
+    #[clap(flatten)]
+    pub table_settings: TableSettings,
+}
+
+#[instrument]
+pub fn list_entries(cli_args: &Cli, args: &ListArgs) -> Result<()> {
+    let entries = if cli_args.get_output_file().exists() {
+        read_entries(cli_args)?
+    } else {
+        Vec::new()
+    };
+
+    let no_entries = entries.is_empty();
+
+    let month_start = args.month.as_date();
+
+    let entries = entries
+        .into_iter()
+        .filter(|e| {
+            month_start
+                .map(|start| {
+                    e.timestamp.year() == start.year() && e.timestamp.month() == start.month()
+                })
+                .unwrap_or(true)
+        })
+        .filter(|e| args.source.as_ref().map(|s| &e.source == s).unwrap_or(true))
+        .collect::<Vec<_>>();
+
+    let df = StringTable::new()
+        .with_column(
+            RES_TYPE,
+            "str",
+            entries
+                .iter()
+                .map(|e| crate::palette::entry_label(e.entry_type, cli_args.accessible))
+                .collect(),
+        )
+        .with_column(
+            RES_TIMESTAMP,
+            "str",
+            entries
+                .iter()
+                .map(|e| e.timestamp.format(SLIM_DATETIME).to_string())
+                .collect(),
+        )
+        .with_column(
+            RES_PROJECT,
+            "str",
+            entries
+                .iter()
+                .map(|e| e.project.clone().unwrap_or_default())
+                .collect(),
+        )
+        .with_column(
+            RES_TAGS,
+            "str",
+            entries.iter().map(|e| e.tags.join(", ")).collect(),
+        )
+        .with_column(
+            RES_SOURCE,
+            "str",
+            entries.iter().map(|e| e.source.to_string()).collect(),
+        )
+        .with_column(
+            RES_NOTE,
+            "str",
+            entries
+                .iter()
+                .map(|e| e.note.clone().unwrap_or_default())
+                .collect(),
+        );
+
+    let projects = read_projects(cli_args)?;
+    let row_colors = entries
+        .iter()
+        .map(|e| project_color(&projects, e.project.as_deref()))
+        .collect();
+
+    let caption = match args.month {
+        Month::All => "all entries".to_string(),
+        month => format!("entries from {}", month.to_pretty_string()),
+    };
+
+    let display = DataFrameDisplay::new(
+        &df,
+        &args.table_settings,
+        table_config(cli_args),
+        cli_args.accessible,
+    )
+    .with_row_colors(row_colors)
+    .with_caption(caption);
+    println!("{display}");
+
+    if no_entries {
+        use owo_colors::OwoColorize;
+        println!("{}", HINT_NO_ENTRIES.yellow());
+    }
+
+    Ok(())
+}
@@ -13,8 +13,34 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
+pub mod absence;
+pub mod approve;
+pub mod backfill;
+pub mod batch;
 pub mod clock;
+pub mod config;
+pub mod doctor;
+pub mod events;
+pub mod export;
+pub mod fix_last;
 #[cfg(feature = "generate_test_data")]
 pub mod generate;
+pub mod git;
+pub mod import;
+pub mod info;
+pub mod kiosk;
+pub mod list;
+pub mod oncall;
+pub mod open;
+pub mod project;
+pub mod recur;
 pub mod report;
+pub mod shell_hook;
+pub mod standup;
+pub mod stats;
 pub mod status;
+pub mod template;
+pub mod timer;
+pub mod url;
+pub mod util;
+pub mod verify;
@@ -0,0 +1,300 @@
+// Copyright (C) 2023 Campbell M. Cole
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+// on-call periods are kept in their own file, next to the data file rather
+// than in it, because they overlap the shifts instead of alternating with
+// them. a clock-in while on call is an incident, which is tagged so its hours
+// can be counted towards the on-call compensation
+
+use chrono::{Duration, Months};
+
+use crate::{
+    prelude::*,
+    shift::{read_shifts, start_of_week, Shift},
+    table::{settings::TableSettings, table_config, DataFrameDisplay, StringTable},
+};
+
+/// The tag of clock-ins made while on call
+pub const INCIDENT_TAG: &str = "incident";
+
+const RES_WEEK_OF: &str = "Week Of";
+const RES_ON_CALL_HOURS: &str = "On-Call Hours";
+const RES_INCIDENTS: &str = "Incidents";
+const RES_INCIDENT_HOURS: &str = "Incident Hours";
+
+#[derive(Debug, Args)]
+pub struct OncallArgs {
+    #[clap(subcommand)]
+    pub operation: OncallOperation,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum OncallOperation {
+    /// Start being on call
+    Start {
+        /// The offset from the current time to use as the start
+        #[clap(short, long)]
+        offset_from_now: Option<BiDuration>,
+    },
+    /// Stop being on call
+    End {
+        /// The offset from the current time to use as the end
+        #[clap(short, long)]
+        offset_from_now: Option<BiDuration>,
+    },
+    /// Show the hours on call and the incidents worked during them, by week
+    Report {
+        /// The month to show, e.g. `March`, `previous`, or `all`
+        #[clap(short, long, default_value_t = Default::default())]
+        month: Month,
+        #[clap(flatten)]
+        table_settings: TableSettings,
+    },
+}
+
+/// A period of being on call, which hasn't ended yet if `end` is `None`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OncallPeriod {
+    pub start: DateTime<Local>,
+    #[serde(default)]
+    pub end: Option<DateTime<Local>>,
+}
+
+impl OncallPeriod {
+    fn end_or(&self, now: DateTime<Local>) -> DateTime<Local> {
+        self.end.unwrap_or(now)
+    }
+
+    fn contains(&self, time: DateTime<Local>) -> bool {
+        self.start <= time && self.end.map(|end| time < end).unwrap_or(true)
+    }
+
+    /// The part of this period which falls between `from` and `to`.
+    fn overlap(
+        &self,
+        from: DateTime<Local>,
+        to: DateTime<Local>,
+        now: DateTime<Local>,
+    ) -> Duration {
+        (self.end_or(now).min(to) - self.start.max(from)).max(Duration::zero())
+    }
+}
+
+pub fn read_oncall(cli_args: &Cli) -> Result<Vec<OncallPeriod>> {
+    read_json_file(&cli_args.get_oncall_file())
+}
+
+/// Tags a clock-in as an incident if it was made while on call.
+pub fn add_incident_tag(cli_args: &Cli, entry: &mut Entry) -> Result<()> {
+    if entry.entry_type != EntryType::ClockIn || entry.tags.contains(INCIDENT_TAG) {
+        return Ok(());
+    }
+    let periods = read_oncall(cli_args)?;
+    if periods.iter().any(|p| p.contains(entry.timestamp)) {
+        entry.tags.0.push(INCIDENT_TAG.to_string());
+    }
+    Ok(())
+}
+
+#[instrument]
+pub fn manage_oncall(cli_args: &Cli, OncallArgs { operation }: &OncallArgs) -> Result<()> {
+    use owo_colors::OwoColorize;
+
+    let mut periods = read_oncall(cli_args)?;
+
+    match operation {
+        OncallOperation::Start { offset_from_now } => {
+            let start = offset_from_now.relative_to_now();
+            if let Some(last) = periods.last() {
+                if last.end.is_none() {
+                    return Err(eyre!(
+                        "You are already on call since {}",
+                        last.start.format(SLIM_DATETIME)
+                    ))
+                    .suggestion("Use 'punchcard oncall end' first");
+                }
+                if last.end_or(start) > start {
+                    return Err(eyre!(
+                        "The last on-call period ended later, at {}",
+                        last.end_or(start).format(SLIM_DATETIME)
+                    ));
+                }
+            }
+            periods.push(OncallPeriod { start, end: None });
+            println!(
+                "{} {}",
+                "On call since".green(),
+                start.format(SLIM_DATETIME).magenta().bold()
+            );
+        }
+        OncallOperation::End { offset_from_now } => {
+            let end = offset_from_now.relative_to_now();
+            let Some(current) = periods.last_mut().filter(|p| p.end.is_none()) else {
+                return Err(eyre!("You are not on call"))
+                    .suggestion("Use 'punchcard oncall start' to start being on call");
+            };
+            if end <= current.start {
+                return Err(eyre!(
+                    "The end must be after the start of the on-call period, {}",
+                    current.start.format(SLIM_DATETIME)
+                ));
+            }
+            current.end = Some(end);
+            println!(
+                "{} {} {}",
+                "No longer on call after".green(),
+                BiDuration::new(end - current.start)
+                    .to_friendly_absolute_string()
+                    .yellow()
+                    .bold(),
+                format!("(since {})", current.start.format(SLIM_DATETIME)).bright_black()
+            );
+        }
+        OncallOperation::Report {
+            month,
+            table_settings,
+        } => return show_report(cli_args, &periods, *month, table_settings),
+    }
+
+    write_json_file(&cli_args.get_oncall_file(), &periods)
+}
+
+/// The on-call time and the incidents during it in one week.
+#[derive(Debug)]
+struct OncallWeek {
+    on_call: Duration,
+    incidents: usize,
+    incident_hours: Duration,
+}
+
+fn show_report(
+    cli_args: &Cli,
+    periods: &[OncallPeriod],
+    month: Month,
+    table_settings: &TableSettings,
+) -> Result<()> {
+    let now = Local::now();
+    let (from, to) = match month.as_date() {
+        Some(start) => (
+            start,
+            start
+                .checked_add_months(Months::new(1))
+                .expect("the next month of a valid date always exists"),
+        ),
+        None => (
+            periods.first().map(|p| p.start).unwrap_or(now),
+            periods
+                .iter()
+                .map(|p| p.end_or(now))
+                .fold(now, DateTime::max),
+        ),
+    };
+
+    let incidents = read_shifts(cli_args)?
+        .into_iter()
+        .filter(|s| s.start.tags.contains(INCIDENT_TAG))
+        .collect::<Vec<_>>();
+
+    let mut weeks = Vec::new();
+    let mut week = start_of_week(from);
+    while week < to {
+        let next = start_of_week(week + Duration::days(8));
+        let (start, end) = (week.max(from), next.min(to));
+        let summary = summarize(periods, &incidents, start, end, now);
+        if summary.on_call > Duration::zero() {
+            weeks.push((week, summary));
+        }
+        week = next;
+    }
+
+    let hours = |d: Duration| BiDuration::new(d).to_compact_string();
+    let table = StringTable::new()
+        .with_column(
+            RES_WEEK_OF,
+            "str",
+            weeks
+                .iter()
+                .map(|(week, _)| week.format("%d %B %Y").to_string())
+                .collect(),
+        )
+        .with_column(
+            RES_ON_CALL_HOURS,
+            "str",
+            weeks.iter().map(|(_, s)| hours(s.on_call)).collect(),
+        )
+        .with_column(
+            RES_INCIDENTS,
+            "u32",
+            weeks.iter().map(|(_, s)| s.incidents.to_string()).collect(),
+        )
+        .with_column(
+            RES_INCIDENT_HOURS,
+            "str",
+            weeks.iter().map(|(_, s)| hours(s.incident_hours)).collect(),
+        );
+
+    let caption = match month {
+        Month::All => "all on-call periods".to_string(),
+        month => format!("on call in {}", month.to_pretty_string()),
+    };
+    println!(
+        "{}",
+        DataFrameDisplay::new(
+            &table,
+            table_settings,
+            table_config(cli_args),
+            cli_args.accessible
+        )
+        .with_caption(caption)
+    );
+
+    if weeks.is_empty() {
+        use owo_colors::OwoColorize;
+        println!(
+            "{}",
+            "You weren't on call in this period. Use 'punchcard oncall start' when you are."
+                .yellow()
+        );
+    }
+
+    Ok(())
+}
+
+/// Adds up the time on call between `from` and `to`, and the incidents
+/// which started in it. Only the part of an incident while on call counts.
+fn summarize(
+    periods: &[OncallPeriod],
+    incidents: &[Shift],
+    from: DateTime<Local>,
+    to: DateTime<Local>,
+    now: DateTime<Local>,
+) -> OncallWeek {
+    let mut week = OncallWeek {
+        on_call: Duration::zero(),
+        incidents: 0,
+        incident_hours: Duration::zero(),
+    };
+    for period in periods {
+        week.on_call = week.on_call + period.overlap(from, to, now);
+        let (start, end) = (period.start.max(from), period.end_or(now).min(to));
+        for incident in incidents {
+            if start <= incident.start.timestamp && incident.start.timestamp < end {
+                week.incidents += 1;
+            }
+            week.incident_hours = week.incident_hours + incident.overlap(start, end, now);
+        }
+    }
+    week
+}
@@ -0,0 +1,234 @@
+// Copyright (C) 2023 Campbell M. Cole
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+// the data file is edited as a copy, which is only moved into place once the
+// doctor has no complaints about it. the copy doubles as a lock, so the file
+// isn't edited twice at the same time
+
+use std::{
+    fs::{self, File},
+    io::{self, ErrorKind, IsTerminal, Write},
+    path::Path,
+    process::Command,
+};
+
+use crate::{
+    csv::{Revision, CSV_HEADER},
+    prelude::*,
+    signal,
+};
+
+use super::doctor::fix_problems;
+
+#[derive(Debug, Args)]
+pub struct OpenArgs {
+    /// Edit the data file in $VISUAL or $EDITOR instead of opening the data folder.
+    /// The changes are only saved if 'punchcard doctor' finds no problems with them.
+    #[clap(long, default_value_t = false)]
+    pub editor: bool,
+}
+
+#[instrument]
+pub fn open(cli_args: &Cli, OpenArgs { editor }: &OpenArgs) -> Result<()> {
+    if *editor {
+        edit_data_file(cli_args)
+    } else {
+        open_data_folder(cli_args)
+    }
+}
+
+/// The program which opens a file or folder in its default application.
+pub(crate) fn default_opener() -> &'static str {
+    if cfg!(target_os = "macos") {
+        "open"
+    } else if cfg!(windows) {
+        "explorer"
+    } else {
+        "xdg-open"
+    }
+}
+
+fn open_data_folder(cli_args: &Cli) -> Result<()> {
+    let opener = default_opener();
+    Command::new(opener)
+        .arg(&cli_args.data_folder)
+        .spawn()
+        .wrap_err_with(|| format!("Failed to run '{opener}'"))
+        .note(format!(
+            "The data folder is {}",
+            cli_args.data_folder.display()
+        ))?;
+    Ok(())
+}
+
+fn edit_data_file(cli_args: &Cli) -> Result<()> {
+    use owo_colors::OwoColorize;
+
+    if cli_args.supervised {
+        return Err(eyre!("Only a supervisor can edit the data file"))
+            .suggestion("Run the command without '--user'");
+    }
+
+    let data_file = cli_args.get_output_file();
+    let edit_file = data_file.with_extension("edit.csv");
+    let backup_file = data_file.with_extension("csv.bak");
+
+    let mut revision = Revision::current(cli_args)?;
+    let original = match revision {
+        Some(_) => fs::read(&data_file)
+            .wrap_err(ERR_READ_CSV(&data_file))
+            .suggestion(SUGG_PROPER_PERMS(&data_file))?,
+        None => format!("{}\n", CSV_HEADER.join(",")).into_bytes(),
+    };
+
+    let mut file = match File::options()
+        .write(true)
+        .create_new(true)
+        .open(&edit_file)
+    {
+        Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+            return Err(eyre!("The data file is already being edited")).suggestion(format!(
+                "If no editor has it open, delete {} and try again",
+                edit_file.display()
+            ));
+        }
+        result => result
+            .wrap_err(ERR_OPEN_CSV(&edit_file))
+            .suggestion(SUGG_PROPER_PERMS(&edit_file))?,
+    };
+    let _cleanup = signal::remove_on_interrupt(&edit_file);
+    file.write_all(&original)
+        .wrap_err(ERR_WRITE_CSV(&edit_file))?;
+    drop(file);
+
+    loop {
+        if let Err(e) = run_editor(&edit_file) {
+            fs::remove_file(&edit_file).ok();
+            return Err(e).note("The data file wasn't changed");
+        }
+
+        let edited = fs::read(&edit_file).wrap_err(ERR_READ_CSV(&edit_file))?;
+        if edited == original {
+            fs::remove_file(&edit_file).ok();
+            println!("{}", "No changes were made".bright_black());
+            return Ok(());
+        }
+
+        // the edit file is kept, so changes made by another process in the
+        // meantime don't cost the edits
+        Revision::ensure_current(revision.as_ref(), cli_args).note(format!(
+            "Your changes are in {}, delete it once you have copied them over",
+            edit_file.display()
+        ))?;
+
+        let problems = replace_and_check(cli_args, &data_file, &edit_file, &backup_file, revision)?;
+        if problems.is_empty() {
+            fs::remove_file(&edit_file).ok();
+            println!("{} Saved the data file", "✓".green());
+            return Ok(());
+        }
+
+        println!(
+            "{} {}",
+            "✗".red(),
+            "The changes were not saved because of these problems:".bold()
+        );
+        for problem in &problems {
+            println!("    {}", problem.bright_black());
+        }
+        revision = Revision::current(cli_args)?;
+
+        if !io::stdin().is_terminal() || !confirm("Edit the file again? [Y/n]")? {
+            fs::remove_file(&edit_file).ok();
+            return Err(eyre!(
+                "Found {} problem(s) in the edited data file",
+                problems.len()
+            ))
+            .note("The data file wasn't changed");
+        }
+    }
+}
+
+/// Moves the edited file into place and runs the checks of the doctor, fixing what
+/// it can. If any problems remain, the data file is rolled back and they are returned.
+fn replace_and_check(
+    cli_args: &Cli,
+    data_file: &Path,
+    edit_file: &Path,
+    backup_file: &Path,
+    revision: Option<Revision>,
+) -> Result<Vec<String>> {
+    if revision.is_some() {
+        fs::copy(data_file, backup_file)
+            .wrap_err(ERR_WRITE_CSV(backup_file))
+            .suggestion(SUGG_PROPER_PERMS(backup_file))?;
+    }
+    signal::uninterrupted(|| fs::copy(edit_file, data_file))
+        .wrap_err(ERR_WRITE_CSV(data_file))
+        .suggestion(SUGG_PROPER_PERMS(data_file))?;
+
+    let result = fix_problems(cli_args);
+    if matches!(&result, Ok(problems) if problems.is_empty()) {
+        fs::remove_file(backup_file).ok();
+        return result;
+    }
+
+    let rollback = signal::uninterrupted(|| match revision {
+        Some(_) => fs::rename(backup_file, data_file),
+        None => fs::remove_file(data_file),
+    });
+    rollback
+        .wrap_err("Failed to roll back the data file")
+        .note(format!(
+            "The data file before editing is in {}",
+            backup_file.display()
+        ))?;
+    result
+}
+
+/// Opens a file in the editor of the user and waits for it to be closed.
+pub(crate) fn run_editor(path: &Path) -> Result<()> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .ok()
+        .filter(|e| !e.trim().is_empty())
+        .unwrap_or_else(|| String::from(if cfg!(windows) { "notepad" } else { "vi" }));
+    // editors are often set with arguments, e.g. 'code --wait'
+    let mut words = editor.split_whitespace();
+    let program = words.next().unwrap_or_default();
+
+    let status = Command::new(program)
+        .args(words)
+        .arg(path)
+        .status()
+        .wrap_err_with(|| format!("Failed to run the editor '{editor}'"))
+        .suggestion("Set $EDITOR to the command of your editor")?;
+    if !status.success() {
+        return Err(eyre!("The editor exited with {status}"));
+    }
+    Ok(())
+}
+
+fn confirm(question: &str) -> Result<bool> {
+    use owo_colors::OwoColorize;
+
+    print!("{} ", question.yellow());
+    io::stdout().flush().wrap_err("Failed to write to stdout")?;
+    let mut answer = String::new();
+    io::stdin()
+        .read_line(&mut answer)
+        .wrap_err("Failed to read the answer")?;
+    Ok(!answer.trim().eq_ignore_ascii_case("n"))
+}
@@ -0,0 +1,469 @@
+// Copyright (C) 2023 Campbell M. Cole
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::BTreeMap;
+
+use chrono::Duration;
+
+use crate::{
+    csv::{read_entries, write_entries, Revision},
+    prelude::*,
+    shift::{read_shifts, start_of_day, total_between, Shift},
+    table::{color::Color, settings::TableSettings, table_config, DataFrameDisplay, StringTable},
+};
+
+use super::{recur::RecurRules, template::read_templates};
+
+const RES_PROJECT: &str = "Project";
+const RES_TOTAL_HOURS: &str = "Total Hours";
+const RES_SHIFTS: &str = "Number of Shifts";
+const RES_LAST_ACTIVE: &str = "Last Active";
+const RES_BUDGET: &str = "Budget";
+
+#[derive(Debug, Args)]
+pub struct ProjectArgs {
+    #[clap(subcommand)]
+    pub operation: ProjectOperation,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ProjectOperation {
+    /// List every project with its total hours, last activity, and budgets
+    List {
+        #[clap(flatten)]
+        table_settings: TableSettings,
+    },
+    /// Set the color used for a project in tables, or clear it if no color is given
+    ///
+    /// Accepts a color name (e.g. `green`), a hex code (e.g. `#ff8800`), or an ANSI color number.
+    Color {
+        /// The name of the project
+        name: String,
+        /// The color to use
+        color: Option<Color>,
+    },
+    /// Set the time budgets of a project, or clear them if none are given
+    ///
+    /// While clocked in on the project, 'punchcard status' shows how much of
+    /// each budget has been used, and a notification is shown when the
+    /// thresholds in the 'budget-alerts' setting are reached.
+    Budget {
+        /// The name of the project
+        name: String,
+        /// The most time to spend on the project in a single day (e.g. `4h`)
+        #[clap(long)]
+        daily: Option<BiDuration>,
+        /// The most time to spend on the project in total (e.g. `100h`)
+        #[clap(long)]
+        total: Option<BiDuration>,
+    },
+    /// Rename a project everywhere it is used
+    ///
+    /// Updates the entries in the data file, templates, recurring shifts,
+    /// and project settings.
+    Rename {
+        /// The current name of the project
+        old: String,
+        /// The new name of the project
+        new: String,
+    },
+    /// Combine several projects into one
+    ///
+    /// Every entry, template, and recurring shift of the given projects is
+    /// moved to the target project. The settings of the target are kept if
+    /// it has any, otherwise those of the first merged project are used.
+    Merge {
+        /// The projects to merge
+        #[clap(required = true)]
+        projects: Vec<String>,
+        /// The project to merge them into, which may be one of them or a new one
+        #[clap(long)]
+        into: String,
+    },
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProjectSettings {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<Color>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub daily_cap: Option<BiDuration>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub budget: Option<BiDuration>,
+}
+
+/// How much of one of a project's budgets has been used.
+#[derive(Debug, Clone)]
+pub struct BudgetUsage {
+    /// What kind of budget this is, e.g. "daily cap"
+    pub kind: &'static str,
+    pub used: Duration,
+    pub limit: Duration,
+    /// Whether the budget starts over every day
+    pub daily: bool,
+}
+
+impl BudgetUsage {
+    pub fn percent(&self) -> i64 {
+        match self.limit.num_seconds() {
+            0 => 100,
+            limit => self.used.num_seconds() * 100 / limit,
+        }
+    }
+
+    /// How much more time can be spent before reaching `percent` of the budget.
+    pub fn until_percent(&self, percent: u32) -> Duration {
+        self.limit * percent as i32 / 100 - self.used
+    }
+}
+
+/// How much of each budget of `project` has been used at `now`.
+pub fn budget_usage(
+    projects: &Projects,
+    shifts: &[Shift],
+    project: &str,
+    now: DateTime<Local>,
+) -> Vec<BudgetUsage> {
+    let Some(settings) = projects.get(project) else {
+        return Vec::new();
+    };
+    let shifts = shifts
+        .iter()
+        .filter(|s| s.start.project.as_deref() == Some(project))
+        .cloned()
+        .collect::<Vec<_>>();
+
+    let mut usage = Vec::new();
+    if let Some(cap) = &settings.daily_cap {
+        usage.push(BudgetUsage {
+            kind: "daily cap",
+            used: total_between(&shifts, start_of_day(now), now, now),
+            limit: **cap,
+            daily: true,
+        });
+    }
+    if let Some(budget) = &settings.budget {
+        let since_forever = DateTime::<Local>::MIN_UTC.with_timezone(&Local);
+        usage.push(BudgetUsage {
+            kind: "total budget",
+            used: total_between(&shifts, since_forever, now, now),
+            limit: **budget,
+            daily: false,
+        });
+    }
+    usage
+}
+
+pub type Projects = BTreeMap<String, ProjectSettings>;
+
+pub fn read_projects(cli_args: &Cli) -> Result<Projects> {
+    read_json_file(&cli_args.get_projects_file())
+}
+
+/// The configured color of a project, if it has one.
+pub fn project_color(projects: &Projects, project: Option<&str>) -> Option<Color> {
+    project
+        .and_then(|p| projects.get(p))
+        .and_then(|settings| settings.color)
+}
+
+#[instrument]
+pub fn manage_projects(cli_args: &Cli, ProjectArgs { operation }: &ProjectArgs) -> Result<()> {
+    use owo_colors::OwoColorize;
+
+    let mut projects = read_projects(cli_args)?;
+
+    match operation {
+        ProjectOperation::List { table_settings } => {
+            return list_projects(cli_args, &projects, table_settings);
+        }
+        ProjectOperation::Color { name, color } => {
+            let settings = projects.entry(name.clone()).or_default();
+            settings.color = *color;
+            match color {
+                Some(color) => println!(
+                    "Set the color of {} to {}",
+                    name.bold().color(owo_colors::DynColors::from(*color)),
+                    color
+                ),
+                None => println!("Cleared the color of {}", name.bold().cyan()),
+            }
+        }
+        ProjectOperation::Budget { name, daily, total } => {
+            if [daily, total]
+                .iter()
+                .any(|b| matches!(b, Some(b) if **b <= Duration::zero()))
+            {
+                return Err(eyre!("Budgets must be positive"));
+            }
+            let settings = projects.entry(name.clone()).or_default();
+            settings.daily_cap = daily.clone();
+            settings.budget = total.clone();
+            if daily.is_none() && total.is_none() {
+                println!("Cleared the budgets of {}", name.bold().cyan());
+            }
+            for (kind, limit) in [("daily cap", daily), ("total budget", total)] {
+                if let Some(limit) = limit {
+                    println!(
+                        "Set the {kind} of {} to {}",
+                        name.bold().cyan(),
+                        BiDuration::new(**limit).to_compact_string().green()
+                    );
+                }
+            }
+        }
+        ProjectOperation::Rename { old, new } => {
+            if old == new {
+                return Err(eyre!("The new name is the same as the old one"));
+            }
+            if project_exists(cli_args, &projects, new)? {
+                return Err(eyre!("There is already a project named '{new}'")).suggestion(format!(
+                    "Use 'punchcard project merge {old} --into {new}' to combine them"
+                ));
+            }
+            let changes = reassign(cli_args, &mut projects, std::slice::from_ref(old), new)?;
+            println!(
+                "Renamed {} to {} {}",
+                old.bold().cyan(),
+                new.bold().cyan(),
+                changes.bright_black()
+            );
+        }
+        ProjectOperation::Merge {
+            projects: sources,
+            into,
+        } => {
+            let sources = sources
+                .iter()
+                .filter(|p| *p != into)
+                .cloned()
+                .collect::<Vec<_>>();
+            if sources.is_empty() {
+                return Err(eyre!("There are no other projects to merge into '{into}'"));
+            }
+            let changes = reassign(cli_args, &mut projects, &sources, into)?;
+            println!(
+                "Merged {} into {} {}",
+                sources.join(", ").bold().cyan(),
+                into.bold().cyan(),
+                changes.bright_black()
+            );
+        }
+    }
+
+    write_json_file(&cli_args.get_projects_file(), &projects)
+}
+
+fn list_projects(
+    cli_args: &Cli,
+    projects: &Projects,
+    table_settings: &TableSettings,
+) -> Result<()> {
+    struct Stats<'a> {
+        name: &'a str,
+        total: Duration,
+        shifts: usize,
+        last_active: Option<DateTime<Local>>,
+    }
+
+    let now = Local::now();
+    let shifts = read_shifts(cli_args)?;
+
+    let mut stats = BTreeMap::<&str, Stats>::new();
+    let names = projects
+        .keys()
+        .map(String::as_str)
+        .chain(shifts.iter().filter_map(|s| s.start.project.as_deref()));
+    for name in names {
+        stats.entry(name).or_insert(Stats {
+            name,
+            total: Duration::zero(),
+            shifts: 0,
+            last_active: None,
+        });
+    }
+    for shift in &shifts {
+        if let Some(stats) = shift
+            .start
+            .project
+            .as_deref()
+            .and_then(|p| stats.get_mut(p))
+        {
+            stats.total = stats.total + shift.duration(now);
+            stats.shifts += 1;
+            stats.last_active = Some(shift.end_or(now));
+        }
+    }
+
+    let mut stats = stats.into_values().collect::<Vec<_>>();
+    stats.sort_by_key(|s| std::cmp::Reverse(s.total));
+
+    let budgets = stats
+        .iter()
+        .map(|s| {
+            let usage = budget_usage(projects, &shifts, s.name, now);
+            if usage.is_empty() {
+                return String::new();
+            }
+            usage
+                .iter()
+                .map(|u| {
+                    format!(
+                        "{}% of {} {}",
+                        u.percent(),
+                        BiDuration::new(u.limit).to_compact_string(),
+                        u.kind
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join(", ")
+        })
+        .collect::<Vec<_>>();
+
+    let df = StringTable::new()
+        .with_column(
+            RES_PROJECT,
+            "str",
+            stats.iter().map(|s| s.name.to_string()).collect(),
+        )
+        .with_column(
+            RES_TOTAL_HOURS,
+            "str",
+            stats
+                .iter()
+                .map(|s| BiDuration::new(s.total).to_friendly_absolute_string())
+                .collect(),
+        )
+        .with_column(
+            RES_SHIFTS,
+            "u64",
+            stats.iter().map(|s| s.shifts.to_string()).collect(),
+        )
+        .with_column(
+            RES_LAST_ACTIVE,
+            "str",
+            stats
+                .iter()
+                .map(|s| {
+                    s.last_active
+                        .map(|t| t.format("%d %B %Y").to_string())
+                        .unwrap_or_default()
+                })
+                .collect(),
+        )
+        .with_column(RES_BUDGET, "str", budgets);
+
+    let row_colors = stats
+        .iter()
+        .map(|s| project_color(projects, Some(s.name)))
+        .collect();
+
+    let display = DataFrameDisplay::new(
+        &df,
+        table_settings,
+        table_config(cli_args),
+        cli_args.accessible,
+    )
+    .with_row_colors(row_colors)
+    .with_caption("all projects");
+    println!("{display}");
+
+    if stats.is_empty() {
+        use owo_colors::OwoColorize;
+        println!(
+            "{}",
+            "No projects yet. Use 'punchcard in --project <name>' to track time on one.".yellow()
+        );
+    }
+
+    Ok(())
+}
+
+/// Checks if a project is used anywhere, or has settings.
+fn project_exists(cli_args: &Cli, projects: &Projects, name: &str) -> Result<bool> {
+    if projects.contains_key(name) {
+        return Ok(true);
+    }
+    let templates = read_templates(cli_args)?;
+    let rules: RecurRules = read_json_file(&cli_args.get_recurring_file())?;
+    let entries = if cli_args.get_output_file().exists() {
+        read_entries(cli_args)?
+    } else {
+        Vec::new()
+    };
+    let is_name = |p: &Option<String>| p.as_deref() == Some(name);
+    Ok(entries.iter().any(|e| is_name(&e.project))
+        || templates.values().any(|t| is_name(&t.project))
+        || rules.values().any(|r| is_name(&r.project)))
+}
+
+/// Moves everything of the `from` projects to the `to` project.
+///
+/// The data file is rewritten in one step, so it never contains a mix of
+/// the old and new names. Returns a summary of what was changed.
+fn reassign(cli_args: &Cli, projects: &mut Projects, from: &[String], to: &str) -> Result<String> {
+    let rename = |project: &mut Option<String>| -> usize {
+        match project {
+            Some(p) if from.contains(p) => {
+                *p = to.to_string();
+                1
+            }
+            _ => 0,
+        }
+    };
+
+    let revision = Revision::current(cli_args)?;
+    let mut entries = if cli_args.get_output_file().exists() {
+        read_entries(cli_args)?
+    } else {
+        Vec::new()
+    };
+    let mut templates = read_templates(cli_args)?;
+    let mut rules: RecurRules = read_json_file(&cli_args.get_recurring_file())?;
+
+    let entry_count: usize = entries.iter_mut().map(|e| rename(&mut e.project)).sum();
+    let template_count: usize = templates.values_mut().map(|t| rename(&mut t.project)).sum();
+    let rule_count: usize = rules.values_mut().map(|r| rename(&mut r.project)).sum();
+
+    let removed: Vec<_> = from.iter().filter_map(|p| projects.remove(p)).collect();
+    let had_settings = !removed.is_empty();
+    if let Some(first) = removed.into_iter().next() {
+        projects.entry(to.to_string()).or_insert(first);
+    }
+
+    if entry_count + template_count + rule_count == 0 && !had_settings {
+        return Err(ErrorCode::NotFound.report(format!(
+            "There is no project named {}",
+            from.iter()
+                .map(|p| format!("'{p}'"))
+                .collect::<Vec<_>>()
+                .join(" or ")
+        )));
+    }
+
+    if entry_count > 0 {
+        write_entries(cli_args, &entries, revision.as_ref())?;
+    }
+    if template_count > 0 {
+        write_json_file(&cli_args.get_templates_file(), &templates)?;
+    }
+    if rule_count > 0 {
+        write_json_file(&cli_args.get_recurring_file(), &rules)?;
+    }
+
+    Ok(format!(
+        "({entry_count} entries, {template_count} templates, {rule_count} recurring shifts)"
+    ))
+}
@@ -0,0 +1,292 @@
+// Copyright (C) 2023 Campbell M. Cole
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::BTreeMap;
+
+use chrono::{Datelike, NaiveTime, Weekday};
+
+use crate::{
+    csv::{read_entries, write_entries, Revision},
+    prelude::*,
+};
+
+use super::clock::EntryMetadataArgs;
+
+#[derive(Debug, Args)]
+pub struct RecurArgs {
+    #[clap(subcommand)]
+    pub operation: RecurOperation,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum RecurOperation {
+    /// Add or replace a recurring shift rule
+    Add {
+        /// The name of the rule
+        name: String,
+        /// The days of the week the shift occurs on (e.g. `tue,thu`)
+        #[clap(long, value_delimiter = ',', required = true)]
+        days: Vec<Weekday>,
+        /// The time the shift starts (e.g. `18:00`)
+        #[clap(long, value_parser = parse_time_of_day)]
+        start: NaiveTime,
+        /// The time the shift ends (e.g. `20:00`)
+        #[clap(long, value_parser = parse_time_of_day)]
+        end: NaiveTime,
+        #[clap(flatten)]
+        metadata: EntryMetadataArgs,
+    },
+    /// List all recurring shift rules
+    List,
+    /// Remove a recurring shift rule
+    Remove {
+        /// The name of the rule to remove
+        name: String,
+    },
+    /// Add the shifts of every rule for the given month to the data file
+    ///
+    /// Shifts which overlap an existing shift are skipped. Shifts which
+    /// have not ended yet are skipped unless '--include-future' is given,
+    /// because entries in the future prevent clocking in or out normally.
+    Apply {
+        /// The month to add the shifts to
+        ///
+        /// Accepts a month name (e.g. `January`) or a number (e.g. `1`)
+        /// or `current`, `previous`, or `next`
+        #[clap(short, long, default_value_t = Default::default())]
+        month: Month,
+        /// Only apply the rule with this name
+        #[clap(short, long)]
+        rule: Option<String>,
+        /// Also add shifts which have not ended yet
+        #[clap(long, default_value_t = false)]
+        include_future: bool,
+        /// Print the shifts that would be added without changing the data file
+        #[clap(long, default_value_t = false)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecurRule {
+    pub days: Vec<Weekday>,
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+    #[serde(default)]
+    pub project: Option<String>,
+    #[serde(default)]
+    pub tags: Tags,
+}
+
+pub type RecurRules = BTreeMap<String, RecurRule>;
+
+#[instrument]
+pub fn manage_recurring(cli_args: &Cli, RecurArgs { operation }: &RecurArgs) -> Result<()> {
+    use owo_colors::OwoColorize;
+
+    let rules_file = cli_args.get_recurring_file();
+    let mut rules: RecurRules = read_json_file(&rules_file)?;
+
+    match operation {
+        RecurOperation::Add {
+            name,
+            days,
+            start,
+            end,
+            metadata,
+        } => {
+            if end <= start {
+                return Err(eyre!(
+                    "The end of a recurring shift must be after its start"
+                ))
+                .suggestion("Shifts which cross midnight are not supported");
+            }
+            let rule = RecurRule {
+                days: days.clone(),
+                start: *start,
+                end: *end,
+                project: metadata.project.clone(),
+                tags: metadata.tags(),
+            };
+            rules.insert(name.clone(), rule);
+            println!("Saved recurring shift {}", name.bold().cyan());
+        }
+        RecurOperation::List => {
+            if rules.is_empty() {
+                println!("{}", "No recurring shifts have been added yet.".red());
+            }
+            for (name, rule) in &rules {
+                println!(
+                    "{} {} {} {} {}",
+                    name.bold().cyan(),
+                    rule.days
+                        .iter()
+                        .map(|d| d.to_string())
+                        .collect::<Vec<_>>()
+                        .join(",")
+                        .yellow(),
+                    format!(
+                        "{}-{}",
+                        rule.start.format("%H:%M"),
+                        rule.end.format("%H:%M")
+                    )
+                    .magenta(),
+                    rule.project
+                        .as_deref()
+                        .unwrap_or("(no project)")
+                        .bright_blue(),
+                    rule.tags
+                        .iter()
+                        .map(|t| format!("#{t}"))
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                        .bright_black(),
+                );
+            }
+            return Ok(());
+        }
+        RecurOperation::Remove { name } => {
+            if rules.remove(name).is_none() {
+                return Err(ErrorCode::NotFound
+                    .report(format!("There is no recurring shift named '{name}'")));
+            }
+            println!("Removed recurring shift {}", name.bold().cyan());
+        }
+        RecurOperation::Apply {
+            month,
+            rule,
+            include_future,
+            dry_run,
+        } => {
+            if let Some(rule) = rule {
+                if !rules.contains_key(rule) {
+                    return Err(ErrorCode::NotFound
+                        .report(format!("There is no recurring shift named '{rule}'")));
+                }
+            }
+            let selected = rules
+                .iter()
+                .filter(|(name, _)| rule.as_ref().map(|r| r == *name).unwrap_or(true))
+                .collect::<Vec<_>>();
+            return apply_rules(cli_args, &selected, month, *include_future, *dry_run);
+        }
+    }
+
+    write_json_file(&rules_file, &rules)
+}
+
+fn apply_rules(
+    cli_args: &Cli,
+    rules: &[(&String, &RecurRule)],
+    month: &Month,
+    include_future: bool,
+    dry_run: bool,
+) -> Result<()> {
+    use owo_colors::OwoColorize;
+
+    let Some(month_start) = month.as_date() else {
+        return Err(eyre!("Recurring shifts must be applied to a single month"));
+    };
+
+    let revision = Revision::current(cli_args)?;
+    let mut entries = if cli_args.get_output_file().exists() {
+        read_entries(cli_args)?
+    } else {
+        Vec::new()
+    };
+    entries.sort_by_key(|e| e.timestamp);
+
+    let now = Local::now();
+    let mut added = 0;
+    let mut skipped = 0;
+
+    let mut day = month_start.date_naive();
+    while day.month() == month_start.month() {
+        for (name, rule) in rules {
+            if !rule.days.contains(&day.weekday()) {
+                continue;
+            }
+
+            let (Some(start), Some(end)) = (
+                Local
+                    .from_local_datetime(&day.and_time(rule.start))
+                    .single(),
+                Local.from_local_datetime(&day.and_time(rule.end)).single(),
+            ) else {
+                warn!("skipping {name} on {day} because of a DST transition");
+                continue;
+            };
+
+            let shift = format!(
+                "{} {} {} -> {}",
+                name.bold().cyan(),
+                start.format(PRETTY_DATE),
+                start.format("%H:%M").magenta(),
+                end.format("%H:%M").magenta(),
+            );
+
+            if end > now && !include_future {
+                continue;
+            }
+
+            if collides(&entries, start, end) {
+                println!(
+                    "{} {shift}",
+                    "Skipped (overlaps an existing shift)".yellow()
+                );
+                skipped += 1;
+                continue;
+            }
+
+            println!("{} {shift}", "Added".green());
+            added += 1;
+
+            for (entry_type, timestamp) in [(EntryType::ClockIn, start), (EntryType::ClockOut, end)]
+            {
+                let mut entry = Entry::new(entry_type, timestamp, EntrySource::Recurring);
+                entry.project = rule.project.clone();
+                entry.tags = rule.tags.clone();
+                let idx = entries.partition_point(|e| e.timestamp <= timestamp);
+                entries.insert(idx, entry);
+            }
+        }
+        day = day.succ_opt().unwrap();
+    }
+
+    println!(
+        "{} shifts added, {} skipped{}",
+        added.to_string().green().bold(),
+        skipped.to_string().yellow().bold(),
+        if dry_run { " (dry run)" } else { "" }
+    );
+
+    if added > 0 && !dry_run {
+        write_entries(cli_args, &entries, revision.as_ref())?;
+    }
+
+    Ok(())
+}
+
+/// Checks if a shift from `start` to `end` would overlap any existing shift.
+/// Shifts are half-open, so one may start right when another ends.
+///
+/// `entries` must be sorted by timestamp.
+pub fn collides(entries: &[Entry], start: DateTime<Local>, end: DateTime<Local>) -> bool {
+    // a clock out at `start` ends the shift before, so it's counted as before it
+    let idx = entries.partition_point(|e| e.timestamp <= start);
+    let clocked_in_at_start = idx > 0 && entries[idx - 1].entry_type == EntryType::ClockIn;
+    let entry_within = entries.get(idx).map(|e| e.timestamp < end).unwrap_or(false);
+    clocked_in_at_start || entry_within
+}
@@ -13,46 +13,375 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
-use polars::prelude::*;
+// the report settings, periods, and columns are shared by both engines.
+// the polars engine lives in `grouped`, the pure Rust one in `simple`
+
+use chrono::{Datelike, Months, NaiveTime, Weekday};
+use clap::ValueEnum;
+#[cfg(feature = "reports")]
+use polars::prelude::{BooleanChunked, CsvWriter, DataFrame, NewChunkedArray, SerWriter};
+
+use std::{collections::BTreeMap, num::NonZeroUsize, path::PathBuf};
 
 // for some reason TimeZone needs to be explicitly imported
 use crate::{
+    command::{
+        absence::{absence_marker, is_absent, read_absences, Absence},
+        project::{project_color, read_projects},
+    },
+    config::{read_config, Schedule},
     prelude::{TimeZone, *},
-    table::{settings::TableSettings, DataFrameDisplay},
+    receipt::{record_receipt, HashingWriter},
+    shift::{read_shifts, start_of_date, start_of_week, Shift},
+    table::{settings::TableSettings, table_config, DataFrameDisplay, StringTable, TableSource},
 };
 
+#[cfg(feature = "charts")]
+mod chart;
+#[cfg(feature = "reports")]
 mod copyable;
-mod daily;
-mod weekly;
+#[cfg(feature = "reports")]
+mod grouped;
+mod narrative;
+mod simple;
+mod snapshot;
 
-const TIME_UNIT: TimeUnit = TimeUnit::Nanoseconds;
+const RES_TOTAL_HOURS: &str = "Total Hours";
+/// The part of the total hours spent travelling, only shown if there was any
+const RES_TRAVEL_HOURS: &str = "Travel Hours";
+const RES_DATE: &str = "Date";
+const RES_WEEK_OF: &str = "Week Of";
+const RES_WEEK_END: &str = "Week End";
+/// The ISO 8601 week, e.g. `2024-W09`, only shown with '--week-numbers'
+const RES_WEEK_NUMBER: &str = "Week #";
+const ISO_WEEK_FORMAT: &str = "%G-W%V";
+/// How many days of a week are within the report, only shown if a week can be cut off
+const RES_DAYS_COVERED: &str = "Days Covered";
+/// Added to the start of a week which isn't wholly within the report
+const PARTIAL_WEEK_MARKER: &str = "*";
+const RES_MONTH: &str = "Month";
+const RES_PROJECT: &str = "Project";
+const RES_TAG: &str = "Tag";
+const RES_LOCATION: &str = "Location";
+const RES_SHIFTS: &str = "Number of Shifts";
+const RES_AVERAGE_SHIFT_DURATION: &str = "Avg. Shift Duration";
 
-const COL_TIMESTAMP: &str = "timestamp";
-const COL_ENTRY_TYPE: &str = "entry_type";
-const COL_DURATION: &str = "duration";
+/// The middle shift duration, only shown with '--median-shift'
+const RES_MEDIAN_SHIFT_DURATION: &str = "Median Shift Duration";
+/// The longest shift, only shown with '--longest-shift'
+const RES_LONGEST_SHIFT: &str = "Longest Shift";
+/// The average of the total hours of the last few periods, only shown with '--rolling'
+#[cfg(feature = "reports")]
+const RES_ROLLING_AVERAGE: &str = "Rolling Avg. Hours";
+/// The bars of the days of each week, only shown with '--sparkline'
+const RES_SPARKLINE: &str = "Days";
+/// The bars of a sparkline, from the shortest to the longest day
+const SPARKLINE_BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+/// The time worked per day worked, only shown with '--daily-average'
+const RES_DAILY_AVERAGE: &str = "Avg. Daily Hours";
+/// The time worked as a percentage of the scheduled time, only shown with '--utilization'
+const RES_UTILIZATION: &str = "Utilization (%)";
 
-const NANOSECOND_OVERFLOW_MESSAGE: &str = "why are you using this 500 years in the future?";
+/// Shown in place of a missing project, tag, or location
+const NONE_LABEL: &str = "(none)";
 
 #[derive(Debug, Args)]
 pub struct ReportSettings {
     #[clap(subcommand)]
     pub report_type: Option<ReportType>,
-    /// Save the report to a file, or '-' for stdout (ignores the '--num-rows' flag)
+    /// Save the report to a file, or '-' for stdout (ignores the '--num-rows' flag).
+    /// With '--copyable', the page is saved instead of opened.
     #[clap(short = 'o', long, default_value = None)]
     pub output_file: Option<Destination>,
+    /// Print the SHA-256 of the saved report and keep it as a receipt,
+    /// so 'punchcard verify' can later show the file wasn't changed
+    #[clap(long, default_value_t = false, requires = "output_file")]
+    pub receipt: bool,
     /// Only print the table and nothing else
     #[clap(short = 'j', long, default_value_t = false)]
     pub just_table: bool,
+    /// Print the table as it is, even if it doesn't fit in the terminal.
+    /// Otherwise a long table is shown in $PAGER, or `less`
+    #[clap(long, default_value_t = false)]
+    pub no_pager: bool,
     /// Print exact durations instead of rounded
     #[clap(long = "exact", default_value_t = false)]
     pub exact_durations: bool,
     /// Generate a page that copies the rich-text report to the clipboard
     #[clap(long = "copyable", default_value_t = false)]
     pub copyable: bool,
+    /// Also print a few sentences on where the time went. They're filled in
+    /// from a template unless `narrative` is set in the config, which sends
+    /// the totals and notes to an API to write them.
+    #[clap(long, default_value_t = false, conflicts_with = "copyable")]
+    pub narrative: bool,
+    /// Use the `template.md` and `template.html` in this folder for
+    /// '--copyable', instead of the built in ones
+    #[clap(long, requires = "copyable")]
+    pub template_dir: Option<PathBuf>,
+    /// The command which opens the page of '--copyable', e.g. `firefox`.
+    /// Defaults to `browser` in the config, or the system's default opener.
+    #[clap(long, requires = "copyable", conflicts_with = "output_file")]
+    pub browser: Option<String>,
+    /// Only print the path of the page of '--copyable' instead of opening it
+    #[clap(
+        long,
+        default_value_t = false,
+        requires = "copyable",
+        conflicts_with = "browser"
+    )]
+    pub no_open: bool,
+    /// Only include shifts where either entry was created this way
+    /// (e.g. `cli`, `template`, `recur`, `auto-clockout`)
+    #[clap(long)]
+    pub source: Option<EntrySource>,
+    /// Only include shifts on this project. The rows are shown in the
+    /// project's color if one was set with 'punchcard project color'.
+    #[clap(short = 'p', long)]
+    pub project: Option<String>,
+    /// What to group the shifts by, e.g. 'week' or 'week,project'.
+    /// Defaults to 'week' for weekly reports and 'day' for daily reports.
+    #[clap(short = 'g', long, value_enum, value_delimiter = ',')]
+    pub group_by: Vec<GroupKey>,
+    /// Sort the rows by a column instead of chronologically, e.g. 'Total Hours:desc'.
+    /// Can be applied multiple times to break ties.
+    #[clap(long, action = clap::ArgAction::Append)]
+    pub sort: Vec<SortKey>,
+    /// When the days of the report start, e.g. `18:00` so that night
+    /// shifts are counted on the day they started. Defaults to midnight.
+    #[clap(long, value_parser = parse_time_of_day)]
+    pub day_boundary: Option<NaiveTime>,
+    /// The first day of the weeks of the report, e.g. `sunday`. Defaults to Monday.
+    #[clap(long)]
+    pub week_start: Option<Weekday>,
+    /// Add a column with the median shift duration, which isn't skewed by a
+    /// few very long or short shifts like the average
+    #[clap(long, default_value_t = false)]
+    pub median_shift: bool,
+    /// Add a column with the duration of the longest shift
+    #[clap(long, default_value_t = false)]
+    pub longest_shift: bool,
+    /// Add a column with the average total hours of the last N periods, to
+    /// show the trend behind week-to-week noise
+    #[clap(long, value_name = "N")]
+    pub rolling: Option<NonZeroUsize>,
+    /// Also draw the total hours of each period as a bar chart, saved as SVG
+    /// or PNG depending on the extension of the file
+    #[clap(long)]
+    pub chart_file: Option<PathBuf>,
+    /// Add a column with a bar for the hours worked on each day of the week,
+    /// e.g. `▅▇▃▆▂  `
+    #[clap(long, default_value_t = false)]
+    pub sparkline: bool,
+    /// Add a column with the average time worked on the days worked
+    #[clap(long, default_value_t = false)]
+    pub daily_average: bool,
+    /// Add a column with the time worked as a percentage of the scheduled
+    /// time (needs `schedule` in the config and grouping by a period)
+    #[clap(long, default_value_t = false)]
+    pub utilization: bool,
+    /// Add a 'Week #' column with the ISO 8601 week (e.g. `2024-W09`)
+    /// to reports grouped by week
+    #[clap(long, default_value_t = false)]
+    pub week_numbers: bool,
+    /// Show columns under other names, e.g. 'Total Hours=Gesamtstunden,Week Of=Woche'.
+    /// Overrides `labels` in the config. '--sort' and '--where' still use the
+    /// original names.
+    #[clap(long, value_delimiter = ',')]
+    pub labels: Vec<ColumnLabel>,
+    /// Only include rows matching a condition on a column, e.g. 'Total Hours > 35h'.
+    /// Can be applied multiple times, rows must match all of them.
+    #[clap(long = "where", action = clap::ArgAction::Append)]
+    pub filters: Vec<FilterExpr>,
+    /// Only show the rows which are new or differ from when this kind of
+    /// report was last saved with '-o', e.g. to resubmit a corrected timesheet
+    #[clap(long, default_value_t = false, conflicts_with = "copyable")]
+    pub changed_only: bool,
+    /// Skip rows with a timestamp that can't be parsed instead of failing,
+    /// and print how many were skipped
+    #[clap(long, default_value_t = false)]
+    pub lenient: bool,
+    /// How to compute the report. The simple engine doesn't need polars,
+    /// but doesn't support '--sort', '--where', or '--copyable'
+    #[clap(long, value_enum, default_value_t = ReportEngine::default())]
+    pub engine: ReportEngine,
     #[clap(flatten)]
     pub table_settings: TableSettings,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ReportEngine {
+    /// Use polars, which supports every option (needs the `reports` feature)
+    Polars,
+    /// Add up the shifts directly, for builds without polars
+    Simple,
+}
+
+impl Default for ReportEngine {
+    fn default() -> Self {
+        if cfg!(feature = "reports") {
+            Self::Polars
+        } else {
+            Self::Simple
+        }
+    }
+}
+
+#[derive(Debug, Clone, Args, Default)]
+pub struct WeeklyReportArgs {
+    #[clap(short, long, default_value_t = Default::default())]
+    /// The month to generate the report for
+    ///
+    /// Accepts a month name (e.g. `January`) or a number (e.g. `1`)
+    /// or `current`, `previous`, or `next`
+    pub month: Month,
+    #[clap(short, long, default_value_t = false)]
+    /// Include shifts that occurred in a previous/upcoming month but
+    /// spill in to or out of this month
+    pub spill_over: bool,
+    #[clap(long, default_value_t = false)]
+    /// Add a column for each day of the week with the hours worked on it,
+    /// like a paper timesheet
+    pub pivot_days: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum GroupKey {
+    Day,
+    Week,
+    Month,
+    Project,
+    Tag,
+    /// The site from the `location:` tag
+    Location,
+}
+
+impl GroupKey {
+    fn column(&self) -> &'static str {
+        match self {
+            GroupKey::Day => RES_DATE,
+            GroupKey::Week => RES_WEEK_OF,
+            GroupKey::Month => RES_MONTH,
+            GroupKey::Project => RES_PROJECT,
+            GroupKey::Tag => RES_TAG,
+            GroupKey::Location => RES_LOCATION,
+        }
+    }
+
+    /// The length of the period for keys where it is always the same
+    fn fixed_length(&self) -> Option<chrono::Duration> {
+        match self {
+            GroupKey::Day => Some(chrono::Duration::days(1)),
+            GroupKey::Week => Some(chrono::Duration::weeks(1)),
+            _ => None,
+        }
+    }
+
+    fn is_time_based(&self) -> bool {
+        matches!(self, GroupKey::Day | GroupKey::Week | GroupKey::Month)
+    }
+
+    /// The time based key with the shortest periods, which each row of a
+    /// report grouped by it is one of.
+    fn shortest_period(keys: &[GroupKey]) -> Option<GroupKey> {
+        keys.iter()
+            .filter(|k| k.is_time_based())
+            .min_by_key(|k| k.fixed_length().unwrap_or(chrono::Duration::days(31)))
+            .copied()
+    }
+
+    /// The end of the group of a time based key which starts at `start`.
+    fn group_end(&self, start: DateTime<Local>) -> DateTime<Local> {
+        match self.fixed_length() {
+            Some(length) => start + length,
+            None => start
+                .checked_add_months(Months::new(1))
+                .unwrap_or(start + chrono::Duration::days(31)),
+        }
+    }
+}
+
+/// The period covered by a report.
+#[derive(Debug, Clone, Copy)]
+pub struct ReportPeriod {
+    /// The start (inclusive) and end (exclusive) of the period, or `None` for all time
+    pub range: Option<(DateTime<Local>, DateTime<Local>)>,
+    /// Include every shift of the groups which overlap the period,
+    /// instead of only the shifts within it
+    pub spill_over: bool,
+}
+
+impl ReportPeriod {
+    /// Whether the weeks at the edges of the period can be cut off, i.e. it
+    /// doesn't start and end at the start of a week.
+    fn has_partial_weeks(&self, keys: &[GroupKey], anchor: Anchor) -> bool {
+        match self.range {
+            Some((from, to)) if keys.contains(&GroupKey::Week) && !self.spill_over => {
+                anchor.start_of_week(from) != from || anchor.start_of_week(to) != to
+            }
+            _ => false,
+        }
+    }
+
+    /// How many days of the week starting at `week_start` are within the period.
+    fn days_covered(&self, week_start: DateTime<Local>) -> i64 {
+        let week_end = week_start + chrono::Duration::weeks(1);
+        let (start, end) = match self.range {
+            Some((from, to)) => (week_start.max(from), week_end.min(to)),
+            None => (week_start, week_end),
+        };
+        // rounded, because days are an hour shorter or longer across a DST change
+        ((end - start) + chrono::Duration::hours(12)).num_days()
+    }
+
+    /// The time based key whose groups are kept whole with spill over, and their length.
+    ///
+    /// Months never partially overlap a report, so they don't need this.
+    fn spill_over_key(&self, keys: &[GroupKey]) -> Option<(GroupKey, chrono::Duration)> {
+        keys.iter()
+            .find(|k| k.is_time_based())
+            .filter(|_| self.spill_over)
+            .and_then(|k| Some((*k, k.fixed_length()?)))
+    }
+}
+
+/// Where the days and weeks of a report start, for shifts which don't fit
+/// into days starting at midnight.
+#[derive(Debug, Clone, Copy)]
+pub struct Anchor {
+    pub day_boundary: NaiveTime,
+    pub week_start: Weekday,
+}
+
+impl Default for Anchor {
+    fn default() -> Self {
+        Self {
+            day_boundary: NaiveTime::MIN,
+            week_start: Weekday::Mon,
+        }
+    }
+}
+
+impl Anchor {
+    /// How long after midnight (or after Monday, for weeks) the groups of a
+    /// time based key start.
+    fn offset(&self, key: GroupKey) -> chrono::Duration {
+        let day = self.day_boundary - NaiveTime::MIN;
+        match key {
+            GroupKey::Week => {
+                chrono::Duration::days(self.week_start.num_days_from_monday() as i64) + day
+            }
+            _ => day,
+        }
+    }
+
+    /// The start of the week containing `time`.
+    fn start_of_week(&self, time: DateTime<Local>) -> DateTime<Local> {
+        let offset = self.offset(GroupKey::Week);
+        start_of_week(time - offset) + offset
+    }
+}
+
 #[derive(Debug, Clone, Subcommand)]
 pub enum ReportType {
     /// Generate a report by week for a given month
@@ -67,87 +396,435 @@ impl Default for ReportType {
     }
 }
 
-fn map_duration_to_str(s: Series) -> PolarsResult<Option<Series>> {
-    Ok(Some(
-        s.iter()
-            .filter_map(|x| {
-                let AnyValue::Duration(duration, time_unit) = x else {
-                    return None;
-                };
-                assert_eq!(time_unit, TIME_UNIT);
-                let duration = chrono::Duration::nanoseconds(duration);
-                let duration = BiDuration::new(duration);
-                let duration_str = duration.to_friendly_absolute_string();
-                Some(duration_str)
-            })
-            .collect(),
-    ))
-}
-
-fn map_duration_to_str_exact(s: Series) -> PolarsResult<Option<Series>> {
-    Ok(Some(
-        s.iter()
-            .filter_map(|x| {
-                let AnyValue::Duration(duration, time_unit) = x else {
-                    return None;
-                };
-                assert_eq!(time_unit, TIME_UNIT);
-                let duration = chrono::Duration::nanoseconds(duration);
-                let duration = BiDuration::new(duration);
-                let (duration, _) = duration.to_std_duration();
-                let duration_str = humantime::format_duration(duration);
-                Some(duration_str.to_string())
-            })
-            .collect(),
-    ))
-}
-
-macro_rules! map_fn {
-    ($settings:ident) => {
-        if $settings.exact_durations {
-            crate::command::report::map_duration_to_str_exact
-        } else {
-            crate::command::report::map_duration_to_str
+impl ReportType {
+    /// The shifts the report covers. Months start at the day boundary of the anchor.
+    fn period(&self, anchor: Anchor) -> ReportPeriod {
+        match self {
+            ReportType::Weekly(args) => ReportPeriod {
+                range: args.month.as_date().map(|month_start| {
+                    let month_end = month_start
+                        .checked_add_months(Months::new(1))
+                        .expect("adding a month to the first of a month is always valid");
+                    debug_assert_eq!(month_end.day(), 1);
+                    let offset = anchor.offset(GroupKey::Month);
+                    (month_start + offset, month_end + offset)
+                }),
+                spill_over: args.spill_over,
+            },
+            ReportType::Daily => {
+                let this_week_start = anchor.start_of_week(Local::now());
+                ReportPeriod {
+                    range: Some((this_week_start, this_week_start + chrono::Duration::days(7))),
+                    spill_over: false,
+                }
+            }
+        }
+    }
+
+    fn default_key(&self) -> GroupKey {
+        match self {
+            ReportType::Weekly(_) => GroupKey::Week,
+            ReportType::Daily => GroupKey::Day,
         }
+    }
+
+    /// A short description of the period the report covers.
+    pub fn describe_period(&self, anchor: Anchor) -> String {
+        match self {
+            ReportType::Weekly(args) => match args.month.as_date() {
+                Some(month_start) if args.spill_over => {
+                    format!("{} (with spill over)", month_start.format("%B %Y"))
+                }
+                Some(month_start) => month_start.format("%B %Y").to_string(),
+                None => "all time".into(),
+            },
+            ReportType::Daily => {
+                let week_start = anchor.start_of_week(Local::now());
+                format!("week of {}", week_start.format("%d %B %Y"))
+            }
+        }
+    }
+}
+
+/// Parses the arguments of `punchcard report` given somewhere other than the command line.
+pub fn parse_report_args(args: &[String]) -> Result<ReportSettings> {
+    use clap::{Command, FromArgMatches};
+    let command = ReportSettings::augment_args(Command::new("report").no_binary_name(true));
+    command
+        .try_get_matches_from(args)
+        .and_then(|matches| ReportSettings::from_arg_matches(&matches))
+        .wrap_err_with(|| format!("Invalid report arguments: {}", args.join(" ")))
+}
+
+/// The scheduled working time of the group of `key` which starts at
+/// `start`, within the period. Days with an absence aren't scheduled.
+fn scheduled_time(
+    schedule: &Schedule,
+    absences: &[Absence],
+    key: GroupKey,
+    start: DateTime<Local>,
+    period: &ReportPeriod,
+    anchor: Anchor,
+) -> chrono::Duration {
+    let end = key.group_end(start);
+    let (from, to) = match period.range {
+        Some((from, to)) => (start.max(from), end.min(to)),
+        None => (start, end),
     };
+
+    // the days start at the day boundary, like the groups
+    let boundary = anchor.offset(GroupKey::Day);
+    let mut scheduled = chrono::Duration::zero();
+    let mut date = (from - boundary).date_naive();
+    loop {
+        if let Some(day_start) = start_of_date(date).map(|d| d + boundary) {
+            if day_start >= to {
+                break;
+            }
+            if day_start >= from && !is_absent(absences, date) {
+                if let Some((start, end)) = schedule.workday(day_start) {
+                    scheduled = scheduled + (end - start);
+                }
+            }
+        }
+        match date.succ_opt() {
+            Some(next) => date = next,
+            None => break,
+        }
+    }
+    scheduled
+}
+
+/// The kinds of the absences during the group of `key` which starts at
+/// `start`, e.g. ` (vacation)`.
+fn group_absence_marker(
+    absences: &[Absence],
+    key: GroupKey,
+    start: DateTime<Local>,
+) -> Option<String> {
+    absence_marker(
+        absences,
+        start.date_naive(),
+        key.group_end(start).date_naive(),
+    )
+}
+
+/// The time worked as a whole percentage of the scheduled time, or `None`
+/// if nothing was scheduled.
+fn utilization_percent(worked: chrono::Duration, scheduled: chrono::Duration) -> Option<i64> {
+    let scheduled = scheduled.num_seconds();
+    (scheduled > 0).then(|| (worked.num_seconds() as f64 * 100.0 / scheduled as f64).round() as i64)
+}
+
+/// Draws the time worked on each day as a bar, as tall as it is compared to
+/// `longest`. Days without any work are left empty.
+fn sparkline(
+    days: impl IntoIterator<Item = chrono::Duration>,
+    longest: chrono::Duration,
+) -> String {
+    let longest = longest.num_seconds().max(1);
+    days.into_iter()
+        .map(|worked| match worked.num_seconds() {
+            seconds if seconds <= 0 => ' ',
+            seconds => {
+                // rounded up, so that even short days get the lowest bar
+                let level = (seconds * SPARKLINE_BARS.len() as i64 + longest - 1) / longest;
+                SPARKLINE_BARS[(level as usize).clamp(1, SPARKLINE_BARS.len()) - 1]
+            }
+        })
+        .collect()
+}
+
+/// Formats a duration for display in a report.
+fn format_duration(duration: chrono::Duration, exact: bool) -> String {
+    let duration = BiDuration::new(duration);
+    match exact {
+        true => humantime::format_duration(duration.to_std_duration().0).to_string(),
+        false => duration.to_friendly_absolute_string(),
+    }
+}
+
+/// A report which is ready to be displayed, made by either engine.
+pub(crate) enum Report {
+    #[cfg(feature = "reports")]
+    Frame(DataFrame),
+    Simple(StringTable),
+}
+
+impl Report {
+    pub(crate) fn table(&self) -> &dyn TableSource {
+        match self {
+            #[cfg(feature = "reports")]
+            Report::Frame(df) => df,
+            Report::Simple(table) => table,
+        }
+    }
+
+    /// Renames the columns which have a label.
+    fn relabel(self, labels: &BTreeMap<String, String>) -> Result<Self> {
+        Ok(match self {
+            #[cfg(feature = "reports")]
+            Report::Frame(mut df) => {
+                relabel_frame(&mut df, labels)?;
+                Report::Frame(df)
+            }
+            Report::Simple(mut table) => {
+                for (column, label) in labels {
+                    table.rename_column(column, label);
+                }
+                Report::Simple(table)
+            }
+        })
+    }
+
+    /// Keeps the rows where `mask` is true.
+    fn retain_rows(self, mask: &[bool]) -> Result<Self> {
+        Ok(match self {
+            #[cfg(feature = "reports")]
+            Report::Frame(df) => Report::Frame(df.filter(&BooleanChunked::from_slice("", mask))?),
+            Report::Simple(mut table) => {
+                table.retain_rows(mask);
+                Report::Simple(table)
+            }
+        })
+    }
+}
+
+/// Renames the columns of `df` which have a label.
+#[cfg(feature = "reports")]
+fn relabel_frame(df: &mut DataFrame, labels: &BTreeMap<String, String>) -> Result<()> {
+    for (column, label) in labels {
+        if df.get_column_names().contains(&column.as_str()) {
+            df.rename(column, label)?;
+        }
+    }
+    Ok(())
+}
+
+impl ReportSettings {
+    /// The names to show instead of the columns, from the config and '--labels'.
+    fn labels(&self, cli_args: &Cli) -> Result<BTreeMap<String, String>> {
+        let mut labels = read_config(cli_args)?.labels;
+        for ColumnLabel { column, label } in &self.labels {
+            labels.insert(column.clone(), label.clone());
+        }
+        Ok(labels)
+    }
+
+    /// The keys the shifts are grouped by, and the period of the report.
+    fn scope(&self) -> (Vec<GroupKey>, ReportPeriod) {
+        let report_type = self.report_type.as_ref().cloned().unwrap_or_default();
+        let keys = if self.group_by.is_empty() {
+            vec![report_type.default_key()]
+        } else {
+            self.group_by.clone()
+        };
+        (keys, report_type.period(self.anchor()))
+    }
+
+    /// The schedule, the absences which are left out of it, and the time
+    /// based key to measure '--utilization' against.
+    fn utilization(
+        &self,
+        cli_args: &Cli,
+        keys: &[GroupKey],
+    ) -> Result<Option<(Schedule, Vec<Absence>, GroupKey)>> {
+        if !self.utilization {
+            return Ok(None);
+        }
+        let Some(key) = GroupKey::shortest_period(keys) else {
+            return Err(eyre!("'--utilization' needs a report grouped by a period"))
+                .suggestion("Add 'day', 'week', or 'month' to '--group-by'");
+        };
+        let Some(schedule) = read_config(cli_args)?.schedule else {
+            return Err(eyre!("'--utilization' needs a schedule to compare with")).suggestion(
+                "Set `schedule` in the config, e.g. with 'punchcard config set schedule'",
+            );
+        };
+        Ok(Some((schedule, read_absences(cli_args)?, key)))
+    }
+
+    /// The absences to mark the groups of the shortest time based key with.
+    fn absences(
+        &self,
+        cli_args: &Cli,
+        keys: &[GroupKey],
+    ) -> Result<Option<(Vec<Absence>, GroupKey)>> {
+        let Some(key) = GroupKey::shortest_period(keys) else {
+            return Ok(None);
+        };
+        let absences = read_absences(cli_args)?;
+        Ok((!absences.is_empty()).then_some((absences, key)))
+    }
+
+    /// The days of the week in the order of their columns, with '--pivot-days'.
+    fn pivot_days(&self, keys: &[GroupKey]) -> Result<Option<Vec<Weekday>>> {
+        let pivot_days = matches!(
+            &self.report_type,
+            Some(ReportType::Weekly(WeeklyReportArgs {
+                pivot_days: true,
+                ..
+            }))
+        );
+        self.days_of_week(pivot_days, "--pivot-days", keys)
+    }
+
+    /// The days of the week in the order of their bars, with '--sparkline'.
+    fn sparkline_days(&self, keys: &[GroupKey]) -> Result<Option<Vec<Weekday>>> {
+        self.days_of_week(self.sparkline, "--sparkline", keys)
+    }
+
+    /// The days of the week from the start of the week, if `option` is used.
+    fn days_of_week(
+        &self,
+        used: bool,
+        option: &str,
+        keys: &[GroupKey],
+    ) -> Result<Option<Vec<Weekday>>> {
+        if !used {
+            return Ok(None);
+        }
+        if !keys.contains(&GroupKey::Week) {
+            return Err(eyre!("'{option}' needs a report grouped by week"))
+                .suggestion("Add 'week' to '--group-by'");
+        }
+        let mut day = self.anchor().week_start;
+        let mut days = Vec::with_capacity(7);
+        for _ in 0..7 {
+            days.push(day);
+            day = day.succ();
+        }
+        Ok(Some(days))
+    }
+
+    /// The number of periods to average for '--rolling'.
+    #[cfg(feature = "reports")]
+    fn rolling_window(&self, keys: &[GroupKey]) -> Result<Option<NonZeroUsize>> {
+        match self.rolling {
+            Some(_) if !keys.iter().any(|k| k.is_time_based()) => {
+                Err(eyre!("'--rolling' needs a report grouped by a period"))
+                    .suggestion("Add 'day', 'week', or 'month' to '--group-by'")
+            }
+            window => Ok(window),
+        }
+    }
+
+    /// Where the days and weeks of the report start.
+    fn anchor(&self) -> Anchor {
+        let default = Anchor::default();
+        Anchor {
+            day_boundary: self.day_boundary.unwrap_or(default.day_boundary),
+            week_start: self.week_start.unwrap_or(default.week_start),
+        }
+    }
 }
 
-pub(crate) use map_fn;
+/// The shifts matching '--source' and '--project', for the parts of a
+/// report which aren't computed by the engines.
+fn report_shifts(cli_args: &Cli, settings: &ReportSettings) -> Result<Vec<Shift>> {
+    let mut shifts = read_shifts(cli_args)?;
+    shifts.retain(|shift| {
+        let source = settings.source.as_ref().map_or(true, |source| {
+            std::iter::once(&shift.start)
+                .chain(&shift.end)
+                .any(|e| e.source == *source)
+        });
+        let project = settings.project.is_none() || shift.project() == settings.project.as_deref();
+        source && project
+    });
+    Ok(shifts)
+}
 
-use self::weekly::WeeklyReportArgs;
+/// Computes the report table with the engine chosen in the settings.
+pub(crate) fn build_report(cli_args: &Cli, settings: &ReportSettings) -> Result<Report> {
+    let (keys, period) = settings.scope();
+    trace!(?keys, ?period);
 
-fn map_datetime_to_date_str(s: Series) -> PolarsResult<Option<Series>> {
-    Ok(Some(
-        s.iter()
-            .filter_map(|x| {
-                let AnyValue::Datetime(epoch, time_unit, tz) = x else {
-                    return None;
-                };
-                assert_eq!(time_unit, TIME_UNIT);
-                assert!(tz.is_some());
-                let naive = chrono::NaiveDateTime::from_timestamp_opt(
-                    epoch / 1_000_000_000,
-                    (epoch % 1_000_000_000) as u32,
-                )
-                .unwrap();
-                Some(naive.format("%d %B %Y").to_string())
-            })
-            .collect(),
-    ))
+    match settings.engine {
+        #[cfg(feature = "reports")]
+        ReportEngine::Polars => {
+            let df = grouped::generate_grouped_report(cli_args, settings, &keys, period)?;
+            // polars does all of the work here, so give it a span of its own. it
+            // can't report its progress, so the most a bar can do is spin
+            let rows = crate::csv::count_rows(cli_args)?;
+            let _spinner = crate::progress::spinner(rows, "Generating report");
+            let df = info_span!("collect")
+                .in_scope(|| df.collect())
+                .wrap_err("Failed to process hours")
+                .with_suggestion(|| match settings.lenient {
+                    true => SUGG_REPORT_ISSUE,
+                    false => {
+                        "Use 'punchcard doctor' to find malformed rows, or '--lenient' to skip them"
+                    }
+                })?;
+            Ok(Report::Frame(df))
+        }
+        #[cfg(not(feature = "reports"))]
+        ReportEngine::Polars => Err(eyre!(
+            "This build of punchcard doesn't include the polars report engine"
+        ))
+        .suggestion("Use '--engine simple', or build punchcard with the `reports` feature"),
+        ReportEngine::Simple => Ok(Report::Simple(simple::generate_simple_report(
+            cli_args, settings, &keys, period,
+        )?)),
+    }
 }
 
 #[instrument]
 pub fn generate_report(cli_args: &Cli, settings: &ReportSettings) -> Result<()> {
-    let df = match &settings.report_type.as_ref().cloned().unwrap_or_default() {
-        ReportType::Weekly(args) => weekly::generate_weekly_report(cli_args, settings, args)?,
-        ReportType::Daily => daily::generate_daily_report(cli_args, settings)?,
-    };
+    let report_type = settings.report_type.as_ref().cloned().unwrap_or_default();
+
+    if let Some(path) = &settings.chart_file {
+        #[cfg(feature = "charts")]
+        chart::write_chart(cli_args, settings, path)?;
+        #[cfg(not(feature = "charts"))]
+        return Err(eyre!(
+            "Can't draw {}, this build of punchcard can't draw charts",
+            path.display()
+        ))
+        .suggestion("Build punchcard with the `charts` feature");
+    }
+
+    #[cfg(feature = "reports")]
+    if settings.copyable && settings.engine == ReportEngine::Polars {
+        let (keys, period) = settings.scope();
+        let df = grouped::generate_grouped_report(cli_args, settings, &keys, period)?;
+        // the page is only saved with '-o', otherwise it's opened
+        let digest = copyable::generate_copyable_report(cli_args, df, settings)?;
+        if let (Some(output_file), Some(digest)) = (&settings.output_file, digest) {
+            keep_export(cli_args, settings, output_file, digest)?;
+        }
+        return Ok(());
+    }
+
+    let mut report = build_report(cli_args, settings)?;
 
-    if settings.copyable {
-        return copyable::generate_copyable_report(df, settings);
+    // the snapshot is of the whole report, even if only the changes are shown
+    let mut snapshots = None;
+    if settings.output_file.is_some() || settings.changed_only {
+        let (keys, _) = settings.scope();
+        let key = snapshot::report_key(settings, &keys);
+        let rows = snapshot::report_rows(report.table(), &keys);
+        let mut all = snapshot::read_snapshots(cli_args)?;
+        if settings.changed_only {
+            let mask = snapshot::changed_rows(&rows, all.get(&key));
+            report = report.retain_rows(&mask)?;
+        }
+        all.entry(key).or_default().extend(rows);
+        snapshots = Some(all);
     }
 
-    let mut df = df.collect().wrap_err("Failed to process hours")?;
+    // looked up before the columns are relabelled, which keeps their order
+    let (keys, _) = settings.scope();
+    let project_column = keys
+        .contains(&GroupKey::Project)
+        .then(|| {
+            (0..report.table().width()).find(|&col| report.table().column(col).0 == RES_PROJECT)
+        })
+        .flatten();
+
+    // after the snapshot, which is compared by the original column names
+    report = report.relabel(&settings.labels(cli_args)?)?;
 
     let using_stdout = settings
         .output_file
@@ -179,8 +856,48 @@ pub fn generate_report(cli_args: &Cli, settings: &ReportSettings) -> Result<()>
     }
 
     if !using_stdout {
-        let display = DataFrameDisplay::new(&df, &settings.table_settings);
-        println!("{display}");
+        let projects = read_projects(cli_args)?;
+        let table = report.table();
+        let row_colors = match (
+            project_column,
+            project_color(&projects, settings.project.as_deref()),
+        ) {
+            (Some(col), _) => (0..table.height())
+                .map(|row| project_color(&projects, Some(&table.cell(row, col))))
+                .collect(),
+            (None, Some(color)) => vec![Some(color); table.height()],
+            (None, None) => Vec::new(),
+        };
+        let caption = match settings.changed_only {
+            true => format!(
+                "{}, changed since the last export",
+                report_type.describe_period(settings.anchor())
+            ),
+            false => report_type.describe_period(settings.anchor()),
+        };
+        let display = DataFrameDisplay::new(
+            report.table(),
+            &settings.table_settings,
+            table_config(cli_args),
+            cli_args.accessible,
+        )
+        .with_row_colors(row_colors)
+        .with_caption(caption);
+        crate::pager::print_paged(&display.to_string(), !settings.no_pager);
+
+        if !settings.just_table && !crate::csv::has_entries(cli_args)? {
+            use owo_colors::OwoColorize;
+            println!("{}", HINT_NO_ENTRIES.yellow());
+        }
+    }
+
+    if settings.narrative {
+        let narrative = narrative::narrative(cli_args, settings)?;
+        match using_stdout {
+            // keep the narrative out of the CSV
+            true => eprintln!("\n{narrative}"),
+            false => println!("\n{narrative}"),
+        }
     }
 
     if let Some(output_file) = &settings.output_file {
@@ -188,11 +905,38 @@ pub fn generate_report(cli_args: &Cli, settings: &ReportSettings) -> Result<()>
             .to_writer()
             .wrap_err_with(|| ERR_OPEN_CSV(output_file.unwrap_path()))
             .with_suggestion(|| SUGG_PROPER_PERMS(output_file.unwrap_path()))?;
-        CsvWriter::new(writer)
-            .include_header(true)
-            .finish(&mut df)
-            .wrap_err_with(|| ERR_WRITE_CSV(output_file.unwrap_path()))?;
+        let mut writer = HashingWriter::new(writer);
+        match report {
+            #[cfg(feature = "reports")]
+            Report::Frame(mut df) => CsvWriter::new(&mut writer)
+                .include_header(true)
+                .finish(&mut df)
+                .map_err(Into::into),
+            Report::Simple(table) => simple::write_csv(&table, &mut writer),
+        }
+        .wrap_err_with(|| ERR_WRITE_CSV(output_file.unwrap_path()))?;
+        keep_export(cli_args, settings, output_file, writer.hex_digest())?;
+
+        if let Some(snapshots) = snapshots {
+            snapshot::write_snapshots(cli_args, &snapshots)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Keeps a receipt of a saved report if asked to, and signs it if configured.
+fn keep_export(
+    cli_args: &Cli,
+    settings: &ReportSettings,
+    output_file: &Destination,
+    sha256: String,
+) -> Result<()> {
+    if settings.receipt {
+        record_receipt(cli_args, output_file, sha256)?;
     }
+    #[cfg(feature = "signing")]
+    crate::sign::sign_export(cli_args, output_file)?;
 
     Ok(())
 }
@@ -0,0 +1,129 @@
+// Copyright (C) 2023 Campbell M. Cole
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+// '--chart-file' draws the total hours of each period of a report as a bar
+// chart, so it can be put in documents. the totals come from the simple
+// engine, which groups the shifts like the table without formatting them
+
+use std::path::Path;
+
+use plotters::{coord::Shift, prelude::*};
+
+use crate::prelude::*;
+
+use super::{simple::period_totals, GroupKey, ReportSettings, RES_TOTAL_HOURS};
+
+/// The size of the chart in pixels, a bit wider than a page of text.
+const CHART_SIZE: (u32, u32) = (960, 480);
+/// The colour of the bars, like the header of the tables.
+const BAR_COLOR: RGBColor = RGBColor(139, 0, 139);
+
+/// Draws the total hours of each period of the report to an SVG or PNG file,
+/// depending on the extension of `path`.
+pub fn write_chart(cli_args: &Cli, settings: &ReportSettings, path: &Path) -> Result<()> {
+    let (keys, period) = settings.scope();
+    let Some(key) = GroupKey::shortest_period(&keys) else {
+        return Err(eyre!("'--chart-file' needs a report grouped by a period"))
+            .suggestion("Add 'day', 'week', or 'month' to '--group-by'");
+    };
+
+    let label_format = match key {
+        GroupKey::Month => "%b %Y",
+        _ => "%d %b",
+    };
+    let bars = period_totals(cli_args, settings, key, period)?
+        .into_iter()
+        .map(|(start, total)| {
+            let hours = total.num_seconds() as f64 / 3600.0;
+            (start.format(label_format).to_string(), hours)
+        })
+        .collect::<Vec<_>>();
+    let report_type = settings.report_type.clone().unwrap_or_default();
+    let caption = format!(
+        "{RES_TOTAL_HOURS}, {}",
+        report_type.describe_period(settings.anchor())
+    );
+
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_ascii_lowercase);
+    match extension.as_deref() {
+        Some("svg") => draw(
+            SVGBackend::new(path, CHART_SIZE).into_drawing_area(),
+            &caption,
+            &bars,
+        ),
+        Some("png") => draw(
+            BitMapBackend::new(path, CHART_SIZE).into_drawing_area(),
+            &caption,
+            &bars,
+        ),
+        _ => {
+            return Err(eyre!(
+                "Can't tell the format of the chart from its file name"
+            ))
+            .suggestion("Use a file name ending in `.svg` or `.png`")
+        }
+    }
+    .wrap_err_with(|| format!("Failed to draw the chart to {}", path.display()))?;
+
+    info!(path = %path.display(), "wrote the chart");
+    Ok(())
+}
+
+/// Draws a bar for each labelled number of hours.
+fn draw<DB: DrawingBackend>(
+    root: DrawingArea<DB, Shift>,
+    caption: &str,
+    bars: &[(String, f64)],
+) -> Result<()> {
+    // the errors of the backends aren't `Send`, so only their messages are kept
+    let err = |err: DrawingAreaErrorKind<DB::ErrorType>| eyre!("{err}");
+
+    root.fill(&WHITE).map_err(err)?;
+    let most = bars.iter().map(|(_, hours)| *hours).fold(1.0, f64::max);
+    let mut chart = ChartBuilder::on(&root)
+        .caption(caption, ("sans-serif", 24))
+        .margin(16)
+        .x_label_area_size(32)
+        .y_label_area_size(48)
+        .build_cartesian_2d(
+            (0..bars.len().saturating_sub(1)).into_segmented(),
+            0.0..most * 1.1,
+        )
+        .map_err(err)?;
+    chart
+        .configure_mesh()
+        .disable_x_mesh()
+        .x_labels(bars.len())
+        .x_label_formatter(&|x| match x {
+            SegmentValue::CenterOf(i) => bars.get(*i).map(|(l, _)| l.clone()).unwrap_or_default(),
+            _ => String::new(),
+        })
+        .y_desc("Hours")
+        .draw()
+        .map_err(err)?;
+    chart
+        .draw_series(
+            Histogram::vertical(&chart)
+                .style(BAR_COLOR.filled())
+                .margin(8)
+                .data(bars.iter().enumerate().map(|(i, (_, hours))| (i, *hours))),
+        )
+        .map_err(err)?;
+    root.present().map_err(err)?;
+    Ok(())
+}
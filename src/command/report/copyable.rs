@@ -12,31 +12,84 @@
 //
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+// the copyable report fills the templates in `web/` (or the ones in
+// '--template-dir') and opens a page which copies it as rich text
+
 use std::{
-    fs::File,
-    io::{Read, Write},
+    collections::BTreeMap,
+    fs::{self, File},
+    io::{self, IsTerminal, Read, Write},
+    path::Path,
     process::{Command, Stdio},
 };
 
+use chrono::Duration;
 use polars::prelude::LazyFrame;
 use snailquote::escape;
 
 use crate::{
+    command::{
+        absence::{is_absent, read_absences, Absence},
+        open::default_opener,
+    },
+    config::{read_config, Schedule},
     prelude::*,
-    table::{settings::TableSettings, style::TableStyle, DataFrameDisplay},
+    receipt::HashingWriter,
+    shift::{start_of_date, start_of_week, total_between, Shift},
+    signal,
+    table::{
+        settings::TableSettings, style::TableStyle, table_config, DataFrameDisplay, StringTable,
+    },
 };
 
-use super::{daily, weekly, ReportSettings, ReportType};
+use super::{
+    format_duration, grouped, relabel_frame, report_shifts, ReportSettings, RES_TOTAL_HOURS,
+    RES_WEEK_OF,
+};
 
 const MARKDOWN_TEMPLATE: &str = include_str!("../../../web/template.md");
 const HTML_TEMPLATE: &str = include_str!("../../../web/template.html");
+const MARKDOWN_TEMPLATE_FILE: &str = "template.md";
+const HTML_TEMPLATE_FILE: &str = "template.html";
 
 const REPORT_DATE_PLACEHOLDER: &str = "%%REPORT_DATE%%";
 const REPORT_TABLE_PLACEHOLDER: &str = "%%REPORT_TABLE%%";
 const TOTAL_HOURS_PLACEHOLDER: &str = "%%TOTAL_HOURS%%";
+const TARGET_SECTION_PLACEHOLDER: &str = "%%TARGET_SECTION%%";
+const TARGET_TABLE_PLACEHOLDER: &str = "%%TARGET_TABLE%%";
+const TARGET_HOURS_PLACEHOLDER: &str = "%%TARGET_HOURS%%";
+const DIFFERENCE_PLACEHOLDER: &str = "%%DIFFERENCE%%";
+const OVERTIME_SECTION_PLACEHOLDER: &str = "%%OVERTIME_SECTION%%";
+const OVERTIME_HOURS_PLACEHOLDER: &str = "%%OVERTIME_HOURS%%";
 const REPORT_HTML_PLACEHOLDER: &str = "%%REPORT_HTML%%";
 
-pub fn generate_copyable_report(lf: LazyFrame, settings: &ReportSettings) -> Result<()> {
+const RES_TARGET: &str = "Target";
+const RES_ACTUAL: &str = "Actual";
+const RES_DIFFERENCE: &str = "Difference";
+
+/// The scheduled and worked time of one week of the report.
+#[derive(Debug)]
+struct TargetWeek {
+    start: DateTime<Local>,
+    target: Duration,
+    actual: Duration,
+}
+
+impl TargetWeek {
+    /// The time worked beyond the target.
+    fn overtime(&self) -> Duration {
+        (self.actual - self.target).max(Duration::zero())
+    }
+}
+
+/// Opens the report as a page which can be copied from, or saves the page
+/// with '-o'. Returns the SHA-256 of the saved page.
+pub fn generate_copyable_report(
+    cli_args: &Cli,
+    lf: LazyFrame,
+    settings: &ReportSettings,
+) -> Result<Option<String>> {
     let mut table = String::new();
 
     let table_settings = TableSettings {
@@ -45,14 +98,18 @@ pub fn generate_copyable_report(lf: LazyFrame, settings: &ReportSettings) -> Res
         ..settings.table_settings.clone()
     };
 
-    let prepped = match settings.report_type.as_ref().cloned().unwrap_or_default() {
-        ReportType::Daily => daily::prepare_for_display(lf.clone(), settings),
-        ReportType::Weekly(_) => weekly::prepare_for_display(lf.clone(), settings),
-    };
+    let prepped = grouped::prepare_for_display(lf.clone(), settings)?;
 
-    let df = prepped.collect()?;
+    let mut df = prepped.collect()?;
+    let labels = settings.labels(cli_args)?;
+    relabel_frame(&mut df, &labels)?;
 
-    let display = DataFrameDisplay::new(&df, &table_settings);
+    let display = DataFrameDisplay::new(
+        &df,
+        &table_settings,
+        table_config(cli_args),
+        cli_args.accessible,
+    );
 
     {
         use std::fmt::Write;
@@ -60,7 +117,8 @@ pub fn generate_copyable_report(lf: LazyFrame, settings: &ReportSettings) -> Res
         write!(table, "{}", display)?;
     }
 
-    let mut template = String::from(MARKDOWN_TEMPLATE);
+    let (markdown_template, html_template) = read_templates(settings.template_dir.as_deref())?;
+    let mut template = markdown_template;
 
     template = template.replace(
         REPORT_DATE_PLACEHOLDER,
@@ -72,13 +130,15 @@ pub fn generate_copyable_report(lf: LazyFrame, settings: &ReportSettings) -> Res
     // this table retains original data types so we can use it to calculate the total hours
     let df = lf.collect()?;
 
-    let total_hours = df.column("Total Hours").unwrap().sum::<i64>().unwrap();
+    let total_hours = df.column(RES_TOTAL_HOURS).unwrap().sum::<i64>().unwrap();
     let total_hours = chrono::Duration::nanoseconds(total_hours);
     let total_hours = BiDuration::new(total_hours);
     let total_hours_str = total_hours.to_friendly_absolute_string();
 
     template = template.replace(TOTAL_HOURS_PLACEHOLDER, &total_hours_str);
 
+    template = fill_target_placeholders(cli_args, settings, &table_settings, &labels, template)?;
+
     let mut pandoc = Command::new("pandoc");
     pandoc.stdin(Stdio::piped()).stdout(Stdio::piped());
 
@@ -98,9 +158,14 @@ pub fn generate_copyable_report(lf: LazyFrame, settings: &ReportSettings) -> Res
 
     html = escape(&html).to_string();
 
-    let full_html = HTML_TEMPLATE.replace(REPORT_HTML_PLACEHOLDER, &html);
+    let full_html = html_template.replace(REPORT_HTML_PLACEHOLDER, &html);
+
+    if let Some(output_file) = &settings.output_file {
+        return write_page(output_file, &full_html).map(Some);
+    }
 
     let temp_dir = temp_dir::TempDir::new()?;
+    let _cleanup = signal::remove_on_interrupt(temp_dir.path());
 
     let temp_file_path = temp_dir.path().join("report.html");
 
@@ -109,19 +174,257 @@ pub fn generate_copyable_report(lf: LazyFrame, settings: &ReportSettings) -> Res
     write!(temp_file, "{}", full_html)?;
     temp_file.flush()?;
 
+    if settings.no_open {
+        // the page is kept for whatever opens it next
+        println!("{}", temp_file_path.display());
+        temp_dir.leak();
+        return Ok(None);
+    }
+
+    let browser = match &settings.browser {
+        Some(browser) => Some(browser.clone()),
+        None => read_config(cli_args)?.browser,
+    };
+    let browser = browser.unwrap_or_else(|| default_opener().into());
+    let mut args = browser.split_whitespace();
+    let program = args
+        .next()
+        .ok_or_else(|| eyre!("The browser command is empty"))?;
+
     println!("Opening report in browser...");
     println!("Follow instructions on the page that opens.");
     std::thread::sleep(std::time::Duration::from_secs(3));
 
-    let mut chromium = Command::new("chromium");
+    let mut command = Command::new(program);
 
-    chromium.stdout(Stdio::null()).stderr(Stdio::null());
+    command.stdout(Stdio::null()).stderr(Stdio::null());
 
-    chromium.arg(&temp_file_path);
+    command.args(args).arg(&temp_file_path);
 
-    chromium.spawn()?.wait()?;
+    command
+        .spawn()
+        .and_then(|mut child| child.wait())
+        .wrap_err_with(|| format!("Failed to run '{browser}'"))
+        .suggestion("Choose the browser with '--browser' or `browser` in the config, or use '--no-open' and open the page yourself")?;
+
+    // openers like xdg-open return right away, so the page can't be removed yet
+    if io::stdin().is_terminal() {
+        print!("Press enter once the report was copied ");
+        io::stdout().flush().wrap_err("Failed to write to stdout")?;
+        io::stdin()
+            .read_line(&mut String::new())
+            .wrap_err("Failed to read from stdin")?;
+    } else {
+        temp_dir.leak();
+    }
 
     println!("Report closed.");
 
-    Ok(())
+    Ok(None)
+}
+
+/// Saves the page instead of opening it, e.g. to host it somewhere.
+/// Returns the SHA-256 of the page, for its receipt.
+fn write_page(output_file: &Destination, html: &str) -> Result<String> {
+    let write = || -> io::Result<String> {
+        let mut writer = HashingWriter::new(output_file.to_writer()?);
+        writer.write_all(html.as_bytes())?;
+        writer.flush()?;
+        Ok(writer.hex_digest())
+    };
+    write()
+        .wrap_err_with(|| {
+            format!(
+                "Failed to write the page to {}",
+                output_file.unwrap_path().display()
+            )
+        })
+        .with_suggestion(|| SUGG_PROPER_PERMS(output_file.unwrap_path()))
+}
+
+/// Reads the templates in `dir`, using the built in one for any which is missing.
+fn read_templates(dir: Option<&Path>) -> Result<(String, String)> {
+    let Some(dir) = dir else {
+        return Ok((MARKDOWN_TEMPLATE.into(), HTML_TEMPLATE.into()));
+    };
+    if !dir.is_dir() {
+        return Err(ErrorCode::NotFound.report(format!(
+            "The template folder {} doesn't exist",
+            dir.display()
+        )));
+    }
+
+    let read = |name: &str, default: &str| -> Result<String> {
+        let path = dir.join(name);
+        match path.exists() {
+            true => fs::read_to_string(&path)
+                .wrap_err_with(|| format!("Failed to read the template {}", path.display()))
+                .with_suggestion(|| SUGG_PROPER_PERMS(&path)),
+            false => Ok(default.into()),
+        }
+    };
+    Ok((
+        read(MARKDOWN_TEMPLATE_FILE, MARKDOWN_TEMPLATE)?,
+        read(HTML_TEMPLATE_FILE, HTML_TEMPLATE)?,
+    ))
+}
+
+/// Fills in the target and overtime placeholders. Without a schedule in the
+/// config there's no target, so the sections are left out and the values are empty.
+fn fill_target_placeholders(
+    cli_args: &Cli,
+    settings: &ReportSettings,
+    table_settings: &TableSettings,
+    labels: &BTreeMap<String, String>,
+    template: String,
+) -> Result<String> {
+    let empty = |template: String| {
+        [
+            TARGET_SECTION_PLACEHOLDER,
+            TARGET_TABLE_PLACEHOLDER,
+            TARGET_HOURS_PLACEHOLDER,
+            DIFFERENCE_PLACEHOLDER,
+            OVERTIME_SECTION_PLACEHOLDER,
+            OVERTIME_HOURS_PLACEHOLDER,
+        ]
+        .iter()
+        .fold(template, |template, placeholder| {
+            template.replace(placeholder, "")
+        })
+    };
+
+    let Some(schedule) = read_config(cli_args)?.schedule else {
+        return Ok(empty(template));
+    };
+    let now = Local::now();
+    let shifts = report_shifts(cli_args, settings)?;
+    let (_, period) = settings.scope();
+    let range = match period.range {
+        Some(range) => range,
+        None => match shifts.iter().map(|s| s.start.timestamp).min() {
+            Some(first) => (start_of_week(first), now),
+            None => return Ok(empty(template)),
+        },
+    };
+    let absences = read_absences(cli_args)?;
+    let weeks = target_weeks(&schedule, &absences, &shifts, range, now);
+
+    let exact = settings.exact_durations;
+    let sum = |f: fn(&TargetWeek) -> Duration| {
+        weeks.iter().map(f).fold(Duration::zero(), |acc, d| acc + d)
+    };
+    let target = sum(|w| w.target);
+    let actual = sum(|w| w.actual);
+    let overtime = sum(TargetWeek::overtime);
+
+    let mut table = StringTable::new()
+        .with_column(
+            RES_WEEK_OF,
+            "str",
+            weeks
+                .iter()
+                .map(|w| w.start.format("%d %B %Y").to_string())
+                .collect(),
+        )
+        .with_column(
+            RES_TARGET,
+            "str",
+            weeks
+                .iter()
+                .map(|w| format_duration(w.target, exact))
+                .collect(),
+        )
+        .with_column(
+            RES_ACTUAL,
+            "str",
+            weeks
+                .iter()
+                .map(|w| format_duration(w.actual, exact))
+                .collect(),
+        )
+        .with_column(
+            RES_DIFFERENCE,
+            "str",
+            weeks
+                .iter()
+                .map(|w| format_difference(w.actual - w.target, exact))
+                .collect(),
+        );
+    for (column, label) in labels {
+        table.rename_column(column, label);
+    }
+    let table = DataFrameDisplay::new(
+        &table,
+        table_settings,
+        table_config(cli_args),
+        cli_args.accessible,
+    )
+    .to_string();
+    let target_hours = format_duration(target, exact);
+    let difference = format_difference(actual - target, exact);
+    let overtime_hours = format_duration(overtime, exact);
+
+    let target_section =
+        format!("## Target vs. Actual\n\n{table}\n\n**Target:** {target_hours} ({difference})");
+    let overtime_section = format!(
+        "## Overtime\n\n**Overtime:** {overtime_hours} worked beyond the target of each week"
+    );
+
+    Ok(template
+        .replace(TARGET_SECTION_PLACEHOLDER, &target_section)
+        .replace(TARGET_TABLE_PLACEHOLDER, &table)
+        .replace(TARGET_HOURS_PLACEHOLDER, &target_hours)
+        .replace(DIFFERENCE_PLACEHOLDER, &difference)
+        .replace(OVERTIME_SECTION_PLACEHOLDER, &overtime_section)
+        .replace(OVERTIME_HOURS_PLACEHOLDER, &overtime_hours))
+}
+
+/// Formats how far the time worked is from the target, e.g. `+2 hours`.
+fn format_difference(difference: Duration, exact: bool) -> String {
+    match difference < Duration::zero() {
+        true => format!("-{}", format_duration(-difference, exact)),
+        false => format!("+{}", format_duration(difference, exact)),
+    }
+}
+
+/// The target and the time worked in each week of `range`. The target only
+/// counts the scheduled days up to today, so a report of the current month
+/// isn't behind on the days still to come, and leaves out days with an absence.
+fn target_weeks(
+    schedule: &Schedule,
+    absences: &[Absence],
+    shifts: &[Shift],
+    (from, to): (DateTime<Local>, DateTime<Local>),
+    now: DateTime<Local>,
+) -> Vec<TargetWeek> {
+    let to = match now.date_naive().succ_opt().and_then(start_of_date) {
+        Some(tomorrow) => to.min(tomorrow),
+        None => to,
+    };
+
+    let mut weeks: Vec<TargetWeek> = Vec::new();
+    let mut date = from.date_naive();
+    while let Some(day_start) = start_of_date(date).filter(|d| *d < to) {
+        let week_start = start_of_week(day_start);
+        if weeks.last().map(|w| w.start) != Some(week_start) {
+            let week_end = week_start + Duration::days(7);
+            weeks.push(TargetWeek {
+                start: week_start,
+                target: Duration::zero(),
+                actual: total_between(shifts, week_start.max(from), week_end.min(to), now),
+            });
+        }
+        if let Some((start, end)) = schedule
+            .workday(day_start)
+            .filter(|_| !is_absent(absences, date))
+        {
+            let week = weeks.last_mut().expect("a week was pushed above");
+            week.target = week.target + (end - start);
+        }
+        let Some(next) = date.succ_opt() else {
+            break;
+        };
+        date = next;
+    }
+    weeks
 }
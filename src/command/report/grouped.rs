@@ -0,0 +1,706 @@
+// Copyright (C) 2023 Campbell M. Cole
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+// every report is built by the same engine: the data file is turned into
+// one row per shift, then the shifts are grouped by the requested keys.
+// the report types only decide which period is covered and the default keys
+
+use polars::{lazy::dsl::GetOutput, prelude::*, series::ops::NullBehavior};
+
+// for some reason TimeZone needs to be explicitly imported
+use crate::command::absence::Absence;
+use crate::config::Schedule;
+use crate::location::LOCATION_TAG_PREFIX;
+use crate::prelude::{TimeZone, *};
+
+use super::{
+    format_duration, group_absence_marker, scheduled_time, sparkline, Anchor, GroupKey,
+    ReportPeriod, ReportSettings, ISO_WEEK_FORMAT, NONE_LABEL, PARTIAL_WEEK_MARKER,
+    RES_AVERAGE_SHIFT_DURATION, RES_DAILY_AVERAGE, RES_DAYS_COVERED, RES_LOCATION,
+    RES_LONGEST_SHIFT, RES_MEDIAN_SHIFT_DURATION, RES_MONTH, RES_PROJECT, RES_ROLLING_AVERAGE,
+    RES_SHIFTS, RES_SPARKLINE, RES_TAG, RES_TOTAL_HOURS, RES_TRAVEL_HOURS, RES_UTILIZATION,
+    RES_WEEK_END, RES_WEEK_NUMBER, RES_WEEK_OF,
+};
+
+const TIME_UNIT: TimeUnit = TimeUnit::Nanoseconds;
+
+const COL_TIMESTAMP: &str = "timestamp";
+const COL_ENTRY_TYPE: &str = "entry_type";
+const COL_DURATION: &str = "duration";
+const COL_SOURCE: &str = "source";
+const COL_PROJECT: &str = "project";
+const COL_TAGS: &str = "tags";
+const COL_LOCATION: &str = "location";
+const COL_TRAVEL: &str = "travel";
+
+const NANOSECOND_OVERFLOW_MESSAGE: &str = "why are you using this 500 years in the future?";
+
+const COL_SHIFT_PROJECT: &str = "shift_project";
+const COL_SHIFT_TAGS: &str = "shift_tags";
+const COL_DAYS_WORKED: &str = "days_worked";
+/// The marker of the absences in each group, added to its date for display
+const COL_ABSENCE: &str = "absence";
+
+impl GroupKey {
+    /// The length of the period for time based keys, in the format `truncate` expects
+    fn every(&self) -> Option<&'static str> {
+        match self {
+            GroupKey::Day => Some("1d"),
+            GroupKey::Week => Some("1w"),
+            GroupKey::Month => Some("1mo"),
+            GroupKey::Project | GroupKey::Tag | GroupKey::Location => None,
+        }
+    }
+
+    /// The start of the group containing each shift, for time based keys.
+    fn group_start(&self, anchor: Anchor) -> Option<Expr> {
+        let every = lit(self.every()?);
+        let offset = anchor.offset(*self);
+        // the offset of `truncate` is added after truncating, so it can't move the groups
+        Some(match offset.is_zero() {
+            true => col(COL_TIMESTAMP).dt().truncate(every, "0".into()),
+            false => {
+                (col(COL_TIMESTAMP) - lit(offset))
+                    .dt()
+                    .truncate(every, "0".into())
+                    + lit(offset)
+            }
+        })
+    }
+
+    fn expr(&self, anchor: Anchor) -> Expr {
+        match self.group_start(anchor) {
+            Some(group_start) => group_start.alias(self.column()),
+            None => match self {
+                GroupKey::Project => col(COL_PROJECT).alias(RES_PROJECT),
+                GroupKey::Tag => col(COL_TAGS).alias(RES_TAG),
+                GroupKey::Location => col(COL_LOCATION).alias(RES_LOCATION),
+                _ => unreachable!("time based keys are handled above"),
+            },
+        }
+    }
+}
+
+/// Builds a predicate which selects the clock-out entries of the shifts
+/// matching the filters in the report settings.
+///
+/// This must be applied after the durations are calculated, because it
+/// looks at the clock-in entry of each shift as well.
+fn shift_predicate(settings: &ReportSettings) -> Expr {
+    let mut predicate = col(COL_ENTRY_TYPE).eq(lit("out"));
+    if let Some(source) = &settings.source {
+        let source = lit(source.to_string());
+        predicate = predicate.and(
+            col(COL_SOURCE)
+                .eq(source.clone())
+                .or(col(COL_SOURCE).shift(lit(1)).eq(source)),
+        );
+    }
+    if let Some(project) = &settings.project {
+        // the project is usually only given when clocking in
+        let project = lit(project.clone());
+        predicate = predicate.and(
+            col(COL_PROJECT)
+                .eq(project.clone())
+                .or(col(COL_PROJECT).shift(lit(1)).eq(project)),
+        );
+    }
+    predicate
+}
+
+/// Finds a column of the aggregated report, ignoring case.
+fn find_column(schema: &Schema, name: &str) -> Result<(String, DataType)> {
+    match schema
+        .iter()
+        .find(|(column, _)| column.eq_ignore_ascii_case(name))
+    {
+        Some((column, dtype)) => Ok((column.to_string(), dtype.clone())),
+        None => {
+            let columns = schema
+                .iter_names()
+                .map(|column| format!("'{column}'"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            Err(eyre!("Unknown column '{name}'"))
+                .suggestion(format!("The columns of this report are: {columns}"))
+        }
+    }
+}
+
+/// Converts the value of a '--where' condition to a literal of the same type as the column.
+fn filter_value(filter: &FilterExpr, dtype: &DataType) -> Result<Expr> {
+    let value = filter.value.as_str();
+    match dtype {
+        DataType::Duration(_) => {
+            let duration = value
+                .parse::<BiDuration>()
+                .wrap_err_with(|| format!("Invalid duration '{value}'"))?;
+            let nanos = duration
+                .num_nanoseconds()
+                .ok_or_else(|| eyre!(NANOSECOND_OVERFLOW_MESSAGE))?;
+            Ok(lit(nanos).cast(DataType::Duration(TIME_UNIT)))
+        }
+        DataType::Datetime(_, _) => {
+            let date = chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .wrap_err_with(|| format!("Invalid date '{value}'"))
+                .suggestion("Dates must be written like 2024-03-01")?;
+            let timestamp = Local
+                .from_local_datetime(&date.and_hms_opt(0, 0, 0).unwrap())
+                .earliest()
+                .ok_or_else(|| eyre!("{value} does not exist in the local timezone"))?;
+            Ok(lit(timestamp
+                .timestamp_nanos_opt()
+                .expect(NANOSECOND_OVERFLOW_MESSAGE)))
+        }
+        dtype if dtype.is_numeric() => {
+            let number = value
+                .parse::<f64>()
+                .wrap_err_with(|| format!("Invalid number '{value}'"))?;
+            Ok(lit(number))
+        }
+        _ => Ok(lit(value.to_string())),
+    }
+}
+
+/// Filters the aggregated report by the '--where' conditions.
+///
+/// Like sorting, this must be applied before the durations are formatted.
+fn apply_filters(mut df: LazyFrame, settings: &ReportSettings) -> Result<LazyFrame> {
+    if settings.filters.is_empty() {
+        return Ok(df);
+    }
+
+    let schema = df.schema().wrap_err("Failed to determine report columns")?;
+    for filter in &settings.filters {
+        let (column, dtype) = find_column(&schema, &filter.column)
+            .wrap_err_with(|| format!("Cannot filter by '{}'", filter.column))?;
+        let value = filter_value(filter, &dtype)
+            .wrap_err_with(|| format!("Cannot filter by '{}'", filter.column))?;
+        let column = col(&column);
+        df = df.filter(match filter.op {
+            CompareOp::Lt => column.lt(value),
+            CompareOp::LtEq => column.lt_eq(value),
+            CompareOp::Gt => column.gt(value),
+            CompareOp::GtEq => column.gt_eq(value),
+            CompareOp::Eq => column.eq(value),
+            CompareOp::NotEq => column.neq(value),
+        });
+    }
+
+    Ok(df)
+}
+
+/// Sorts the aggregated report by the '--sort' columns.
+///
+/// This must be applied before the durations are formatted as strings,
+/// otherwise they would be sorted alphabetically.
+fn apply_sort(df: LazyFrame, settings: &ReportSettings) -> Result<LazyFrame> {
+    if settings.sort.is_empty() {
+        return Ok(df);
+    }
+
+    let schema = df.schema().wrap_err("Failed to determine report columns")?;
+    let mut by = Vec::with_capacity(settings.sort.len());
+    let mut descending = Vec::with_capacity(settings.sort.len());
+    for key in &settings.sort {
+        let (column, _) = find_column(&schema, &key.column)
+            .wrap_err_with(|| format!("Cannot sort by '{}'", key.column))?;
+        by.push(col(&column));
+        descending.push(key.descending);
+    }
+
+    Ok(df.sort_by_exprs(by, descending, true, true))
+}
+
+/// Formats every duration in a column for display.
+fn map_duration_to_str(s: Series, exact: bool) -> PolarsResult<Option<Series>> {
+    Ok(Some(
+        s.iter()
+            .map(|x| {
+                // e.g. the first rows of '--rolling', which don't have enough periods before them
+                let AnyValue::Duration(duration, time_unit) = x else {
+                    return String::new();
+                };
+                assert_eq!(time_unit, TIME_UNIT);
+                format_duration(chrono::Duration::nanoseconds(duration), exact)
+            })
+            .collect::<Series>(),
+    ))
+}
+
+fn timestamp_lit(timestamp: DateTime<Local>) -> Expr {
+    lit(timestamp
+        .timestamp_nanos_opt()
+        .expect(NANOSECOND_OVERFLOW_MESSAGE))
+}
+
+/// Reads the data file into one row per shift, with the end of the shift as its timestamp.
+fn read_shifts(cli_args: &Cli, settings: &ReportSettings) -> Result<LazyFrame> {
+    let mut df = new_reader(cli_args)?.select([
+        col(COL_ENTRY_TYPE),
+        col(COL_SOURCE).cast(DataType::String),
+        col(COL_PROJECT).cast(DataType::String),
+        col(COL_TAGS).cast(DataType::String),
+        col(COL_TIMESTAMP)
+            .str()
+            .strptime(
+                DataType::Datetime(TIME_UNIT, None),
+                StrptimeOptions {
+                    format: Some(CSV_DATETIME_FORMAT.into()),
+                    exact: true,
+                    cache: false,
+                    // unparseable timestamps become null in lenient mode
+                    strict: !settings.lenient,
+                },
+                lit("1970-01-01T00:00:00.0000000Z"),
+            )
+            // then we cast back to local time
+            .cast(DataType::Datetime(
+                TIME_UNIT,
+                Some(cli_args.timezone.to_string()),
+            )),
+    ]);
+
+    if settings.lenient {
+        let skipped = df
+            .clone()
+            .select([col(COL_TIMESTAMP).null_count()])
+            .collect()
+            .wrap_err("Failed to count unparseable rows")?
+            .column(COL_TIMESTAMP)
+            .and_then(|c| c.get(0))
+            .ok()
+            .and_then(|v| v.extract::<u64>())
+            .unwrap_or(0);
+        if skipped > 0 {
+            use owo_colors::OwoColorize;
+            eprintln!(
+                "{}",
+                format!("Skipped {skipped} row(s) with a timestamp that couldn't be parsed")
+                    .yellow()
+            );
+        }
+        df = df.filter(col(COL_TIMESTAMP).is_not_null());
+    }
+
+    Ok(df
+        .sort(
+            COL_TIMESTAMP,
+            SortOptions {
+                descending: false,
+                nulls_last: false,
+                multithreaded: true,
+                maintain_order: false,
+            },
+        )
+        .with_columns([
+            col(COL_TIMESTAMP)
+                .diff(1, NullBehavior::Ignore)
+                .alias(COL_DURATION),
+            // the project and tags are usually only given when clocking in
+            col(COL_PROJECT)
+                .shift(lit(1))
+                .fill_null(col(COL_PROJECT))
+                .alias(COL_SHIFT_PROJECT),
+            col(COL_TAGS)
+                .shift(lit(1))
+                .fill_null(col(COL_TAGS))
+                .alias(COL_SHIFT_TAGS),
+        ])
+        .filter(shift_predicate(settings))
+        .select([
+            col(COL_TIMESTAMP),
+            col(COL_DURATION),
+            col(COL_SHIFT_PROJECT)
+                .fill_null(lit(NONE_LABEL))
+                .alias(COL_PROJECT),
+            col(COL_SHIFT_TAGS).alias(COL_TAGS),
+        ]))
+}
+
+/// The scheduled time of each group in a column of group starts, or null
+/// if nothing was scheduled.
+fn map_group_start_to_scheduled(
+    s: Series,
+    schedule: &Schedule,
+    absences: &[Absence],
+    key: GroupKey,
+    period: ReportPeriod,
+    anchor: Anchor,
+) -> PolarsResult<Option<Series>> {
+    let scheduled = s
+        .datetime()?
+        .into_iter()
+        .map(|start| {
+            let start = Local.timestamp_nanos(start?);
+            let scheduled = scheduled_time(schedule, absences, key, start, &period, anchor);
+            (scheduled > chrono::Duration::zero()).then(|| scheduled.num_nanoseconds())?
+        })
+        .collect::<Int64Chunked>();
+    Ok(Some(scheduled.into_duration(TIME_UNIT).into_series()))
+}
+
+/// Draws a sparkline of the columns with the time worked on each day of the
+/// week, scaled to the longest day in the report.
+fn map_days_to_sparkline(days: &mut [Series]) -> PolarsResult<Option<Series>> {
+    let days = days
+        .iter()
+        .map(|s| Ok(s.duration()?.into_iter().collect::<Vec<_>>()))
+        .collect::<PolarsResult<Vec<_>>>()?;
+    let longest = days.iter().flatten().flatten().max().copied().unwrap_or(0);
+    let rows = days.first().map_or(0, Vec::len);
+    let lines = (0..rows)
+        .map(|row| {
+            sparkline(
+                days.iter()
+                    .map(|day| chrono::Duration::nanoseconds(day[row].unwrap_or(0))),
+                chrono::Duration::nanoseconds(longest),
+            )
+        })
+        .collect::<Vec<_>>();
+    Ok(Some(Series::new(RES_SPARKLINE, lines)))
+}
+
+/// The marker of the absences during each group in a column of group starts,
+/// or null if there were none.
+fn map_group_start_to_absence(
+    s: Series,
+    absences: &[Absence],
+    key: GroupKey,
+) -> PolarsResult<Option<Series>> {
+    let markers = s
+        .datetime()?
+        .into_iter()
+        .map(|start| group_absence_marker(absences, key, Local.timestamp_nanos(start?)))
+        .collect::<StringChunked>();
+    Ok(Some(markers.into_series()))
+}
+
+/// Generates a report with one row per combination of the given keys.
+#[instrument]
+pub fn generate_grouped_report(
+    cli_args: &Cli,
+    settings: &ReportSettings,
+    keys: &[GroupKey],
+    period: ReportPeriod,
+) -> Result<LazyFrame> {
+    let utilization = settings.utilization(cli_args, keys)?;
+    let rolling_window = settings.rolling_window(keys)?;
+    let pivot_days = settings.pivot_days(keys)?;
+    let sparkline_days = settings.sparkline_days(keys)?;
+    let mut df = read_shifts(cli_args, settings)?;
+    let anchor = settings.anchor();
+
+    if let Some((from, to)) = period.range {
+        // with spill over, whole groups are kept as long as they overlap the period
+        df = match period.spill_over_key(keys) {
+            Some((key, length)) => {
+                let group_start = key
+                    .group_start(anchor)
+                    .expect("only time based keys are used for spill over");
+                df.filter(
+                    group_start
+                        .clone()
+                        .lt(timestamp_lit(to))
+                        .and((group_start + lit(length)).gt(timestamp_lit(from))),
+                )
+            }
+            None => df.filter(
+                col(COL_TIMESTAMP)
+                    .gt_eq(timestamp_lit(from))
+                    .and(col(COL_TIMESTAMP).lt(timestamp_lit(to))),
+            ),
+        };
+    }
+
+    // taken before the tags are split up below, like the location
+    let travel_pattern = format!("(?:^|{TAG_SEPARATOR}){TRAVEL_TAG}(?:{TAG_SEPARATOR}|$)");
+    df = df.with_column(
+        col(COL_TAGS)
+            .str()
+            .contains(lit(travel_pattern), false)
+            .fill_null(lit(false))
+            .alias(COL_TRAVEL),
+    );
+    let has_travel = df
+        .clone()
+        .filter(col(COL_TRAVEL))
+        .limit(1)
+        .collect()
+        .wrap_err("Failed to look for travel time")?
+        .height()
+        > 0;
+
+    if keys.contains(&GroupKey::Location) {
+        // taken before the tags are split up below
+        let pattern = format!("(?:^|{TAG_SEPARATOR}){LOCATION_TAG_PREFIX}([^{TAG_SEPARATOR}]*)");
+        df = df.with_column(
+            col(COL_TAGS)
+                .str()
+                .extract(&pattern, 1)
+                .fill_null(lit(NONE_LABEL))
+                .alias(COL_LOCATION),
+        );
+    }
+
+    if keys.contains(&GroupKey::Tag) {
+        // a shift with several tags is counted once for each of them
+        df = df
+            .with_column(col(COL_TAGS).str().split(lit(TAG_SEPARATOR.to_string())))
+            .explode([col(COL_TAGS)])
+            .with_column(
+                when(col(COL_TAGS).is_null().or(col(COL_TAGS).eq(lit(""))))
+                    .then(lit(NONE_LABEL))
+                    .otherwise(col(COL_TAGS))
+                    .alias(COL_TAGS),
+            );
+    }
+
+    let key_exprs = keys.iter().map(|k| k.expr(anchor)).collect::<Vec<_>>();
+    let key_cols = keys.iter().map(|k| col(k.column())).collect::<Vec<_>>();
+
+    // like a paper timesheet, the pivoted days replace the columns about the week and its shifts
+    let timesheet = pivot_days.is_some();
+    let mut result_cols = key_cols.clone();
+    for day in pivot_days.iter().flatten() {
+        let day = day.to_string();
+        // days without any shifts are left empty
+        result_cols.push(
+            when(col(&day).gt(lit(chrono::Duration::zero())))
+                .then(col(&day))
+                .otherwise(lit(NULL).cast(DataType::Duration(TIME_UNIT)))
+                .alias(&day),
+        );
+    }
+    result_cols.push(col(RES_TOTAL_HOURS));
+    if let Some(days) = &sparkline_days {
+        let day_cols = days.iter().map(|d| col(&d.to_string())).collect::<Vec<_>>();
+        result_cols.push(
+            map_multiple(
+                map_days_to_sparkline,
+                day_cols,
+                GetOutput::from_type(DataType::String),
+            )
+            .alias(RES_SPARKLINE),
+        );
+    }
+    if has_travel && !timesheet {
+        result_cols.push(col(RES_TRAVEL_HOURS));
+    }
+    if keys.contains(&GroupKey::Week) {
+        if !timesheet {
+            result_cols
+                .push((col(RES_WEEK_OF) + lit(chrono::Duration::weeks(1))).alias(RES_WEEK_END));
+        }
+        if let (true, false, Some((from, to))) = (
+            period.has_partial_weeks(keys, anchor),
+            timesheet,
+            period.range,
+        ) {
+            let nanos = |d: chrono::Duration| lit(d.num_nanoseconds().unwrap());
+            let week_start = col(RES_WEEK_OF).cast(DataType::Int64);
+            let week_end = week_start.clone() + nanos(chrono::Duration::weeks(1));
+            let start = when(week_start.clone().lt(timestamp_lit(from)))
+                .then(timestamp_lit(from))
+                .otherwise(week_start);
+            let end = when(week_end.clone().gt(timestamp_lit(to)))
+                .then(timestamp_lit(to))
+                .otherwise(week_end);
+            // rounded, because days are an hour shorter or longer across a DST change
+            let days = (end - start + nanos(chrono::Duration::hours(12)))
+                / nanos(chrono::Duration::days(1));
+            result_cols.push(days.cast(DataType::Int64).alias(RES_DAYS_COVERED));
+        }
+        if settings.week_numbers {
+            result_cols.push(
+                col(RES_WEEK_OF)
+                    .dt()
+                    .strftime(ISO_WEEK_FORMAT)
+                    .alias(RES_WEEK_NUMBER),
+            );
+        }
+    }
+    if !timesheet {
+        result_cols.extend([
+            col(RES_SHIFTS),
+            (col(RES_TOTAL_HOURS) / col(RES_SHIFTS))
+                .alias(RES_AVERAGE_SHIFT_DURATION)
+                .cast(DataType::Duration(TIME_UNIT)),
+        ]);
+    }
+    if settings.median_shift {
+        result_cols.push(col(RES_MEDIAN_SHIFT_DURATION));
+    }
+    if settings.longest_shift {
+        result_cols.push(col(RES_LONGEST_SHIFT));
+    }
+    if settings.daily_average {
+        result_cols.push(
+            (col(RES_TOTAL_HOURS) / col(COL_DAYS_WORKED))
+                .alias(RES_DAILY_AVERAGE)
+                .cast(DataType::Duration(TIME_UNIT)),
+        );
+    }
+    if let Some((schedule, absences, key)) = utilization {
+        let scheduled = col(key.column()).map(
+            move |s| map_group_start_to_scheduled(s, &schedule, &absences, key, period, anchor),
+            GetOutput::from_type(DataType::Duration(TIME_UNIT)),
+        );
+        // half a percent is added before cutting off the fraction, to round it
+        let percent = col(RES_TOTAL_HOURS).cast(DataType::Float64) * lit(100.0)
+            / scheduled.cast(DataType::Float64)
+            + lit(0.5);
+        result_cols.push(percent.cast(DataType::Int64).alias(RES_UTILIZATION));
+    }
+
+    if let Some((absences, key)) = settings.absences(cli_args, keys)? {
+        result_cols.push(
+            col(key.column())
+                .map(
+                    move |s| map_group_start_to_absence(s, &absences, key),
+                    GetOutput::from_type(DataType::String),
+                )
+                .alias(COL_ABSENCE),
+        );
+    }
+
+    let weekday = GroupKey::Day
+        .group_start(anchor)
+        .expect("days are time based")
+        .dt()
+        .weekday();
+    let day_sums = pivot_days.as_ref().or(sparkline_days.as_ref());
+    let pivot_aggs = day_sums.into_iter().flatten().map(|day| {
+        col(COL_DURATION)
+            .filter(weekday.clone().eq(lit(day.number_from_monday())))
+            .sum()
+            .alias(&day.to_string())
+    });
+
+    df = df
+        .with_columns(key_exprs)
+        .group_by(key_cols.clone())
+        .agg(
+            pivot_aggs
+                .chain([
+                    col(COL_DURATION).sum().alias(RES_TOTAL_HOURS),
+                    col(COL_DURATION)
+                        .filter(col(COL_TRAVEL))
+                        .sum()
+                        .alias(RES_TRAVEL_HOURS),
+                    col(COL_DURATION).count().alias(RES_SHIFTS),
+                    col(COL_DURATION)
+                        .cast(DataType::Int64)
+                        .median()
+                        .cast(DataType::Duration(TIME_UNIT))
+                        .alias(RES_MEDIAN_SHIFT_DURATION),
+                    col(COL_DURATION).max().alias(RES_LONGEST_SHIFT),
+                    GroupKey::Day
+                        .group_start(anchor)
+                        .expect("days are time based")
+                        .n_unique()
+                        .alias(COL_DAYS_WORKED),
+                ])
+                .collect::<Vec<_>>(),
+        )
+        .sort_by_exprs(key_cols, vec![false; keys.len()], false, true)
+        .select(result_cols);
+
+    if let Some(window) = rolling_window {
+        // the rows are still in order here, so the window covers the periods before each row
+        let mut rolling = col(RES_TOTAL_HOURS)
+            .cast(DataType::Int64)
+            .cast(DataType::Float64)
+            .rolling_mean(RollingOptions {
+                window_size: Duration::new(window.get() as i64),
+                min_periods: window.get(),
+                ..Default::default()
+            })
+            .cast(DataType::Int64)
+            .cast(DataType::Duration(TIME_UNIT));
+        // e.g. with '--group-by week,project', each project is averaged separately
+        let labels = keys
+            .iter()
+            .filter(|k| !k.is_time_based())
+            .map(|k| col(k.column()))
+            .collect::<Vec<_>>();
+        if !labels.is_empty() {
+            rolling = rolling.over(labels);
+        }
+        df = df.with_column(rolling.alias(RES_ROLLING_AVERAGE));
+    }
+
+    df = apply_filters(df, settings)?;
+    df = apply_sort(df, settings)?;
+
+    if !settings.copyable {
+        df = prepare_for_display(df, settings)?;
+    }
+
+    Ok(df)
+}
+
+/// Formats the durations and dates of a grouped report.
+pub fn prepare_for_display(df: LazyFrame, settings: &ReportSettings) -> Result<LazyFrame> {
+    let exact = settings.exact_durations;
+
+    let schema = df.schema().wrap_err("Failed to determine report columns")?;
+    let has_partial_weeks = schema.contains(RES_DAYS_COVERED);
+    // the marker is added to the dates of the shortest period
+    let absence_column = match schema.contains(COL_ABSENCE) {
+        true => GroupKey::shortest_period(&settings.scope().0).map(|key| key.column()),
+        false => None,
+    };
+    let columns = schema
+        .iter()
+        .filter(|(name, _)| name.as_str() != COL_ABSENCE)
+        .map(|(name, dtype)| {
+            (
+                name,
+                match dtype {
+                    DataType::Duration(_) => col(name).map(
+                        move |s| map_duration_to_str(s, exact),
+                        GetOutput::from_type(DataType::String),
+                    ),
+                    DataType::Datetime(_, _) if name == RES_MONTH => {
+                        col(name).dt().strftime("%B %Y")
+                    }
+                    DataType::Datetime(_, _) if name == RES_WEEK_OF && has_partial_weeks => {
+                        let date = col(name).dt().strftime("%d %B %Y");
+                        when(col(RES_DAYS_COVERED).lt(lit(7)))
+                            .then(date.clone() + lit(PARTIAL_WEEK_MARKER))
+                            .otherwise(date)
+                            .alias(name)
+                    }
+                    DataType::Datetime(_, _) => col(name).dt().strftime("%d %B %Y"),
+                    // nothing was scheduled
+                    _ if name == RES_UTILIZATION => {
+                        col(name).cast(DataType::String).fill_null(lit(""))
+                    }
+                    _ => col(name),
+                },
+            )
+        })
+        .map(|(name, expr)| match absence_column {
+            Some(column) if name == column => when(col(COL_ABSENCE).is_not_null())
+                .then(expr.clone() + col(COL_ABSENCE))
+                .otherwise(expr)
+                .alias(name),
+            _ => expr,
+        })
+        .collect::<Vec<_>>();
+
+    Ok(df.select(columns))
+}
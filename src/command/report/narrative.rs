@@ -0,0 +1,254 @@
+// Copyright (C) 2023 Campbell M. Cole
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+// the narrative is a few sentences on where the time of a report went. it's
+// filled in from a template, unless the config asks an OpenAI-compatible API
+// to write it (in builds with the `narrative` feature)
+
+use std::collections::BTreeMap;
+
+use chrono::{Duration, NaiveDate};
+
+use crate::{config::read_config, prelude::*};
+
+use super::{report_shifts, ReportSettings, ReportType};
+
+/// How many projects the template names before summing up the rest.
+const TEMPLATE_PROJECTS: usize = 3;
+
+/// What the narrative is written from.
+#[derive(Debug)]
+struct Facts {
+    /// The period of the report, e.g. `in October 2026`
+    period: String,
+    total: Duration,
+    travel: Duration,
+    /// The time worked on each day
+    days: BTreeMap<NaiveDate, Duration>,
+    /// The time worked on each project, the most first
+    projects: Vec<(String, Duration)>,
+    notes: Vec<String>,
+}
+
+#[instrument(skip_all)]
+pub(super) fn narrative(cli_args: &Cli, settings: &ReportSettings) -> Result<String> {
+    let facts = gather_facts(cli_args, settings)?;
+
+    #[cfg(feature = "narrative")]
+    if let Some(config) = read_config(cli_args)?.narrative {
+        match ask_api(&config, &facts) {
+            Ok(narrative) => return Ok(narrative),
+            Err(err) => {
+                use owo_colors::OwoColorize;
+                warn!(?err, "failed to write the narrative with the API");
+                eprintln!(
+                    "{}",
+                    format!("Couldn't get the narrative from the API, using the template: {err}")
+                        .yellow()
+                );
+            }
+        }
+    }
+    #[cfg(not(feature = "narrative"))]
+    if read_config(cli_args)?.narrative.is_some() {
+        debug!("the `narrative` feature isn't built, using the template");
+    }
+
+    Ok(template_narrative(&facts))
+}
+
+fn gather_facts(cli_args: &Cli, settings: &ReportSettings) -> Result<Facts> {
+    let report_type = settings.report_type.clone().unwrap_or_default();
+    let (_, period) = settings.scope();
+    let now = Local::now();
+
+    let mut facts = Facts {
+        period: match &report_type {
+            ReportType::Weekly(args) if args.month.as_date().is_some() => {
+                format!("in {}", report_type.describe_period(settings.anchor()))
+            }
+            ReportType::Weekly(_) => "so far".into(),
+            ReportType::Daily => "this week".into(),
+        },
+        total: Duration::zero(),
+        travel: Duration::zero(),
+        days: BTreeMap::new(),
+        projects: Vec::new(),
+        notes: Vec::new(),
+    };
+    let mut projects = BTreeMap::<String, Duration>::new();
+
+    for shift in report_shifts(cli_args, settings)? {
+        let worked = match period.range {
+            Some((from, to)) => shift.overlap(from, to, now),
+            None => shift.duration(now),
+        };
+        if worked <= Duration::zero() {
+            continue;
+        }
+
+        facts.total = facts.total + worked;
+        if shift.is_travel() {
+            facts.travel = facts.travel + worked;
+        }
+        let day = facts
+            .days
+            .entry(shift.start.timestamp.date_naive())
+            .or_insert_with(Duration::zero);
+        *day = *day + worked;
+        let project = projects
+            .entry(shift.project().unwrap_or("no project").to_string())
+            .or_insert_with(Duration::zero);
+        *project = *project + worked;
+        for note in shift.notes() {
+            if !facts.notes.iter().any(|n| n == note) {
+                facts.notes.push(note.to_string());
+            }
+        }
+    }
+
+    facts.projects = projects.into_iter().collect();
+    facts.projects.sort_by(|a, b| b.1.cmp(&a.1));
+    Ok(facts)
+}
+
+fn compact(duration: Duration) -> String {
+    BiDuration::new(duration).to_compact_string()
+}
+
+/// Fills in the template, which only states the facts.
+fn template_narrative(facts: &Facts) -> String {
+    let period = capitalize(&facts.period);
+    if facts.total <= Duration::zero() {
+        return format!("{period} no time was tracked.");
+    }
+
+    let days = facts.days.len();
+    let mut sentences = vec![format!(
+        "{period} you worked {} over {days} day{}.",
+        compact(facts.total),
+        if days == 1 { "" } else { "s" }
+    )];
+
+    let mut named = facts
+        .projects
+        .iter()
+        .take(TEMPLATE_PROJECTS)
+        .map(|(project, worked)| format!("{project} ({})", compact(*worked)))
+        .collect::<Vec<_>>();
+    let rest = facts.projects.get(TEMPLATE_PROJECTS..).unwrap_or_default();
+    if !rest.is_empty() {
+        let worked = rest
+            .iter()
+            .fold(Duration::zero(), |acc, (_, worked)| acc + *worked);
+        named.push(format!("{} others ({})", rest.len(), compact(worked)));
+    }
+    sentences.push(match named.len() {
+        1 => format!("All of it went to {}.", named[0]),
+        _ => {
+            let last = named.pop().unwrap_or_default();
+            format!("Most of it went to {}, then {last}.", named.join(", "))
+        }
+    });
+
+    if days > 1 {
+        if let Some((day, worked)) = facts.days.iter().max_by_key(|(_, worked)| **worked) {
+            sentences.push(format!(
+                "{} was the busiest day with {}.",
+                day.format("%A %-d %B"),
+                compact(*worked)
+            ));
+        }
+    }
+    if facts.travel > Duration::zero() {
+        sentences.push(format!("{} of it was travel.", compact(facts.travel)));
+    }
+    if !facts.notes.is_empty() {
+        // notes written in the editor can span several lines
+        let notes = facts
+            .notes
+            .iter()
+            .map(|note| note.lines().collect::<Vec<_>>().join("; "))
+            .collect::<Vec<_>>()
+            .join("; ");
+        sentences.push(format!("Notes: {notes}."));
+    }
+
+    sentences.join(" ")
+}
+
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(feature = "narrative")]
+const SYSTEM_PROMPT: &str = "You summarize a timesheet for the person who worked it. \
+    Write two to four plain sentences in the second person on where the time went, \
+    using only the figures given. Don't use markdown or lists.";
+
+/// Asks the API in the config to write the narrative from the facts.
+#[cfg(feature = "narrative")]
+fn ask_api(config: &crate::config::Narrative, facts: &Facts) -> Result<String> {
+    let key = std::env::var(&config.api_key_env)
+        .wrap_err_with(|| format!("{} isn't set", config.api_key_env))?;
+
+    let mut prompt = vec![format!(
+        "Period: {}\nTotal: {}\nTravel: {}",
+        facts.period,
+        compact(facts.total),
+        compact(facts.travel)
+    )];
+    prompt.push("Days:".into());
+    for (day, worked) in &facts.days {
+        prompt.push(format!(
+            "- {} {}",
+            day.format("%A %-d %B"),
+            compact(*worked)
+        ));
+    }
+    prompt.push("Projects:".into());
+    for (project, worked) in &facts.projects {
+        prompt.push(format!("- {project} {}", compact(*worked)));
+    }
+    prompt.push("Notes:".into());
+    for note in &facts.notes {
+        prompt.push(format!("- {}", note.lines().collect::<Vec<_>>().join("; ")));
+    }
+
+    let body = serde_json::json!({
+        "model": config.model,
+        "messages": [
+            { "role": "system", "content": SYSTEM_PROMPT },
+            { "role": "user", "content": prompt.join("\n") },
+        ],
+    });
+    let response: serde_json::Value = ureq::AgentBuilder::new()
+        .timeout(std::time::Duration::from_secs(30))
+        .build()
+        .post(&config.endpoint)
+        .set("Authorization", &format!("Bearer {key}"))
+        .send_json(body)?
+        .into_json()?;
+
+    response["choices"][0]["message"]["content"]
+        .as_str()
+        .map(|content| content.trim().to_string())
+        .filter(|content| !content.is_empty())
+        .ok_or_else(|| eyre!("The response didn't include a summary"))
+}
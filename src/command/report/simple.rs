@@ -0,0 +1,470 @@
+// Copyright (C) 2023 Campbell M. Cole
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::Write,
+};
+
+use chrono::{Datelike, NaiveDate};
+use chrono_tz::Tz;
+
+use crate::{location::location_of, prelude::*, table::StringTable};
+
+use super::{
+    format_duration, group_absence_marker, scheduled_time, sparkline, utilization_percent, Anchor,
+    GroupKey, ReportPeriod, ReportSettings, ISO_WEEK_FORMAT, NONE_LABEL, PARTIAL_WEEK_MARKER,
+    RES_AVERAGE_SHIFT_DURATION, RES_DAILY_AVERAGE, RES_DAYS_COVERED, RES_LONGEST_SHIFT,
+    RES_MEDIAN_SHIFT_DURATION, RES_SHIFTS, RES_SPARKLINE, RES_TOTAL_HOURS, RES_TRAVEL_HOURS,
+    RES_UTILIZATION, RES_WEEK_END, RES_WEEK_NUMBER,
+};
+
+/// A finished shift, with the project and tags it was worked on.
+#[derive(Debug)]
+struct ReportShift {
+    end: DateTime<Local>,
+    duration: chrono::Duration,
+    project: String,
+    tags: Vec<String>,
+}
+
+impl ReportShift {
+    fn is_travel(&self) -> bool {
+        self.tags.iter().any(|tag| tag == TRAVEL_TAG)
+    }
+}
+
+/// The totals of one group of shifts.
+#[derive(Debug)]
+struct Group {
+    total: chrono::Duration,
+    travel: chrono::Duration,
+    /// The duration of each shift, for the median and longest shift
+    durations: Vec<chrono::Duration>,
+    /// The days with a shift in the group, for the daily average
+    days: BTreeSet<DateTime<Tz>>,
+    /// The time worked on each day of the week from Monday, for '--pivot-days'
+    weekdays: [chrono::Duration; 7],
+}
+
+impl Group {
+    /// The middle duration, or the average of the two middle ones, like polars.
+    fn median(&mut self) -> chrono::Duration {
+        self.durations.sort();
+        let middle = self.durations.len() / 2;
+        match self.durations.len() % 2 {
+            0 => (self.durations[middle - 1] + self.durations[middle]) / 2,
+            _ => self.durations[middle],
+        }
+    }
+}
+
+impl Default for Group {
+    fn default() -> Self {
+        Self {
+            total: chrono::Duration::zero(),
+            travel: chrono::Duration::zero(),
+            durations: Vec::new(),
+            days: BTreeSet::new(),
+            weekdays: [chrono::Duration::zero(); 7],
+        }
+    }
+}
+
+/// One part of the key of a group, ordered like the columns of the polars engine.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum KeyPart {
+    Time(DateTime<Tz>),
+    Label(String),
+}
+
+/// Returns an error for the options which only the polars engine supports.
+fn check_supported(settings: &ReportSettings) -> Result<()> {
+    let unsupported = [
+        ("--sort", !settings.sort.is_empty()),
+        ("--where", !settings.filters.is_empty()),
+        ("--copyable", settings.copyable),
+        ("--rolling", settings.rolling.is_some()),
+    ];
+    match unsupported.iter().find(|(_, used)| *used) {
+        Some((option, _)) => Err(eyre!(
+            "'{option}' isn't supported by the simple report engine"
+        ))
+        .with_suggestion(|| match cfg!(feature = "reports") {
+            true => "Use '--engine polars' instead",
+            false => "This option needs a build of punchcard with the `reports` feature",
+        }),
+        None => Ok(()),
+    }
+}
+
+/// The start of the day, week, or month containing `time`, in the report timezone.
+fn truncate(time: DateTime<Local>, key: GroupKey, tz: Tz, anchor: Anchor) -> DateTime<Tz> {
+    // like the polars engine, the groups are moved by subtracting the offset
+    // before truncating and adding it back afterwards
+    let offset = anchor.offset(key);
+    let date = (time.with_timezone(&tz) - offset).date_naive();
+    let date = match key {
+        GroupKey::Day => date,
+        GroupKey::Week => {
+            date - chrono::Duration::days(date.weekday().num_days_from_monday() as i64)
+        }
+        GroupKey::Month => NaiveDate::from_ymd_opt(date.year(), date.month(), 1).unwrap(),
+        GroupKey::Project | GroupKey::Tag | GroupKey::Location => {
+            unreachable!("only time based keys are truncated")
+        }
+    };
+    let midnight = date.and_hms_opt(0, 0, 0).unwrap();
+    // midnight can be skipped by a DST transition, fall back to UTC midnight then
+    let start = tz
+        .from_local_datetime(&midnight)
+        .earliest()
+        .unwrap_or_else(|| tz.from_utc_datetime(&midnight));
+    start + offset
+}
+
+fn read_shifts(cli_args: &Cli, settings: &ReportSettings) -> Result<Vec<ReportShift>> {
+    let mut entries = if settings.lenient {
+        let skipped = crate::csv::malformed_rows(cli_args)?.len();
+        if skipped > 0 {
+            use owo_colors::OwoColorize;
+            eprintln!(
+                "{}",
+                format!("Skipped {skipped} row(s) that couldn't be parsed").yellow()
+            );
+        }
+        crate::csv::read_valid_entries(cli_args)?
+    } else {
+        crate::csv::read_entries(cli_args)?
+    };
+    entries.sort_by_key(|entry| entry.timestamp);
+
+    // like the polars engine, each clock-out is paired with the entry before it
+    Ok(entries
+        .windows(2)
+        .filter_map(|pair| {
+            let (start, end) = (&pair[0], &pair[1]);
+            if end.entry_type != EntryType::ClockOut {
+                return None;
+            }
+            if let Some(source) = &settings.source {
+                if start.source != *source && end.source != *source {
+                    return None;
+                }
+            }
+            if let Some(project) = &settings.project {
+                // the project is usually only given when clocking in
+                if start.project.as_ref() != Some(project) && end.project.as_ref() != Some(project)
+                {
+                    return None;
+                }
+            }
+
+            let tags = match start.tags.is_empty() {
+                true => &end.tags,
+                false => &start.tags,
+            };
+            Some(ReportShift {
+                end: end.timestamp,
+                duration: end.timestamp - start.timestamp,
+                project: start
+                    .project
+                    .clone()
+                    .or_else(|| end.project.clone())
+                    .unwrap_or_else(|| NONE_LABEL.to_string()),
+                tags: tags.to_vec(),
+            })
+        })
+        .collect())
+}
+
+/// Reads the shifts in the period and adds them up by the keys, and whether
+/// any of them were spent travelling.
+fn group_shifts(
+    cli_args: &Cli,
+    settings: &ReportSettings,
+    keys: &[GroupKey],
+    period: ReportPeriod,
+) -> Result<(BTreeMap<Vec<KeyPart>, Group>, bool)> {
+    let tz = cli_args.timezone;
+    let anchor = settings.anchor();
+    let mut shifts = read_shifts(cli_args, settings)?;
+
+    if let Some((from, to)) = period.range {
+        // with spill over, whole groups are kept as long as they overlap the period
+        match period.spill_over_key(keys) {
+            Some((key, length)) => shifts.retain(|shift| {
+                let group_start = truncate(shift.end, key, tz, anchor);
+                group_start < to.with_timezone(&tz)
+                    && group_start + length > from.with_timezone(&tz)
+            }),
+            None => shifts.retain(|shift| shift.end >= from && shift.end < to),
+        }
+    }
+
+    let has_travel = shifts.iter().any(ReportShift::is_travel);
+    let mut groups = BTreeMap::<Vec<KeyPart>, Group>::new();
+    for shift in &shifts {
+        // a shift with several tags is counted once for each of them
+        let tags = match keys.contains(&GroupKey::Tag) {
+            true if shift.tags.is_empty() => vec![NONE_LABEL],
+            true => shift
+                .tags
+                .iter()
+                .map(|tag| match tag.is_empty() {
+                    true => NONE_LABEL,
+                    false => tag.as_str(),
+                })
+                .collect(),
+            false => vec![NONE_LABEL],
+        };
+
+        for tag in tags {
+            let key = keys
+                .iter()
+                .map(|key| match key {
+                    GroupKey::Project => KeyPart::Label(shift.project.clone()),
+                    GroupKey::Tag => KeyPart::Label(tag.to_string()),
+                    GroupKey::Location => {
+                        KeyPart::Label(location_of(&shift.tags).unwrap_or(NONE_LABEL).to_string())
+                    }
+                    time_key => KeyPart::Time(truncate(shift.end, *time_key, tz, anchor)),
+                })
+                .collect();
+            let group = groups.entry(key).or_default();
+            group.total = group.total + shift.duration;
+            if shift.is_travel() {
+                group.travel = group.travel + shift.duration;
+            }
+            group.durations.push(shift.duration);
+            let day = truncate(shift.end, GroupKey::Day, tz, anchor);
+            let weekday = &mut group.weekdays[day.weekday().num_days_from_monday() as usize];
+            *weekday = *weekday + shift.duration;
+            group.days.insert(day);
+        }
+    }
+    Ok((groups, has_travel))
+}
+
+/// The total time worked in each period of a time based `key`, in order.
+///
+/// Used for the parts of a report which need numbers instead of formatted cells.
+#[cfg(feature = "charts")]
+pub fn period_totals(
+    cli_args: &Cli,
+    settings: &ReportSettings,
+    key: GroupKey,
+    period: ReportPeriod,
+) -> Result<Vec<(DateTime<Tz>, chrono::Duration)>> {
+    let (groups, _) = group_shifts(cli_args, settings, &[key], period)?;
+    Ok(groups
+        .into_iter()
+        .filter_map(|(parts, group)| match parts.first() {
+            Some(KeyPart::Time(start)) => Some((*start, group.total)),
+            _ => None,
+        })
+        .collect())
+}
+
+/// Adds up the shifts in the period without polars.
+///
+/// This produces the same table as the polars engine, already formatted for display.
+#[instrument]
+pub fn generate_simple_report(
+    cli_args: &Cli,
+    settings: &ReportSettings,
+    keys: &[GroupKey],
+    period: ReportPeriod,
+) -> Result<StringTable> {
+    check_supported(settings)?;
+    let utilization = settings.utilization(cli_args, keys)?;
+    let absences = settings.absences(cli_args, keys)?;
+    let pivot_days = settings.pivot_days(keys)?;
+    let sparkline_days = settings.sparkline_days(keys)?;
+    // like a paper timesheet, the pivoted days replace the columns about the week and its shifts
+    let timesheet = pivot_days.is_some();
+    let anchor = settings.anchor();
+
+    let (groups, has_travel) = group_shifts(cli_args, settings, keys, period)?;
+
+    let mut key_cells = vec![Vec::with_capacity(groups.len()); keys.len()];
+    let mut total_cells = Vec::with_capacity(groups.len());
+    let mut travel_cells = Vec::with_capacity(groups.len());
+    let mut week_end_cells = Vec::with_capacity(groups.len());
+    let mut week_number_cells = Vec::with_capacity(groups.len());
+    let mut days_covered_cells = Vec::with_capacity(groups.len());
+    let has_partial_weeks = period.has_partial_weeks(keys, anchor) && !timesheet;
+    // the bars are scaled to the longest day in the whole report
+    let longest_day = groups
+        .values()
+        .flat_map(|group| group.weekdays)
+        .max()
+        .unwrap_or_else(chrono::Duration::zero);
+    let mut sparkline_cells = Vec::with_capacity(groups.len());
+    let mut pivot_cells = (0..7)
+        .map(|_| Vec::with_capacity(groups.len()))
+        .collect::<Vec<_>>();
+    let mut count_cells = Vec::with_capacity(groups.len());
+    let mut average_cells = Vec::with_capacity(groups.len());
+    let mut daily_average_cells = Vec::with_capacity(groups.len());
+    let mut utilization_cells = Vec::with_capacity(groups.len());
+    let mut median_cells = Vec::with_capacity(groups.len());
+    let mut longest_cells = Vec::with_capacity(groups.len());
+    for (key, mut group) in groups {
+        let count = group.durations.len();
+        if let Some((schedule, absences, utilization_key)) = &utilization {
+            let start = keys.iter().zip(&key).find_map(|(k, part)| match part {
+                KeyPart::Time(time) if k == utilization_key => Some(time.with_timezone(&Local)),
+                _ => None,
+            });
+            let scheduled = scheduled_time(
+                schedule,
+                absences,
+                *utilization_key,
+                start.expect("the key of the utilization is one of the keys"),
+                &period,
+                anchor,
+            );
+            utilization_cells.push(
+                utilization_percent(group.total, scheduled)
+                    .map(|percent| percent.to_string())
+                    .unwrap_or_default(),
+            );
+        }
+        for ((cells, part), group_key) in key_cells.iter_mut().zip(key).zip(keys) {
+            let marker = match (&part, &absences) {
+                (KeyPart::Time(time), Some((absences, key))) if key == group_key => {
+                    group_absence_marker(absences, *key, time.with_timezone(&Local))
+                }
+                _ => None,
+            };
+            let cell = match part {
+                KeyPart::Time(time) if *group_key == GroupKey::Month => {
+                    time.format("%B %Y").to_string()
+                }
+                KeyPart::Time(time) => {
+                    let mut cell = time.format("%d %B %Y").to_string();
+                    if *group_key == GroupKey::Week {
+                        week_end_cells.push(
+                            (time + chrono::Duration::weeks(1))
+                                .format("%d %B %Y")
+                                .to_string(),
+                        );
+                        week_number_cells.push(time.format(ISO_WEEK_FORMAT).to_string());
+                        if has_partial_weeks {
+                            let days = period.days_covered(time.with_timezone(&Local));
+                            days_covered_cells.push(days.to_string());
+                            if days < 7 {
+                                cell.push_str(PARTIAL_WEEK_MARKER);
+                            }
+                        }
+                    }
+                    cell
+                }
+                KeyPart::Label(label) => label,
+            };
+            cells.push(cell + marker.as_deref().unwrap_or_default());
+        }
+        total_cells.push(format_duration(group.total, settings.exact_durations));
+        travel_cells.push(format_duration(group.travel, settings.exact_durations));
+        count_cells.push(count.to_string());
+        average_cells.push(format_duration(
+            group.total / count as i32,
+            settings.exact_durations,
+        ));
+        median_cells.push(format_duration(group.median(), settings.exact_durations));
+        let longest = *group
+            .durations
+            .iter()
+            .max()
+            .expect("every group has at least one shift");
+        longest_cells.push(format_duration(longest, settings.exact_durations));
+        daily_average_cells.push(format_duration(
+            group.total / group.days.len() as i32,
+            settings.exact_durations,
+        ));
+        if let Some(days) = &sparkline_days {
+            sparkline_cells.push(sparkline(
+                days.iter()
+                    .map(|day| group.weekdays[day.num_days_from_monday() as usize]),
+                longest_day,
+            ));
+        }
+        for (cells, worked) in pivot_cells.iter_mut().zip(group.weekdays) {
+            // days without any shifts are left empty
+            cells.push(match worked > chrono::Duration::zero() {
+                true => format_duration(worked, settings.exact_durations),
+                false => String::new(),
+            });
+        }
+    }
+
+    let mut table = StringTable::new();
+    for (key, cells) in keys.iter().zip(key_cells) {
+        table = table.with_column(key.column(), "str", cells);
+    }
+    for day in pivot_days.iter().flatten() {
+        let cells = std::mem::take(&mut pivot_cells[day.num_days_from_monday() as usize]);
+        table = table.with_column(day.to_string(), "str", cells);
+    }
+    table = table.with_column(RES_TOTAL_HOURS, "str", total_cells);
+    if sparkline_days.is_some() {
+        table = table.with_column(RES_SPARKLINE, "str", sparkline_cells);
+    }
+    if has_travel && !timesheet {
+        table = table.with_column(RES_TRAVEL_HOURS, "str", travel_cells);
+    }
+    if keys.contains(&GroupKey::Week) {
+        if !timesheet {
+            table = table.with_column(RES_WEEK_END, "str", week_end_cells);
+        }
+        if has_partial_weeks {
+            table = table.with_column(RES_DAYS_COVERED, "i64", days_covered_cells);
+        }
+        if settings.week_numbers {
+            table = table.with_column(RES_WEEK_NUMBER, "str", week_number_cells);
+        }
+    }
+    if !timesheet {
+        table = table
+            .with_column(RES_SHIFTS, "u32", count_cells)
+            .with_column(RES_AVERAGE_SHIFT_DURATION, "str", average_cells);
+    }
+    if settings.median_shift {
+        table = table.with_column(RES_MEDIAN_SHIFT_DURATION, "str", median_cells);
+    }
+    if settings.longest_shift {
+        table = table.with_column(RES_LONGEST_SHIFT, "str", longest_cells);
+    }
+    if settings.daily_average {
+        table = table.with_column(RES_DAILY_AVERAGE, "str", daily_average_cells);
+    }
+    if utilization.is_some() {
+        table = table.with_column(RES_UTILIZATION, "i64", utilization_cells);
+    }
+    Ok(table)
+}
+
+/// Writes a report made by the simple engine as CSV.
+pub fn write_csv(table: &StringTable, writer: impl Write) -> Result<()> {
+    use crate::table::TableSource;
+    let mut writer = ::csv::Writer::from_writer(writer);
+    writer.write_record((0..table.width()).map(|col| table.column(col).0.into_owned()))?;
+    for row in 0..table.height() {
+        writer.write_record((0..table.width()).map(|col| table.cell(row, col).into_owned()))?;
+    }
+    writer.flush()?;
+    Ok(())
+}
@@ -0,0 +1,110 @@
+// Copyright (C) 2023 Campbell M. Cole
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+// every report written with '-o' is remembered row by row, so that
+// '--changed-only' can show just the rows which differ from what was exported
+
+use std::collections::BTreeMap;
+
+use clap::ValueEnum;
+
+use crate::{prelude::*, table::TableSource};
+
+use super::{GroupKey, ReportSettings, ReportType};
+
+/// The exported rows of each kind of report, by the cells of their group columns.
+pub type Snapshots = BTreeMap<String, BTreeMap<String, Vec<String>>>;
+
+pub fn read_snapshots(cli_args: &Cli) -> Result<Snapshots> {
+    read_json_file(&cli_args.get_snapshots_file())
+}
+
+pub fn write_snapshots(cli_args: &Cli, snapshots: &Snapshots) -> Result<()> {
+    write_json_file(&cli_args.get_snapshots_file(), snapshots)
+}
+
+/// Identifies the kind of report, e.g. `weekly -g week,project --exact`.
+///
+/// Everything which changes how the rows are grouped, filtered, or formatted
+/// is part of it, so only rows which mean the same thing are compared.
+pub fn report_key(settings: &ReportSettings, keys: &[GroupKey]) -> String {
+    let report_type = settings.report_type.as_ref().cloned().unwrap_or_default();
+    let mut key = match report_type {
+        ReportType::Weekly(_) => "weekly",
+        ReportType::Daily => "daily",
+    }
+    .to_string();
+    if let ReportType::Weekly(args) = &report_type {
+        if args.pivot_days {
+            key.push_str(" --pivot-days");
+        }
+    }
+
+    let keys = keys
+        .iter()
+        .filter_map(|k| Some(k.to_possible_value()?.get_name().to_string()))
+        .collect::<Vec<_>>();
+    key.push_str(&format!(" -g {}", keys.join(",")));
+    if let Some(project) = &settings.project {
+        key.push_str(&format!(" --project {project}"));
+    }
+    if let Some(source) = &settings.source {
+        key.push_str(&format!(" --source {source}"));
+    }
+    if settings.exact_durations {
+        key.push_str(" --exact");
+    }
+    if let Some(boundary) = settings.day_boundary {
+        key.push_str(&format!(" --day-boundary {}", boundary.format("%H:%M")));
+    }
+    if let Some(week_start) = settings.week_start {
+        key.push_str(&format!(" --week-start {week_start}"));
+    }
+    key
+}
+
+/// The cells of every row of the report, by the cells of its group columns.
+pub fn report_rows(table: &dyn TableSource, keys: &[GroupKey]) -> Vec<(String, Vec<String>)> {
+    let key_columns = (0..table.width())
+        .filter(|&col| {
+            let (name, _) = table.column(col);
+            keys.iter().any(|k| k.column() == name)
+        })
+        .collect::<Vec<_>>();
+
+    (0..table.height())
+        .map(|row| {
+            let key = key_columns
+                .iter()
+                .map(|&col| table.cell(row, col))
+                .collect::<Vec<_>>()
+                .join(" / ");
+            let cells = (0..table.width())
+                .map(|col| table.cell(row, col).into_owned())
+                .collect();
+            (key, cells)
+        })
+        .collect()
+}
+
+/// Which of the rows are new or differ from the exported ones.
+pub fn changed_rows(
+    rows: &[(String, Vec<String>)],
+    exported: Option<&BTreeMap<String, Vec<String>>>,
+) -> Vec<bool> {
+    rows.iter()
+        .map(|(key, cells)| exported.and_then(|e| e.get(key)) != Some(cells))
+        .collect()
+}
@@ -0,0 +1,205 @@
+// Copyright (C) 2023 Campbell M. Cole
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+// the scripts printed here are sourced by the shell and run 'shell-hook
+// --check' on the first prompt of each day, which offers to clock in
+
+use std::{
+    io::{self, IsTerminal, Write},
+    path::Path,
+};
+
+use chrono::NaiveDate;
+use clap::ValueEnum;
+
+use crate::{config::read_config, prelude::*, shift::start_of_day};
+
+use super::{
+    clock::add_entry_inner,
+    status::{get_clock_status_inner, ClockStatusType},
+};
+
+#[derive(Debug, Args)]
+pub struct ShellHookArgs {
+    /// The shell to print the hook for
+    #[clap(value_enum, required_unless_present = "check")]
+    pub shell: Option<HookShell>,
+    /// Run the check of the first prompt of the day (used by the hook)
+    #[clap(long, hide = true, conflicts_with = "shell")]
+    pub check: bool,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum HookShell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+/// Remembers the last check, so only the first prompt in any shell offers to clock in.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct ShellHookState {
+    last_check: Option<NaiveDate>,
+}
+
+#[instrument]
+pub fn shell_hook(cli_args: &Cli, ShellHookArgs { shell, check }: &ShellHookArgs) -> Result<()> {
+    match shell {
+        Some(shell) => print_hook(cli_args, *shell),
+        None if *check => check_first_prompt(cli_args),
+        None => unreachable!("clap requires a shell without --check"),
+    }
+}
+
+fn print_hook(cli_args: &Cli, shell: HookShell) -> Result<()> {
+    let exe = std::env::current_exe().wrap_err("Failed to find the punchcard binary")?;
+    // the hook runs in whatever directory the prompt is in
+    let data_folder = std::fs::canonicalize(&cli_args.data_folder)
+        .unwrap_or_else(|_| cli_args.data_folder.clone());
+    let command = match shell {
+        HookShell::Bash | HookShell::Zsh => {
+            format!(
+                "{} -d {} shell-hook --check",
+                quote_sh(&exe),
+                quote_sh(&data_folder)
+            )
+        }
+        HookShell::Fish => format!(
+            "{} -d {} shell-hook --check",
+            quote_fish(&exe),
+            quote_fish(&data_folder)
+        ),
+    };
+
+    // each script keeps the day of its last check in a variable, so punchcard
+    // itself only runs on the first prompt of the day in every shell
+    let script = match shell {
+        HookShell::Bash => format!(
+            r#"# add this to ~/.bashrc: eval "$(punchcard shell-hook bash)"
+_punchcard_hook() {{
+    local today
+    printf -v today '%(%F)T' -1
+    [[ $today == "$_punchcard_day" ]] && return
+    _punchcard_day=$today
+    {command}
+}}
+if [[ ";${{PROMPT_COMMAND[*]}};" != *";_punchcard_hook;"* ]]; then
+    PROMPT_COMMAND="_punchcard_hook${{PROMPT_COMMAND:+;$PROMPT_COMMAND}}"
+fi
+"#
+        ),
+        HookShell::Zsh => format!(
+            r#"# add this to ~/.zshrc: eval "$(punchcard shell-hook zsh)"
+_punchcard_hook() {{
+    local today=${{(%):-%D{{%F}}}}
+    [[ $today == "$_punchcard_day" ]] && return
+    _punchcard_day=$today
+    {command}
+}}
+autoload -Uz add-zsh-hook
+add-zsh-hook precmd _punchcard_hook
+"#
+        ),
+        HookShell::Fish => format!(
+            r#"# add this to ~/.config/fish/config.fish: punchcard shell-hook fish | source
+function _punchcard_hook --on-event fish_prompt
+    set -l today (date +%F)
+    test "$today" = "$_punchcard_day"; and return
+    set -g _punchcard_day $today
+    {command}
+end
+"#
+        ),
+    };
+    print!("{script}");
+    Ok(())
+}
+
+fn quote_sh(path: &Path) -> String {
+    format!("'{}'", path.display().to_string().replace('\'', "'\\''"))
+}
+
+fn quote_fish(path: &Path) -> String {
+    let path = path.display().to_string();
+    format!("'{}'", path.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+fn check_first_prompt(cli_args: &Cli) -> Result<()> {
+    use owo_colors::OwoColorize;
+
+    let now = Local::now();
+    let path = cli_args.get_shell_hook_file();
+    let mut state: ShellHookState = read_json_file(&path)?;
+    if state.last_check == Some(now.date_naive()) {
+        return Ok(());
+    }
+    state.last_check = Some(now.date_naive());
+    write_json_file(&path, &state)?;
+
+    let status = get_clock_status_inner(cli_args, now)?;
+    let started_today = status.since.is_some_and(|since| since >= start_of_day(now));
+    if let ClockStatusType::Entry(EntryType::ClockIn) = status.status_type {
+        if let Some(since) = status.since.filter(|_| !started_today) {
+            println!(
+                "{}",
+                format!(
+                    "You have been clocked in since {}. Don't forget to clock out!",
+                    since.format(SLIM_DATETIME)
+                )
+                .yellow()
+            );
+        }
+        return Ok(());
+    }
+    if started_today {
+        return Ok(());
+    }
+
+    let config = read_config(cli_args)?;
+    if config
+        .schedule
+        .as_ref()
+        .is_some_and(|s| s.workday(now).is_none())
+    {
+        return Ok(());
+    }
+    let settings = config.shell_hook.unwrap_or_default();
+
+    if !settings.auto_clock_in {
+        if !io::stdin().is_terminal() {
+            println!("{}", "You haven't clocked in today".yellow());
+            return Ok(());
+        }
+        print!(
+            "{} ",
+            "You haven't clocked in today. Clock in now? [y/N]".yellow()
+        );
+        io::stdout().flush().wrap_err("Failed to write to stdout")?;
+        let mut answer = String::new();
+        io::stdin()
+            .read_line(&mut answer)
+            .wrap_err("Failed to read the answer")?;
+        if !answer.trim().eq_ignore_ascii_case("y") {
+            return Ok(());
+        }
+    }
+
+    let mut entry = Entry::new(EntryType::ClockIn, Local::now(), EntrySource::Cli);
+    entry.project = settings.project;
+    let status = get_clock_status_inner(cli_args, entry.timestamp)?;
+    add_entry_inner(cli_args, entry, &None, status)?;
+    Ok(())
+}
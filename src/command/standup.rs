@@ -0,0 +1,147 @@
+// Copyright (C) 2023 Campbell M. Cole
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+// the standup summary lists what was worked on during the last day worked,
+// by project, as plain text bullets which paste well into chat apps
+
+use chrono::{Duration, NaiveDate};
+
+use crate::{
+    clipboard::copy_to_clipboard,
+    prelude::*,
+    shift::{read_shifts, start_of_date, Shift},
+};
+
+#[derive(Debug, Args)]
+pub struct StandupArgs {
+    /// The day to summarize, e.g. `2024-03-01`. Defaults to the last day
+    /// worked before today, so Monday's standup covers Friday.
+    #[clap(long)]
+    pub day: Option<NaiveDate>,
+    /// Also copy the summary to the clipboard
+    #[clap(short, long, default_value_t = false)]
+    pub copy: bool,
+}
+
+/// The time worked on a project during the day, and the notes of its shifts.
+struct ProjectSummary<'a> {
+    project: Option<&'a str>,
+    worked: Duration,
+    notes: Vec<&'a str>,
+}
+
+#[instrument]
+pub fn print_standup(cli_args: &Cli, StandupArgs { day, copy }: &StandupArgs) -> Result<()> {
+    let now = Local::now();
+    let shifts = read_shifts(cli_args)?;
+
+    let day = match day {
+        Some(day) => *day,
+        None => {
+            let today = now.date_naive();
+            let Some(day) = shifts
+                .iter()
+                .map(|s| s.start.timestamp.date_naive())
+                .filter(|d| *d < today)
+                .max()
+            else {
+                return Err(ErrorCode::NotFound.report("You haven't worked before today"))
+                    .suggestion("Pass '--day' to summarize today");
+            };
+            day
+        }
+    };
+
+    let summary = standup_summary(&shifts, day, now);
+    println!("{summary}");
+
+    if *copy {
+        use owo_colors::OwoColorize;
+        copy_to_clipboard(&summary)?;
+        eprintln!("{}", "Copied to the clipboard".green());
+    }
+
+    Ok(())
+}
+
+fn standup_summary(shifts: &[Shift], day: NaiveDate, now: DateTime<Local>) -> String {
+    let (Some(from), Some(to)) = (start_of_date(day), day.succ_opt().and_then(start_of_date))
+    else {
+        return format!("*{}*\n- nothing tracked", day.format("%A %-d %B"));
+    };
+
+    // projects in the order they were first worked on that day
+    let mut projects: Vec<ProjectSummary> = Vec::new();
+    for shift in shifts {
+        let worked = shift.overlap(from, to, now);
+        if worked <= Duration::zero() {
+            continue;
+        }
+        let project = shift
+            .start
+            .project
+            .as_deref()
+            .or(shift.end.as_ref().and_then(|e| e.project.as_deref()));
+        let index = match projects.iter().position(|p| p.project == project) {
+            Some(index) => index,
+            None => {
+                projects.push(ProjectSummary {
+                    project,
+                    worked: Duration::zero(),
+                    notes: Vec::new(),
+                });
+                projects.len() - 1
+            }
+        };
+        let summary = &mut projects[index];
+        summary.worked = summary.worked + worked;
+        for note in shift.notes() {
+            if !summary.notes.contains(&note) {
+                summary.notes.push(note);
+            }
+        }
+    }
+
+    let total = projects
+        .iter()
+        .fold(Duration::zero(), |acc, p| acc + p.worked);
+    let mut lines = vec![format!(
+        "*{}* ({})",
+        day.format("%A %-d %B"),
+        BiDuration::new(total).to_compact_string()
+    )];
+    if projects.is_empty() {
+        lines.push("- nothing tracked".into());
+    }
+    for summary in &projects {
+        let mut line = format!(
+            "- {} ({})",
+            summary.project.unwrap_or("no project"),
+            BiDuration::new(summary.worked).to_compact_string()
+        );
+        if !summary.notes.is_empty() {
+            // notes written in the editor can span several lines
+            let notes = summary
+                .notes
+                .iter()
+                .map(|note| note.lines().collect::<Vec<_>>().join("; "))
+                .collect::<Vec<_>>()
+                .join("; ");
+            line.push_str(&format!(": {notes}"));
+        }
+        lines.push(line);
+    }
+    lines.join("\n")
+}
@@ -0,0 +1,268 @@
+// Copyright (C) 2023 Campbell M. Cole
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+// the work pattern is learned from the recent history in the data file: when
+// the first shift of each weekday usually starts, when the last one ends, and
+// how long is worked in total
+
+use chrono::{Datelike, Duration, NaiveDate, NaiveTime, Timelike, Weekday};
+
+use crate::{
+    prelude::*,
+    shift::{read_shifts, start_of_date, total_between, Shift},
+    table::{settings::TableSettings, table_config, DataFrameDisplay, StringTable},
+};
+
+const RES_WEEKDAY: &str = "Weekday";
+const RES_DAYS_WORKED: &str = "Days Worked";
+const RES_USUAL_START: &str = "Usual Start";
+const RES_USUAL_END: &str = "Usual End";
+const RES_AVERAGE_HOURS: &str = "Avg. Hours";
+
+/// How many weeks of history the pattern is learned from by default
+const DEFAULT_WEEKS: u32 = 12;
+/// A weekday needs this many worked days before it is considered a workday.
+const MIN_DAYS_WORKED: usize = 3;
+
+#[derive(Debug, Args)]
+pub struct StatsArgs {
+    #[clap(subcommand)]
+    pub operation: StatsOperation,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum StatsOperation {
+    /// Show when you usually start and finish on each weekday
+    ///
+    /// Averages the first clock-in and the last clock-out of every day
+    /// in the last few weeks. 'punchcard status' uses the same pattern to
+    /// point out when you start or finish much later than usual.
+    Pattern {
+        /// How many weeks of history to learn from, not counting today
+        #[clap(long, default_value_t = DEFAULT_WEEKS)]
+        weeks: u32,
+        #[clap(flatten)]
+        table_settings: TableSettings,
+    },
+}
+
+/// How one weekday is usually worked.
+#[derive(Debug, Clone)]
+pub struct DayPattern {
+    pub weekday: Weekday,
+    /// How many of these weekdays were worked, and how many there were
+    /// since the first entry
+    pub days_worked: usize,
+    pub days_observed: usize,
+    /// The average start of the first shift and end of the last shift
+    pub start: Option<NaiveTime>,
+    pub end: Option<NaiveTime>,
+    /// The average time worked on the days which were worked
+    pub hours: Duration,
+}
+
+impl DayPattern {
+    /// Whether this weekday is usually worked.
+    pub fn is_workday(&self) -> bool {
+        self.days_worked >= MIN_DAYS_WORKED && self.days_worked * 2 >= self.days_observed
+    }
+}
+
+/// Learns the pattern of each weekday (Monday first) from the `weeks` before `now`.
+pub fn learn_pattern(shifts: &[Shift], now: DateTime<Local>, weeks: u32) -> Vec<DayPattern> {
+    let today = now.date_naive();
+    let first_day = shifts
+        .first()
+        .map(|s| s.start.timestamp.date_naive())
+        .unwrap_or(today);
+    let from = (today - Duration::weeks(weeks as i64)).max(first_day);
+
+    let mut days: Vec<Vec<(NaiveTime, NaiveTime, Duration)>> = vec![Vec::new(); 7];
+    let mut observed = [0; 7];
+    let mut date = from;
+    while date < today {
+        let index = date.weekday().num_days_from_monday() as usize;
+        observed[index] += 1;
+        if let Some(day) = worked_day(shifts, date, now) {
+            days[index].push(day);
+        }
+        date = date.succ_opt().unwrap_or(today);
+    }
+
+    days.into_iter()
+        .zip(observed)
+        .enumerate()
+        .map(|(index, (worked, days_observed))| DayPattern {
+            weekday: Weekday::try_from(index as u8).unwrap_or(Weekday::Mon),
+            days_worked: worked.len(),
+            days_observed,
+            start: average_time(worked.iter().map(|(start, _, _)| *start)),
+            end: average_time(worked.iter().map(|(_, end, _)| *end)),
+            hours: match worked.len() {
+                0 => Duration::zero(),
+                n => {
+                    worked
+                        .iter()
+                        .fold(Duration::zero(), |acc, (_, _, d)| acc + *d)
+                        / n as i32
+                }
+            },
+        })
+        .collect()
+}
+
+/// The start of the first shift, end of the last shift, and total time worked
+/// on a day, if any shift started on it.
+fn worked_day(
+    shifts: &[Shift],
+    date: NaiveDate,
+    now: DateTime<Local>,
+) -> Option<(NaiveTime, NaiveTime, Duration)> {
+    let started = shifts
+        .iter()
+        .filter(|s| s.start.timestamp.date_naive() == date && s.start.timestamp <= now)
+        .collect::<Vec<_>>();
+    let first = started.first()?;
+    let last = started.last()?;
+    let total = match (start_of_date(date), date.succ_opt().and_then(start_of_date)) {
+        (Some(from), Some(to)) => total_between(shifts, from, to, now),
+        _ => Duration::zero(),
+    };
+    Some((first.start.timestamp.time(), last.end_or(now).time(), total))
+}
+
+fn average_time(times: impl Iterator<Item = NaiveTime>) -> Option<NaiveTime> {
+    let seconds = times
+        .map(|t| t.num_seconds_from_midnight() as u64)
+        .collect::<Vec<_>>();
+    if seconds.is_empty() {
+        return None;
+    }
+    let average = seconds.iter().sum::<u64>() / seconds.len() as u64;
+    NaiveTime::from_num_seconds_from_midnight_opt(average as u32, 0)
+}
+
+fn weekday_name(weekday: Weekday) -> &'static str {
+    match weekday {
+        Weekday::Mon => "Monday",
+        Weekday::Tue => "Tuesday",
+        Weekday::Wed => "Wednesday",
+        Weekday::Thu => "Thursday",
+        Weekday::Fri => "Friday",
+        Weekday::Sat => "Saturday",
+        Weekday::Sun => "Sunday",
+    }
+}
+
+/// Describes how the day at `now` differs from the usual pattern, if it does.
+///
+/// Starting or finishing up to `grace` later than usual isn't worth mentioning.
+pub fn pattern_anomaly(
+    shifts: &[Shift],
+    now: DateTime<Local>,
+    clocked_in: bool,
+    grace: Duration,
+) -> Option<String> {
+    let pattern = learn_pattern(shifts, now, DEFAULT_WEEKS);
+    let day = &pattern[now.weekday().num_days_from_monday() as usize];
+    if !day.is_workday() {
+        return None;
+    }
+    let today = worked_day(shifts, now.date_naive(), now);
+    let weekday = weekday_name(day.weekday);
+
+    match (today, day.start, day.end) {
+        (None, Some(start), _) if now.time() > start + grace => Some(format!(
+            "You usually start around {} on {weekday}s",
+            start.format("%H:%M")
+        )),
+        (Some(_), _, Some(end)) if clocked_in && now.time() > end + grace => Some(format!(
+            "You usually finish around {} on {weekday}s",
+            end.format("%H:%M")
+        )),
+        _ => None,
+    }
+}
+
+#[instrument]
+pub fn show_stats(cli_args: &Cli, StatsArgs { operation }: &StatsArgs) -> Result<()> {
+    match operation {
+        StatsOperation::Pattern {
+            weeks,
+            table_settings,
+        } => show_pattern(cli_args, *weeks, table_settings),
+    }
+}
+
+fn show_pattern(cli_args: &Cli, weeks: u32, table_settings: &TableSettings) -> Result<()> {
+    let shifts = read_shifts(cli_args)?;
+    let pattern = learn_pattern(&shifts, Local::now(), weeks);
+    let time = |t: Option<NaiveTime>| t.map(|t| t.format("%H:%M").to_string()).unwrap_or_default();
+
+    let table = StringTable::new()
+        .with_column(
+            RES_WEEKDAY,
+            "str",
+            pattern
+                .iter()
+                .map(|d| weekday_name(d.weekday).to_string())
+                .collect(),
+        )
+        .with_column(
+            RES_DAYS_WORKED,
+            "str",
+            pattern
+                .iter()
+                .map(|d| format!("{} of {}", d.days_worked, d.days_observed))
+                .collect(),
+        )
+        .with_column(
+            RES_USUAL_START,
+            "time",
+            pattern.iter().map(|d| time(d.start)).collect(),
+        )
+        .with_column(
+            RES_USUAL_END,
+            "time",
+            pattern.iter().map(|d| time(d.end)).collect(),
+        )
+        .with_column(
+            RES_AVERAGE_HOURS,
+            "str",
+            pattern
+                .iter()
+                .map(|d| match d.days_worked {
+                    0 => String::new(),
+                    _ => BiDuration::new(d.hours).to_compact_string(),
+                })
+                .collect(),
+        );
+
+    let display = DataFrameDisplay::new(
+        &table,
+        table_settings,
+        table_config(cli_args),
+        cli_args.accessible,
+    )
+    .with_caption(format!("the last {weeks} weeks"));
+    println!("{display}");
+
+    if shifts.is_empty() {
+        use owo_colors::OwoColorize;
+        println!("{}", HINT_NO_ENTRIES.yellow());
+    }
+
+    Ok(())
+}
@@ -13,20 +13,70 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
-use crate::{csv::build_reader, prelude::*};
+use chrono::{Datelike, Duration};
+use clap::ValueEnum;
 
-use super::clock::ClockEntryArgs;
+use crate::{
+    command::project::{budget_usage, read_projects},
+    config::read_config,
+    csv::{build_reader, Revision},
+    prelude::*,
+    shift::{read_shifts, start_of_date, start_of_day, start_of_week, total_between},
+};
+
+use super::stats::pattern_anomaly;
+
+/// The width of a day in the calendar view
+const CALENDAR_COLUMN_WIDTH: usize = 8;
+/// Starting or finishing this many minutes later than usual isn't pointed out
+const PATTERN_GRACE_MINUTES: i64 = 30;
+
+#[derive(Debug, Args)]
+pub struct StatusArgs {
+    /// The offset from the current time to check the status at
+    #[clap(short, long)]
+    pub offset_from_now: Option<BiDuration>,
+    /// Also show the hours tracked on each day of the week
+    #[clap(short, long, default_value_t = false)]
+    pub calendar: bool,
+    /// Also show the last few completed shifts
+    #[clap(short, long, value_name = "N")]
+    pub recent: Option<usize>,
+    /// How to print the status. The bar formats are a single line for status bars,
+    /// and leave out the calendar and recent shifts
+    #[clap(long, value_enum, default_value_t = StatusFormat::Text)]
+    pub format: StatusFormat,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum StatusFormat {
+    /// The full status report
+    Text,
+    /// JSON for a custom module of Waybar, with the class `in` or `out`
+    Waybar,
+    /// A line of text for polybar, i3blocks, and other status bars
+    Plain,
+}
 
 #[instrument]
 pub fn get_clock_status(
     cli_args: &Cli,
-    ClockEntryArgs { offset_from_now }: &ClockEntryArgs,
+    StatusArgs {
+        offset_from_now,
+        calendar,
+        recent,
+        format,
+    }: &StatusArgs,
 ) -> Result<()> {
     let is_now = offset_from_now.is_none();
     let current_time = offset_from_now.relative_to_now();
 
     let status = get_clock_status_inner(cli_args, current_time)?;
 
+    if *format != StatusFormat::Text {
+        return print_bar_status(cli_args, &status, *format);
+    }
+
     {
         use owo_colors::{DynColors, OwoColorize};
         let gray = DynColors::Rgb(128, 128, 128);
@@ -52,10 +102,12 @@ pub fn get_clock_status(
             }
         );
         let status_str = match status.status_type {
-            ClockStatusType::Entry(entry) => format!("{clocked} {}", entry.colored().bold()),
+            ClockStatusType::Entry(entry) => {
+                format!("{clocked} {}", entry.colored(cli_args.accessible).bold())
+            }
             _ => format!(
                 "{clocked} {} {op}{}{cp})",
-                EntryType::ClockOut.colored().bold(),
+                EntryType::ClockOut.colored(cli_args.accessible).bold(),
                 "no entries".cyan()
             ),
         };
@@ -86,6 +138,86 @@ pub fn get_clock_status(
         );
         println!("{}\n{}\n{}\n{}", header, status_str, since, until);
 
+        let schedule = read_config(cli_args)?.schedule;
+        let has_schedule = schedule.is_some();
+        if let Some((_, end)) = schedule.and_then(|s| s.workday(status.current_time)) {
+            let remaining = BiDuration::new(end - status.current_time);
+            let end_str = end.format("%H:%M").to_string();
+            let clocked_in = matches!(
+                status.status_type,
+                ClockStatusType::Entry(EntryType::ClockIn)
+            );
+            let schedule_str = if *remaining >= Duration::zero() {
+                format!(
+                    "{} {} {op}{}{cp}",
+                    remaining.to_compact_string().green().bold(),
+                    "until scheduled end".color(gray),
+                    end_str.cyan()
+                )
+            } else if clocked_in {
+                format!(
+                    "{} {op}{}{cp}",
+                    format!("{} past scheduled end", remaining.to_compact_string())
+                        .red()
+                        .bold(),
+                    end_str.cyan()
+                )
+            } else {
+                format!("{} {}", "Ended at".color(gray), end_str.cyan())
+            };
+            println!("  {} {}", "Workday:".bold().bright_blue(), schedule_str);
+        }
+
+        let shifts = read_shifts(cli_args)?;
+
+        // a schedule says when the workday is, so the learned pattern is only
+        // used without one
+        if !has_schedule {
+            let grace = Duration::minutes(PATTERN_GRACE_MINUTES);
+            let clocked_in = matches!(
+                status.status_type,
+                ClockStatusType::Entry(EntryType::ClockIn)
+            );
+            if let Some(anomaly) = pattern_anomaly(&shifts, status.current_time, clocked_in, grace)
+            {
+                println!("  {} {}", "Pattern:".bold().bright_blue(), anomaly.yellow());
+            }
+        }
+
+        let current_project = shifts
+            .iter()
+            .rev()
+            .find(|s| s.start.timestamp <= status.current_time)
+            .filter(|s| s.end_or(status.current_time) >= status.current_time)
+            .and_then(|s| s.start.project.clone());
+        if let Some(project) = current_project {
+            let alerts = read_config(cli_args)?.budget_alerts();
+            let projects = read_projects(cli_args)?;
+            for usage in budget_usage(&projects, &shifts, &project, status.current_time) {
+                let percent = usage.percent();
+                let summary = format!(
+                    "{} of {} {} ({percent}%)",
+                    BiDuration::new(usage.used).to_compact_string(),
+                    BiDuration::new(usage.limit).to_compact_string(),
+                    usage.kind,
+                );
+                let summary = if percent >= 100 {
+                    summary.red().bold().to_string()
+                } else if alerts.iter().any(|a| percent >= *a as i64) {
+                    summary.yellow().bold().to_string()
+                } else {
+                    summary.green().to_string()
+                };
+                println!("   {} {}", "Budget:".bold().bright_blue(), summary);
+            }
+        }
+
+        // an offset before the first entry also has no entries, but the
+        // data file isn't empty in that case
+        if status.since.is_none() && status.until.is_none() {
+            println!("{}", HINT_NO_ENTRIES.yellow());
+        }
+
         // match status.status_type {
         //     ClockStatusType::NoDataFile => {
         //         println!(
@@ -139,6 +271,171 @@ pub fn get_clock_status(
         // }
     }
 
+    if let Some(count) = recent {
+        print_recent_shifts(cli_args, status.current_time, *count)?;
+    }
+
+    if *calendar {
+        print_calendar(cli_args, status.current_time)?;
+    }
+
+    Ok(())
+}
+
+/// Prints the status as a single line for a status bar, e.g. `in 1h05m acme`
+/// or `out 6h20m today`.
+fn print_bar_status(cli_args: &Cli, status: &ClockStatus, format: StatusFormat) -> Result<()> {
+    let now = status.current_time;
+    let shifts = read_shifts(cli_args)?;
+    let today = total_between(&shifts, start_of_day(now), now, now);
+    let week = total_between(&shifts, start_of_week(now), now, now);
+    let compact = |d: Duration| BiDuration::new(d).to_compact_string();
+
+    let clocked_in = matches!(
+        status.status_type,
+        ClockStatusType::Entry(EntryType::ClockIn)
+    );
+    let class = if clocked_in { "in" } else { "out" };
+    let text = match (clocked_in, status.since) {
+        (true, Some(since)) => {
+            let project = shifts
+                .last()
+                .and_then(|s| s.start.project.as_ref())
+                .map(|p| format!(" {p}"))
+                .unwrap_or_default();
+            format!("in {}{project}", compact(now - since))
+        }
+        _ => format!("out {} today", compact(today)),
+    };
+
+    match format {
+        StatusFormat::Plain => println!("{text}"),
+        StatusFormat::Waybar => {
+            let since = status
+                .since
+                .map(|since| format!("Clocked {class} since {}\n", since.format(SLIM_DATETIME)))
+                .unwrap_or_default();
+            let tooltip = format!(
+                "{since}Today: {}\nThis week: {}",
+                compact(today),
+                compact(week)
+            );
+            let json = serde_json::json!({
+                "text": text,
+                "alt": class,
+                "tooltip": tooltip,
+                "class": class,
+            });
+            println!("{json}");
+        }
+        StatusFormat::Text => unreachable!("the text format is the full status report"),
+    }
+
+    Ok(())
+}
+
+/// Prints the last `count` shifts which ended before `now`.
+fn print_recent_shifts(cli_args: &Cli, now: DateTime<Local>, count: usize) -> Result<()> {
+    use owo_colors::{DynColors, OwoColorize};
+    let gray = DynColors::Rgb(128, 128, 128);
+
+    let shifts = read_shifts(cli_args)?;
+    let completed = shifts
+        .iter()
+        .filter(|s| s.end.as_ref().map(|e| e.timestamp <= now).unwrap_or(false))
+        .collect::<Vec<_>>();
+
+    println!("\n{}", "Recent Shifts".bold().bright_magenta());
+    if completed.is_empty() {
+        println!("    {}", "No completed shifts yet".color(gray));
+    }
+    for shift in &completed[completed.len().saturating_sub(count)..] {
+        let end = shift.end_or(now);
+        let end_format = if end.date_naive() == shift.start.timestamp.date_naive() {
+            "%H:%M"
+        } else {
+            "%a %d %b %H:%M"
+        };
+        println!(
+            "    {} {} {} {}{}",
+            shift.start.timestamp.format("%a %d %b %H:%M").blue(),
+            "->".bold().color(gray),
+            end.format(end_format).blue(),
+            BiDuration::new(shift.duration(now))
+                .to_compact_string()
+                .green()
+                .bold(),
+            shift
+                .start
+                .project
+                .as_ref()
+                .map(|p| format!(" {}", p.bright_blue()))
+                .unwrap_or_default(),
+        );
+    }
+
+    Ok(())
+}
+
+/// Prints the week containing `now` with the hours tracked on each day.
+fn print_calendar(cli_args: &Cli, now: DateTime<Local>) -> Result<()> {
+    use owo_colors::{DynColors, OwoColorize};
+    let gray = DynColors::Rgb(128, 128, 128);
+
+    let shifts = read_shifts(cli_args)?;
+    let monday = start_of_week(now).date_naive();
+
+    let mut names = String::new();
+    let mut dates = String::new();
+    let mut hours = String::new();
+    let mut marker = String::new();
+    let mut week_total = Duration::zero();
+
+    for date in monday.iter_days().take(7) {
+        let cell = |s: &str| format!("{s:^CALENDAR_COLUMN_WIDTH$}");
+        let is_today = date == now.date_naive();
+
+        let (Some(from), Some(to)) = (start_of_date(date), date.succ_opt().and_then(start_of_date))
+        else {
+            return Err(eyre!("Failed to determine the start of {date}"));
+        };
+        let total = total_between(&shifts, from, to, now);
+        week_total = week_total + total;
+
+        let name = cell(&date.weekday().to_string());
+        let day = cell(&date.day().to_string());
+        let tracked = cell(&if from > now {
+            "·".to_string()
+        } else if total.is_zero() {
+            "-".to_string()
+        } else {
+            BiDuration::new(total).to_compact_string()
+        });
+
+        if is_today {
+            names.push_str(&name.bold().yellow().to_string());
+            dates.push_str(&day.bold().yellow().to_string());
+            hours.push_str(&tracked.bold().green().to_string());
+            marker.push_str(&cell("▲ now").yellow().to_string());
+        } else {
+            names.push_str(&name.bright_blue().to_string());
+            dates.push_str(&day.color(gray).to_string());
+            hours.push_str(&tracked.green().to_string());
+            marker.push_str(&cell(""));
+        }
+    }
+
+    println!(
+        "\n{}\n{names}\n{dates}\n{hours}\n{}",
+        "This Week".bold().bright_magenta(),
+        marker.trim_end()
+    );
+    println!(
+        "{} {}",
+        "Total:".bold().bright_blue(),
+        BiDuration::new(week_total).to_compact_string().green()
+    );
+
     Ok(())
 }
 
@@ -165,6 +462,8 @@ pub struct ClockStatus {
     pub current_time: DateTime<Local>,
     pub since: Option<DateTime<Local>>,
     pub until: Option<DateTime<Local>>,
+    /// The revision of the data file this status was read from
+    pub revision: Option<Revision>,
 }
 
 #[instrument]
@@ -174,12 +473,15 @@ pub fn get_clock_status_inner(
 ) -> Result<ClockStatus> {
     let output_file = cli_args.get_output_file();
 
+    let revision = Revision::current(cli_args)?;
+
     if !output_file.exists() {
         return Ok(ClockStatus {
             status_type: ClockStatusType::NoDataFile,
             current_time,
             since: None,
             until: None,
+            revision,
         });
     }
 
@@ -206,6 +508,7 @@ pub fn get_clock_status_inner(
             current_time,
             since: None,
             until: None,
+            revision,
         });
     };
 
@@ -220,5 +523,6 @@ pub fn get_clock_status_inner(
         current_time,
         since,
         until,
+        revision,
     })
 }
@@ -0,0 +1,184 @@
+// Copyright (C) 2023 Campbell M. Cole
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::BTreeMap;
+
+use crate::prelude::*;
+
+use super::{
+    clock::{add_entry_inner, EntryMetadataArgs},
+    status::get_clock_status_inner,
+    timer::schedule_clock_out,
+};
+
+#[derive(Debug, Args)]
+pub struct TemplateArgs {
+    #[clap(subcommand)]
+    pub operation: TemplateOperation,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum TemplateOperation {
+    /// Add or replace a shift template
+    Add {
+        /// The name used to start this template
+        name: String,
+        #[clap(flatten)]
+        metadata: EntryMetadataArgs,
+        /// How long the shift lasts. If given, a clock-out entry is scheduled
+        /// when the template is started.
+        #[clap(long)]
+        duration: Option<BiDuration>,
+    },
+    /// List all shift templates
+    List,
+    /// Remove a shift template
+    Remove {
+        /// The name of the template to remove
+        name: String,
+    },
+}
+
+#[derive(Debug, Args)]
+pub struct StartArgs {
+    /// The name of the template to start
+    pub name: String,
+    /// The offset from the current time to use as the clock in time
+    #[clap(short, long)]
+    pub offset_from_now: Option<BiDuration>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Template {
+    #[serde(default)]
+    pub project: Option<String>,
+    #[serde(default)]
+    pub tags: Tags,
+    #[serde(default)]
+    pub duration: Option<BiDuration>,
+}
+
+pub type Templates = BTreeMap<String, Template>;
+
+pub fn read_templates(cli_args: &Cli) -> Result<Templates> {
+    read_json_file(&cli_args.get_templates_file())
+}
+
+#[instrument]
+pub fn manage_templates(cli_args: &Cli, TemplateArgs { operation }: &TemplateArgs) -> Result<()> {
+    use owo_colors::OwoColorize;
+
+    let mut templates = read_templates(cli_args)?;
+
+    match operation {
+        TemplateOperation::Add {
+            name,
+            metadata,
+            duration,
+        } => {
+            if matches!(duration, Some(d) if **d <= chrono::Duration::zero()) {
+                return Err(eyre!("The duration of a template must be positive"));
+            }
+            let template = Template {
+                project: metadata.project.clone(),
+                tags: metadata.tags(),
+                duration: duration.clone(),
+            };
+            templates.insert(name.clone(), template);
+            println!("Saved template {}", name.bold().cyan());
+        }
+        TemplateOperation::List => {
+            if templates.is_empty() {
+                println!("{}", "No templates have been added yet.".red());
+            }
+            for (name, template) in &templates {
+                println!(
+                    "{} {} {} {}",
+                    name.bold().cyan(),
+                    template
+                        .project
+                        .as_deref()
+                        .unwrap_or("(no project)")
+                        .bright_blue(),
+                    template
+                        .tags
+                        .iter()
+                        .map(|t| format!("#{t}"))
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                        .bright_black(),
+                    template
+                        .duration
+                        .as_ref()
+                        .map(|d| d.to_friendly_absolute_string())
+                        .unwrap_or_default()
+                        .yellow(),
+                );
+            }
+            return Ok(());
+        }
+        TemplateOperation::Remove { name } => {
+            if templates.remove(name).is_none() {
+                return Err(
+                    ErrorCode::NotFound.report(format!("There is no template named '{name}'"))
+                );
+            }
+            println!("Removed template {}", name.bold().cyan());
+        }
+    }
+
+    write_json_file(&cli_args.get_templates_file(), &templates)
+}
+
+#[instrument]
+pub fn start_template(
+    cli_args: &Cli,
+    StartArgs {
+        name,
+        offset_from_now,
+    }: &StartArgs,
+) -> Result<()> {
+    let templates = read_templates(cli_args)?;
+    let template = templates
+        .get(name)
+        .ok_or_else(|| ErrorCode::NotFound.report(format!("There is no template named '{name}'")))
+        .suggestion("Use 'punchcard template list' to see all templates")?;
+
+    let timestamp = offset_from_now.relative_to_now();
+    let status = get_clock_status_inner(cli_args, timestamp)?;
+
+    let mut entry = Entry::new(EntryType::ClockIn, timestamp, EntrySource::Template);
+    entry.project = template.project.clone();
+    entry.tags = template.tags.clone();
+
+    let since = add_entry_inner(cli_args, entry, offset_from_now, status)?;
+
+    if let Some(duration) = &template.duration {
+        let end = timestamp + **duration;
+
+        let mut entry = Entry::new(EntryType::ClockOut, end, EntrySource::AutoClockOut);
+        entry.project = template.project.clone();
+        entry.tags = template.tags.clone();
+
+        let offset = offset_from_now
+            .as_ref()
+            .map(|offset| BiDuration::new(**offset + **duration))
+            .unwrap_or_else(|| duration.clone());
+        let message = format!("Your {name} shift is over. You have been clocked out.");
+        schedule_clock_out(cli_args, entry, since, &Some(offset), &message)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,259 @@
+// Copyright (C) 2023 Campbell M. Cole
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::process::{Command, Stdio};
+
+use chrono::SecondsFormat;
+
+use crate::{notify::send_notification, prelude::*};
+
+use super::{
+    clock::add_entry_inner,
+    status::{get_clock_status_inner, ClockStatusType},
+};
+
+#[instrument]
+pub fn schedule_reminder(
+    cli_args: &Cli,
+    RemindArgs {
+        message,
+        after,
+        always,
+    }: &RemindArgs,
+) -> Result<()> {
+    use owo_colors::OwoColorize;
+
+    if **after <= chrono::Duration::zero() {
+        return Err(eyre!("Reminders can only be scheduled in the future"));
+    }
+
+    let now = Local::now();
+    let status = get_clock_status_inner(cli_args, now)?;
+
+    // a reminder is tied to the clock status when it was scheduled, so
+    // e.g. a "clock out" reminder is canceled by clocking out
+    let unchanged_since = if *always { None } else { status.since };
+    // without any entries there is no time to compare against, but the
+    // first clock-in should still cancel it
+    let while_no_entries = !*always && status.since.is_none();
+
+    let at = now + **after;
+    let mut command = timer_command(cli_args, at, message, unchanged_since)?;
+    if while_no_entries {
+        command.arg("--while-no-entries");
+    }
+    spawn(command)?;
+
+    println!(
+        "{} {} {}{}",
+        "Reminder scheduled for".bright_black(),
+        at.format(SLIM_DATETIME).magenta().bold(),
+        after.to_friendly_relative_string().yellow(),
+        match (unchanged_since, status.status_type) {
+            (Some(_), ClockStatusType::Entry(entry_type)) => format!(
+                " {} {}{}",
+                "(canceled if you are no longer clocked".bright_black(),
+                entry_type.colored(cli_args.accessible),
+                ")".bright_black()
+            ),
+            _ if while_no_entries => format!(" {}", "(canceled if you clock in)".bright_black()),
+            _ => String::new(),
+        }
+    );
+
+    Ok(())
+}
+
+#[derive(Debug, Args)]
+pub struct TimerArgs {
+    /// When to show the notification
+    #[clap(long)]
+    pub at: DateTime<Local>,
+    /// The body of the notification
+    #[clap(long)]
+    pub message: String,
+    /// Only show the notification if the latest entry is still the one at this time
+    #[clap(long)]
+    pub unchanged_since: Option<DateTime<Local>>,
+    /// Only show the notification if there are still no entries
+    #[clap(long, conflicts_with = "unchanged_since", default_value_t = false)]
+    pub while_no_entries: bool,
+    /// Also clock out at the given time
+    #[clap(long, requires = "unchanged_since", default_value_t = false)]
+    pub clock_out: bool,
+    /// The project of the clock-out
+    #[clap(long, requires = "clock_out")]
+    pub project: Option<String>,
+    /// A tag of the clock-out. Can be applied multiple times.
+    #[clap(long = "tag", requires = "clock_out", action = clap::ArgAction::Append)]
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct RemindArgs {
+    /// The message to show
+    pub message: String,
+    /// How long from now to show the reminder
+    #[clap(short, long = "in")]
+    pub after: BiDuration,
+    /// Show the reminder even if you clock in or out before it fires
+    #[clap(long, default_value_t = false)]
+    pub always: bool,
+}
+
+/// Spawns a detached punchcard process which shows a notification at the given time.
+///
+/// The process re-executes the current binary with the hidden `timer` subcommand
+/// so no daemon is required. It is placed in its own process group so that it
+/// survives the terminal it was started from being closed.
+pub fn spawn_timer(
+    cli_args: &Cli,
+    at: DateTime<Local>,
+    message: &str,
+    unchanged_since: Option<DateTime<Local>>,
+) -> Result<()> {
+    spawn(timer_command(cli_args, at, message, unchanged_since)?)
+}
+
+/// Adds `entry`, a clock-out, once its time has come, if the latest entry is
+/// still the clock-in at `since`. `message` is shown after clocking out.
+///
+/// A clock-out in the future would keep the user from clocking out early, so
+/// it is left to a timer, unless its time has passed already.
+pub fn schedule_clock_out(
+    cli_args: &Cli,
+    entry: Entry,
+    since: DateTime<Local>,
+    offset_from_now: &Option<BiDuration>,
+    message: &str,
+) -> Result<()> {
+    use owo_colors::OwoColorize;
+
+    if entry.timestamp <= Local::now() {
+        let status = get_clock_status_inner(cli_args, entry.timestamp)?;
+        add_entry_inner(cli_args, entry, offset_from_now, status)?;
+        return Ok(());
+    }
+
+    let mut command = timer_command(cli_args, entry.timestamp, message, Some(since))?;
+    command.arg("--clock-out");
+    if let Some(project) = &entry.project {
+        command.arg("--project").arg(project);
+    }
+    for tag in entry.tags.iter() {
+        command.arg("--tag").arg(tag);
+    }
+    spawn(command)?;
+
+    println!(
+        "{} {}",
+        "Clocking out automatically at".bright_black(),
+        entry.timestamp.format(SLIM_DATETIME).magenta().bold()
+    );
+    Ok(())
+}
+
+/// The command which runs the hidden `timer` subcommand.
+fn timer_command(
+    cli_args: &Cli,
+    at: DateTime<Local>,
+    message: &str,
+    unchanged_since: Option<DateTime<Local>>,
+) -> Result<Command> {
+    let exe = std::env::current_exe().wrap_err("Failed to locate the punchcard executable")?;
+
+    let mut command = Command::new(exe);
+    command
+        .arg("--data-folder")
+        .arg(&cli_args.data_folder)
+        .arg("--timezone")
+        .arg(cli_args.timezone.to_string())
+        .arg("timer")
+        .arg("--at")
+        .arg(at.to_rfc3339_opts(SecondsFormat::Nanos, false))
+        .arg("--message")
+        .arg(message);
+
+    if let Some(since) = unchanged_since {
+        command
+            .arg("--unchanged-since")
+            .arg(since.to_rfc3339_opts(SecondsFormat::Nanos, false));
+    }
+
+    Ok(command)
+}
+
+/// Starts a timer in the background.
+fn spawn(mut command: Command) -> Result<()> {
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        command.process_group(0);
+    }
+
+    command.spawn().wrap_err("Failed to start timer process")?;
+
+    Ok(())
+}
+
+#[instrument]
+pub fn run_timer(
+    cli_args: &Cli,
+    TimerArgs {
+        at,
+        message,
+        unchanged_since,
+        while_no_entries,
+        clock_out,
+        project,
+        tags,
+    }: &TimerArgs,
+) -> Result<()> {
+    if let Ok(wait) = (*at - Local::now()).to_std() {
+        std::thread::sleep(wait);
+    }
+
+    if let Some(since) = unchanged_since {
+        let status = get_clock_status_inner(cli_args, Local::now())?;
+        if status.since.as_ref() != Some(since) {
+            debug!("clock status changed since the timer was started, not notifying");
+            return Ok(());
+        }
+    }
+
+    if *while_no_entries
+        && get_clock_status_inner(cli_args, Local::now())?
+            .since
+            .is_some()
+    {
+        debug!("an entry was added since the timer was started, not notifying");
+        return Ok(());
+    }
+
+    if *clock_out {
+        let mut entry = Entry::new(EntryType::ClockOut, *at, EntrySource::AutoClockOut);
+        entry.project = project.clone();
+        entry.tags = Tags(tags.clone());
+        let status = get_clock_status_inner(cli_args, *at)?;
+        add_entry_inner(cli_args, entry, &None, status)?;
+    }
+
+    send_notification("punchcard", message)
+}
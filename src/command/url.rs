@@ -0,0 +1,118 @@
+// Copyright (C) 2023 Campbell M. Cole
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use crate::prelude::*;
+
+use super::{
+    clock::{
+        add_entry, clock_in, toggle_clock, ClockEntryArgs, ClockInArgs, ClockToggleArgs,
+        EntryMetadataArgs,
+    },
+    status::{get_clock_status, StatusArgs, StatusFormat},
+    template::{start_template, StartArgs},
+};
+
+#[derive(Debug, Args)]
+pub struct HandleUrlArgs {
+    /// The URL to handle (e.g. `punchcard://in?project=acme&tag=meeting`)
+    pub url: PunchcardUrl,
+}
+
+#[instrument]
+pub fn handle_url(cli_args: &Cli, HandleUrlArgs { url }: &HandleUrlArgs) -> Result<()> {
+    let offset_from_now = url
+        .get("offset")
+        .map(str::parse::<BiDuration>)
+        .transpose()
+        .wrap_err("Invalid value for 'offset'")?;
+
+    let entry = ClockEntryArgs {
+        offset_from_now: offset_from_now.clone(),
+        metadata: EntryMetadataArgs {
+            project: url.get("project").map(str::to_string),
+            tags: url.get_all("tag"),
+            location: url.get("location").map(str::to_string),
+            travel: is_flag_set(url, "travel"),
+        },
+        note: url.get("note").map(str::to_string),
+    };
+
+    match url.action.as_str() {
+        "in" => {
+            let timebox = url
+                .get("for")
+                .map(str::parse::<BiDuration>)
+                .transpose()
+                .wrap_err("Invalid value for 'for'")?;
+            let auto_out = is_flag_set(url, "auto-out");
+            if auto_out && timebox.is_none() {
+                return Err(eyre!("'auto-out' requires 'for' to be given"));
+            }
+            clock_in(
+                cli_args,
+                &ClockInArgs {
+                    entry,
+                    timebox,
+                    auto_out,
+                },
+            )
+        }
+        "out" => add_entry(cli_args, EntryType::ClockOut, &entry),
+        "toggle" => toggle_clock(
+            cli_args,
+            &ClockToggleArgs {
+                entry,
+                notify: is_flag_set(url, "notify"),
+            },
+        ),
+        "status" => {
+            let recent = url
+                .get("recent")
+                .map(str::parse::<usize>)
+                .transpose()
+                .wrap_err("Invalid value for 'recent'")?;
+            get_clock_status(
+                cli_args,
+                &StatusArgs {
+                    offset_from_now,
+                    calendar: is_flag_set(url, "calendar"),
+                    recent,
+                    format: StatusFormat::Text,
+                },
+            )
+        }
+        "start" => {
+            let Some(name) = url.get("name") else {
+                return Err(eyre!("The 'start' action requires a 'name'"));
+            };
+            start_template(
+                cli_args,
+                &StartArgs {
+                    name: name.to_string(),
+                    offset_from_now,
+                },
+            )
+        }
+        action => Err(eyre!("Unknown action '{action}'"))
+            .suggestion("Supported actions are: in, out, toggle, status, start"),
+    }
+}
+
+/// A flag is set if it is present without a value or with a truthy value
+fn is_flag_set(url: &PunchcardUrl, key: &str) -> bool {
+    url.get(key)
+        .map(|v| matches!(v, "" | "1" | "true" | "yes"))
+        .unwrap_or(false)
+}
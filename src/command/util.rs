@@ -0,0 +1,170 @@
+// Copyright (C) 2023 Campbell M. Cole
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::{collections::BTreeSet, ffi::OsStr};
+
+use clap::ValueEnum;
+
+use crate::{csv::read_entries, prelude::*};
+
+use super::{project::read_projects, recur::RecurRules, template::read_templates};
+
+#[derive(Debug, Args)]
+pub struct UtilArgs {
+    #[clap(subcommand)]
+    pub operation: UtilOperation,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum UtilOperation {
+    /// Print how punchcard was built and where it reads its files from
+    ///
+    /// Please include this when reporting a bug.
+    About,
+    /// Print the known values of something, one per line
+    ///
+    /// Meant to be used by shell completion scripts, e.g. to complete
+    /// the value of '--project'.
+    Complete {
+        /// What to print
+        #[clap(value_enum)]
+        source: CompletionSource,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum CompletionSource {
+    /// Every project used in the data file, templates, recurring shifts, or project settings
+    Projects,
+    /// Every tag used in the data file, templates, or recurring shifts
+    Tags,
+    /// The names of the shift templates
+    Templates,
+}
+
+#[instrument]
+pub fn run_util(cli_args: &Cli, UtilArgs { operation }: &UtilArgs) -> Result<()> {
+    match operation {
+        UtilOperation::About => print_about(cli_args),
+        UtilOperation::Complete { source } => {
+            for value in completion_values(cli_args, *source)? {
+                println!("{value}");
+            }
+        }
+    }
+    Ok(())
+}
+
+fn print_about(cli_args: &Cli) {
+    use owo_colors::OwoColorize;
+
+    let label = |label: &str| {
+        format!("{:<10}", format!("{label}:"))
+            .bright_black()
+            .to_string()
+    };
+    let origin = |origin: &str| format!("({origin})").bright_black().to_string();
+
+    println!(
+        "{} {} {}",
+        "punchcard".bold(),
+        env!("CARGO_PKG_VERSION").bold(),
+        origin(env!("PUNCHCARD_GIT_HASH"))
+    );
+    println!("{} {}", label("rustc"), env!("PUNCHCARD_RUSTC_VERSION"));
+    println!("{} {}", label("target"), env!("PUNCHCARD_TARGET"));
+    println!("{} {}", label("features"), env!("PUNCHCARD_FEATURES"));
+    #[cfg(feature = "reports")]
+    println!("{} {}", label("polars"), polars::VERSION);
+
+    let data_folder = &cli_args.data_folder;
+    println!(
+        "{} {} {}",
+        label("data"),
+        data_folder.display().cyan(),
+        origin(&value_origin(
+            data_folder.as_os_str(),
+            "PUNCHCARD_DATA_FOLDER",
+            crate::default_data_folder().as_os_str()
+        ))
+    );
+
+    let timezone = cli_args.timezone.to_string();
+    println!(
+        "{} {} {}",
+        label("timezone"),
+        timezone.cyan(),
+        origin(&value_origin(
+            timezone.as_ref(),
+            "PUNCHCARD_TIMEZONE",
+            crate::default_timezone().to_string().as_ref()
+        ))
+    );
+
+    let config_file = cli_args.get_config_file();
+    println!(
+        "{} {} {}",
+        label("config"),
+        config_file.display().cyan(),
+        origin(if config_file.exists() {
+            "found"
+        } else {
+            "not found, using the defaults"
+        })
+    );
+}
+
+/// Guesses where a global option came from, since clap doesn't keep track
+/// of it once the arguments are parsed.
+fn value_origin(value: &OsStr, env: &str, default: &OsStr) -> String {
+    if value == default {
+        "default".into()
+    } else if std::env::var_os(env).as_deref() == Some(value) {
+        format!("from {env}")
+    } else {
+        "from the command line".into()
+    }
+}
+
+fn completion_values(cli_args: &Cli, source: CompletionSource) -> Result<BTreeSet<String>> {
+    let templates = read_templates(cli_args)?;
+    if source == CompletionSource::Templates {
+        return Ok(templates.into_keys().collect());
+    }
+
+    let entries = if cli_args.get_output_file().exists() {
+        read_entries(cli_args)?
+    } else {
+        Vec::new()
+    };
+    let rules: RecurRules = read_json_file(&cli_args.get_recurring_file())?;
+
+    let mut values = BTreeSet::new();
+    match source {
+        CompletionSource::Projects => {
+            values.extend(entries.into_iter().filter_map(|e| e.project));
+            values.extend(templates.into_values().filter_map(|t| t.project));
+            values.extend(rules.into_values().filter_map(|r| r.project));
+            values.extend(read_projects(cli_args)?.into_keys());
+        }
+        CompletionSource::Tags => {
+            values.extend(entries.into_iter().flat_map(|e| e.tags.0));
+            values.extend(templates.into_values().flat_map(|t| t.tags.0));
+            values.extend(rules.into_values().flat_map(|r| r.tags.0));
+        }
+        CompletionSource::Templates => unreachable!("templates are handled above"),
+    }
+    Ok(values)
+}
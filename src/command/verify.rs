@@ -0,0 +1,64 @@
+// Copyright (C) 2023 Campbell M. Cole
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::path::PathBuf;
+
+use crate::{
+    prelude::*,
+    receipt::{hash_file, read_receipts},
+};
+
+#[derive(Debug, Args)]
+pub struct VerifyArgs {
+    /// The exported file to check
+    pub file: PathBuf,
+}
+
+#[instrument]
+pub fn verify(cli_args: &Cli, args: &VerifyArgs) -> Result<()> {
+    use owo_colors::{DynColors, OwoColorize};
+    let gray = DynColors::Rgb(128, 128, 128);
+
+    let sha256 = hash_file(&args.file)?;
+    let receipts = read_receipts(cli_args)?;
+    let matching = receipts
+        .iter()
+        .filter(|r| r.sha256 == sha256)
+        .collect::<Vec<_>>();
+
+    if matching.is_empty() {
+        return Err(ErrorCode::Unverified
+            .report(format!("{} doesn't match any receipt", args.file.display())))
+        .note(format!("SHA-256: {sha256}"))
+        .suggestion("It was changed after it was exported, or exported without '--receipt'");
+    }
+
+    println!(
+        "{} {} {}",
+        "Verified".green().bold(),
+        args.file.display().bold(),
+        format!("(SHA-256: {sha256})").color(gray)
+    );
+    for receipt in matching {
+        println!(
+            "  {} {} {}",
+            "exported".color(gray),
+            receipt.created_at.format(PRETTY_DATETIME).cyan(),
+            format!("by '{}'", receipt.command).color(gray)
+        );
+    }
+
+    Ok(())
+}
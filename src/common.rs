@@ -15,16 +15,23 @@
 
 #![allow(non_snake_case)]
 
-use std::path::Path;
+use std::{fs, path::Path};
 
-use color_eyre::{eyre::Context, Result};
-use polars::prelude::{LazyCsvReader, LazyFileListReader, LazyFrame};
+use color_eyre::{eyre::Context, Help, Result};
+#[cfg(feature = "reports")]
+use polars::prelude::{
+    DataFrame, IntoLazy, LazyCsvReader, LazyFileListReader, LazyFrame, NamedFrom, Series,
+};
+use serde::{de::DeserializeOwned, Serialize};
 
+#[cfg(feature = "reports")]
 use crate::Cli;
 
 pub const ERR_LATEST_ENTRY: &str = "Failed to get latest entry";
 pub const SUGG_REPORT_ISSUE: &str =
     "If you have not manually modified this file, please report this issue";
+pub const HINT_NO_ENTRIES: &str =
+    "There are no entries yet. Use 'punchcard in' to start tracking your time.";
 
 #[inline(always)]
 pub fn ERR_OPEN_CSV(p: &Path) -> String {
@@ -41,6 +48,26 @@ pub fn ERR_READ_CSV(p: &Path) -> String {
     format!("Failed to read CSV file {}", p.display())
 }
 
+#[inline(always)]
+pub fn ERR_READ_JSON(p: &Path) -> String {
+    format!("Failed to read JSON file {}", p.display())
+}
+
+#[inline(always)]
+pub fn ERR_READ_TOML(p: &Path) -> String {
+    format!("Failed to read TOML file {}", p.display())
+}
+
+#[inline(always)]
+pub fn ERR_WRITE_TOML(p: &Path) -> String {
+    format!("Failed to write TOML file {}", p.display())
+}
+
+#[inline(always)]
+pub fn ERR_WRITE_JSON(p: &Path) -> String {
+    format!("Failed to write JSON file {}", p.display())
+}
+
 #[inline(always)]
 pub fn SUGG_PROPER_PERMS(p: &Path) -> String {
     format!("Ensure you have proper permissions for {}", p.display())
@@ -54,9 +81,72 @@ pub const SLIM_DATETIME: &str = "%r %d %B %Y";
 // RFC3339 with nanoseconds, no space between ns and tz
 pub const CSV_DATETIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S.%f%z";
 
+/// Parses a time of day such as `18:00` or `18:00:30`.
+pub fn parse_time_of_day(s: &str) -> std::result::Result<chrono::NaiveTime, chrono::ParseError> {
+    chrono::NaiveTime::parse_from_str(s, "%H:%M")
+        .or_else(|_| chrono::NaiveTime::parse_from_str(s, "%H:%M:%S"))
+}
+
+/// Parses a timestamp such as `2024-03-01T09:00`, `2024-03-01T09:00:30`,
+/// or an RFC 3339 timestamp with an explicit offset.
+///
+/// Timestamps without an offset are interpreted in the local timezone.
+pub fn parse_local_datetime(s: &str) -> Option<chrono::DateTime<chrono::Local>> {
+    use chrono::TimeZone;
+    if let Ok(timestamp) = chrono::DateTime::parse_from_rfc3339(s) {
+        return Some(timestamp.with_timezone(&chrono::Local));
+    }
+    ["%Y-%m-%dT%H:%M", "%Y-%m-%dT%H:%M:%S"]
+        .iter()
+        .find_map(|format| chrono::NaiveDateTime::parse_from_str(s, format).ok())
+        .and_then(|naive| chrono::Local.from_local_datetime(&naive).single())
+}
+
+#[cfg(feature = "reports")]
 #[inline(always)]
 pub fn new_reader(cli_args: &Cli) -> Result<LazyFrame> {
+    // polars can't infer a schema from an empty file, so give the
+    // reports the columns they expect without any rows
+    if !crate::csv::has_entries(cli_args)? {
+        let columns = crate::csv::CSV_HEADER
+            .iter()
+            .map(|name| Series::new(name, Vec::<String>::new()))
+            .collect();
+        return DataFrame::new(columns)
+            .map(IntoLazy::lazy)
+            .wrap_err("Failed to create empty table");
+    }
+    // make sure every column the reports expect is present
+    crate::csv::migrate_data_file(cli_args)?;
     LazyCsvReader::new(cli_args.get_output_file())
         .finish()
         .wrap_err("Failed to create lazy csv reader")
 }
+
+/// Reads a JSON file from the data folder, or returns the default value if it doesn't exist yet.
+pub fn read_json_file<T: DeserializeOwned + Default>(path: &Path) -> Result<T> {
+    if !path.exists() {
+        return Ok(T::default());
+    }
+    let contents = fs::read_to_string(path)
+        .wrap_err(ERR_READ_JSON(path))
+        .suggestion(SUGG_PROPER_PERMS(path))?;
+    serde_json::from_str(&contents)
+        .wrap_err(ERR_READ_JSON(path))
+        .suggestion(SUGG_REPORT_ISSUE)
+}
+
+pub fn write_json_file<T: Serialize>(path: &Path, value: &T) -> Result<()> {
+    let contents = serde_json::to_string_pretty(value).wrap_err(ERR_WRITE_JSON(path))?;
+    fs::write(path, contents)
+        .wrap_err(ERR_WRITE_JSON(path))
+        .suggestion(SUGG_PROPER_PERMS(path))
+}
+
+/// The command line punchcard was run with, for records of what made a change.
+pub fn command_line() -> String {
+    std::iter::once("punchcard".to_string())
+        .chain(std::env::args().skip(1))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
@@ -0,0 +1,399 @@
+// Copyright (C) 2023 Campbell M. Cole
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+// settings which change how commands behave, as opposed to the templates,
+// recurring shifts and projects which are data. the file is edited by hand
+
+use chrono::{Datelike, NaiveTime, Weekday};
+use serde::{Deserialize, Deserializer, Serializer};
+
+use std::{collections::BTreeMap, fs};
+
+use tracing_subscriber::EnvFilter;
+
+use crate::{command::report::parse_report_args, prelude::*};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Config {
+    /// The regular working hours
+    pub schedule: Option<Schedule>,
+    /// Print a report when clocking out on the last workday of the week
+    pub weekly_summary: Option<WeeklySummary>,
+    /// The percentages of a project budget at which to show a notification
+    pub budget_alerts: Option<Vec<u32>>,
+    /// Also write the logs to rotating files in the data folder
+    pub log_file: Option<LogFile>,
+    /// Sign exported files with this key (needs the `signing` feature)
+    pub signing: Option<Signing>,
+    /// What the shell hook does on the first prompt of the day
+    pub shell_hook: Option<ShellHook>,
+    /// Tag clock-ins with where they were made
+    pub location: Option<Location>,
+    /// Tag every entry with the hostname of the machine it was made on
+    pub record_device: bool,
+    /// Ask what was worked on when clocking out from a terminal
+    pub note_prompt: bool,
+    /// Ask an API to write 'report --narrative' (needs the `narrative` feature)
+    pub narrative: Option<Narrative>,
+    /// The command which opens copyable reports, e.g. `firefox`
+    pub browser: Option<String>,
+    /// Names to show instead of the report columns, e.g.
+    /// `"Total Hours" = "Gesamtstunden"`
+    pub labels: BTreeMap<String, String>,
+    /// How tables are drawn
+    pub table: TableConfig,
+    /// Short names for commands, e.g. `lunch = "out --tag lunch"`
+    pub aliases: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct TableConfig {
+    /// The characters of '--style custom', in the order of comfy-table's presets
+    pub border: Option<String>,
+    /// The width of tables when '--width' isn't set, ahead of $COLUMNS
+    pub width: Option<u16>,
+}
+
+/// The number of characters in a comfy-table preset, one for each part of the borders.
+pub const TABLE_BORDER_LEN: usize = 19;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Narrative {
+    /// An OpenAI-compatible chat completions endpoint
+    pub endpoint: String,
+    pub model: String,
+    /// The environment variable holding the API key
+    pub api_key_env: String,
+}
+
+impl Default for Narrative {
+    fn default() -> Self {
+        Self {
+            endpoint: "https://api.openai.com/v1/chat/completions".into(),
+            model: "gpt-4o-mini".into(),
+            api_key_env: "OPENAI_API_KEY".into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Location {
+    /// What to detect the location from
+    pub capture: LocationCapture,
+    /// The site to record for each detected hostname or network, e.g.
+    /// `{"Office-5G": "office"}`. Others are recorded as they are.
+    pub names: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LocationCapture {
+    /// The name of this machine
+    #[default]
+    Hostname,
+    /// The SSID of the connected wifi network
+    Wifi,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ShellHook {
+    /// Clock in without asking
+    pub auto_clock_in: bool,
+    /// The project of the clock-in
+    pub project: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Signing {
+    pub tool: SigningTool,
+    /// The secret key file for minisign, or the key ID for GPG
+    pub key: String,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SigningTool {
+    Minisign,
+    Gpg,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct LogFile {
+    /// The most detailed level to write, e.g. `info` or `debug`
+    pub level: String,
+    /// How often to start a new file
+    pub rotation: LogRotation,
+    /// How many files to keep before deleting the oldest
+    pub keep: usize,
+}
+
+impl Default for LogFile {
+    fn default() -> Self {
+        Self {
+            level: "info".into(),
+            rotation: LogRotation::Daily,
+            keep: 7,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LogRotation {
+    Hourly,
+    #[default]
+    Daily,
+    Never,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct WeeklySummary {
+    /// The arguments of the report, as they would be given to `punchcard report`
+    pub report_args: Vec<String>,
+}
+
+/// The regular working hours, e.g. Monday to Friday from 09:00 to 17:00.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Schedule {
+    pub days: Vec<Weekday>,
+    #[serde(with = "time_of_day")]
+    pub start: NaiveTime,
+    #[serde(with = "time_of_day")]
+    pub end: NaiveTime,
+}
+
+impl Schedule {
+    /// The scheduled start and end of the workday containing `time`,
+    /// or `None` if it isn't a scheduled day.
+    pub fn workday(&self, time: DateTime<Local>) -> Option<(DateTime<Local>, DateTime<Local>)> {
+        if !self.days.contains(&time.weekday()) {
+            return None;
+        }
+        let at = |t: NaiveTime| {
+            Local
+                .from_local_datetime(&time.date_naive().and_time(t))
+                .earliest()
+        };
+        Some((at(self.start)?, at(self.end)?))
+    }
+}
+
+impl Config {
+    pub fn budget_alerts(&self) -> Vec<u32> {
+        self.budget_alerts.clone().unwrap_or_else(|| vec![80, 100])
+    }
+
+    /// The last workday of the week, Friday unless a schedule says otherwise.
+    pub fn last_workday(&self) -> Weekday {
+        self.schedule
+            .as_ref()
+            .and_then(|s| s.days.iter().max_by_key(|d| d.num_days_from_monday()))
+            .copied()
+            .unwrap_or(Weekday::Fri)
+    }
+
+    /// Describes every setting which can be read but doesn't make sense.
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        if let Some(schedule) = &self.schedule {
+            if schedule.days.is_empty() {
+                problems.push("schedule.days: there are no scheduled days".into());
+            }
+            if schedule.end <= schedule.start {
+                problems.push("schedule.end: the workday must end after it starts".into());
+            }
+        }
+
+        if let Some(summary) = &self.weekly_summary {
+            if let Err(err) = parse_report_args(&summary.report_args) {
+                let cause = err.root_cause().to_string();
+                let cause = cause.lines().next().unwrap_or_default();
+                let cause = cause.trim_start_matches("error: ");
+                problems.push(format!("weekly-summary.report-args: {cause}"));
+            }
+        }
+
+        if let Some(alerts) = &self.budget_alerts {
+            if alerts.contains(&0) {
+                problems.push("budget-alerts: an alert at 0% would fire immediately".into());
+            }
+        }
+
+        if let Some(signing) = &self.signing {
+            if !cfg!(feature = "signing") {
+                problems.push(
+                    "signing: this build of punchcard doesn't include the `signing` feature".into(),
+                );
+            }
+            if signing.key.trim().is_empty() {
+                problems.push("signing.key: the key must not be empty".into());
+            }
+        }
+
+        if let Some(narrative) = &self.narrative {
+            if !cfg!(feature = "narrative") {
+                problems.push(
+                    "narrative: this build of punchcard doesn't include the `narrative` feature"
+                        .into(),
+                );
+            }
+            if narrative.model.trim().is_empty() {
+                problems.push("narrative.model: the model must not be empty".into());
+            }
+        }
+
+        if self.browser.as_ref().is_some_and(|b| b.trim().is_empty()) {
+            problems.push("browser: the command must not be empty".into());
+        }
+
+        for (column, label) in &self.labels {
+            if label.trim().is_empty() {
+                problems.push(format!("labels.{column}: the label must not be empty"));
+            }
+        }
+
+        if let Some(border) = &self.table.border {
+            let len = border.chars().count();
+            if len != TABLE_BORDER_LEN {
+                problems.push(format!(
+                    "table.border: expected {TABLE_BORDER_LEN} characters, found {len}"
+                ));
+            }
+        }
+
+        for (name, expansion) in &self.aliases {
+            if crate::alias::is_command(name) {
+                problems.push(format!(
+                    "aliases.{name}: there is already a command called `{name}`"
+                ));
+            }
+            match shell_words::split(expansion) {
+                Ok(words) if words.is_empty() => {
+                    problems.push(format!("aliases.{name}: the alias must not be empty"));
+                }
+                Ok(_) => {}
+                Err(err) => problems.push(format!("aliases.{name}: {err}")),
+            }
+        }
+
+        if self.table.width == Some(0) {
+            problems.push("table.width: the width must be at least 1".into());
+        }
+
+        if let Some(log_file) = &self.log_file {
+            if let Err(err) = EnvFilter::try_new(&log_file.level) {
+                problems.push(format!("log-file.level: {err}"));
+            }
+            if log_file.keep == 0 {
+                problems.push("log-file.keep: at least one file must be kept".into());
+            }
+        }
+
+        problems
+    }
+}
+
+pub fn read_config(cli_args: &Cli) -> Result<Config> {
+    let path = cli_args.get_config_file();
+    if !path.exists() {
+        return Ok(Config::default());
+    }
+    let contents = fs::read_to_string(&path)
+        .wrap_err(ERR_READ_TOML(&path))
+        .suggestion(SUGG_PROPER_PERMS(&path))?;
+    parse_config(&contents)
+        .map_err(|err| eyre!(err))
+        .wrap_err(ERR_READ_TOML(&path))
+        .suggestion("Run 'punchcard config check' to check the rest of the config")
+}
+
+/// Parses the config, describing where the first problem is and what was
+/// probably meant if it looks like a typo.
+pub fn parse_config(contents: &str) -> std::result::Result<Config, String> {
+    let de = toml::Deserializer::new(contents);
+    serde_path_to_error::deserialize(de).map_err(|err| {
+        let path = err.path().to_string();
+        let inner = err.into_inner();
+        // syntax errors are on several lines, which don't fit in a list of problems
+        let message = inner.message().replace('\n', ", ");
+
+        let mut location = Vec::new();
+        if let Some(span) = inner.span() {
+            let before = &contents[..span.start];
+            let line = before.matches('\n').count() + 1;
+            let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+            let column = before[line_start..].chars().count() + 1;
+            location.push(format!("line {line}, column {column}"));
+        }
+        // the path is `?` when the TOML itself is invalid
+        if path != "." && path != "?" {
+            location.push(format!("at `{path}`"));
+        }
+        let mut description = match location.is_empty() {
+            true => message.to_string(),
+            false => format!("{}: {message}", location.join(", ")),
+        };
+        if let Some(suggestion) = suggest_name(&message) {
+            description.push_str(&format!(" (did you mean `{suggestion}`?)"));
+        }
+        description
+    })
+}
+
+/// Finds the expected name closest to the unknown one in a serde error like
+/// "unknown field `a`, expected one of `b`, `c`".
+fn suggest_name(message: &str) -> Option<&str> {
+    if !message.starts_with("unknown field") && !message.starts_with("unknown variant") {
+        return None;
+    }
+    let mut names = message.split('`').skip(1).step_by(2);
+    let unknown = names.next()?;
+    names
+        .map(|name| (strsim::levenshtein(unknown, name), name))
+        .filter(|(distance, _)| *distance <= 3)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, name)| name)
+}
+
+/// Times of day are written as `17:00` so the file is easy to edit by hand.
+mod time_of_day {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        time: &NaiveTime,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(&time.format("%H:%M"))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<NaiveTime, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        parse_time_of_day(&s).map_err(|_| {
+            serde::de::Error::custom(format!("invalid time of day `{s}`, expected e.g. `17:00`"))
+        })
+    }
+}
@@ -13,16 +13,74 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
-use std::{fmt::Display, fs::File};
+use std::{
+    convert::Infallible,
+    fmt::Display,
+    fs::{self, File},
+    io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write},
+    str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
-use csv::{Reader, ReaderBuilder};
+use csv::{Reader, ReaderBuilder, StringRecord, WriterBuilder};
+use indicatif::ProgressIterator;
+use rayon::prelude::*;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use sha2::{Digest, Sha256};
 
-use crate::prelude::*;
+use crate::{amend, prelude::*, progress, signal};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Data files are split into chunks of about this many bytes to be checked in parallel.
+const CHUNK_SIZE: usize = 1 << 20;
+
+/// The header of the data file. This must match the field order of [`Entry`].
+pub const CSV_HEADER: [&str; 6] = [
+    "entry_type",
+    "timestamp",
+    "project",
+    "tags",
+    "source",
+    "note",
+];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Entry {
     pub entry_type: EntryType,
+    #[serde(serialize_with = "serialize_timestamp")]
     pub timestamp: DateTime<Local>,
+    // columns added after the initial release must have a default so that
+    // older data files can still be read before they are migrated
+    #[serde(default)]
+    pub project: Option<String>,
+    #[serde(default)]
+    pub tags: Tags,
+    #[serde(default)]
+    pub source: EntrySource,
+    /// What was worked on, usually given when clocking out
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+// the default serializer omits the fractional seconds when they are zero
+// and uses `Z` for UTC, neither of which the report parser accepts
+fn serialize_timestamp<S: Serializer>(
+    timestamp: &DateTime<Local>,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    serializer.collect_str(&timestamp.format(CSV_DATETIME_FORMAT))
+}
+
+impl Entry {
+    pub fn new(entry_type: EntryType, timestamp: DateTime<Local>, source: EntrySource) -> Self {
+        Self {
+            entry_type,
+            timestamp,
+            project: None,
+            tags: Tags::default(),
+            source,
+            note: None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
@@ -34,12 +92,11 @@ pub enum EntryType {
 }
 
 impl EntryType {
-    pub fn colored(&self) -> String {
+    pub fn colored(&self, accessible: bool) -> String {
         use owo_colors::OwoColorize;
-        match self {
-            EntryType::ClockIn => "in".green().to_string(),
-            EntryType::ClockOut => "out".red().to_string(),
-        }
+        crate::palette::entry_label(*self, accessible)
+            .color(crate::palette::entry_color(*self, accessible))
+            .to_string()
     }
 }
 
@@ -52,6 +109,74 @@ impl Display for EntryType {
     }
 }
 
+/// How an entry was created.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub enum EntrySource {
+    /// Created by the `in`, `out`, or `toggle` commands
+    // entries created before this column existed could only have been created this way
+    #[default]
+    Cli,
+    /// Created by starting a template
+    Template,
+    /// Created by applying a recurring shift
+    Recurring,
+    /// A clock-out scheduled ahead of time by a timebox or template
+    AutoClockOut,
+    /// Read from stdin by the `batch` command
+    Batch,
+    /// Clocked at the shared terminal of the `kiosk` command
+    Kiosk,
+    /// Entered for a past week with the `backfill` command
+    Backfill,
+    /// Any source this version doesn't know about, kept as-is
+    Other(String),
+}
+
+impl Display for EntrySource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EntrySource::Cli => write!(f, "cli"),
+            EntrySource::Template => write!(f, "template"),
+            EntrySource::Recurring => write!(f, "recur"),
+            EntrySource::AutoClockOut => write!(f, "auto-clockout"),
+            EntrySource::Batch => write!(f, "batch"),
+            EntrySource::Kiosk => write!(f, "kiosk"),
+            EntrySource::Backfill => write!(f, "backfill"),
+            EntrySource::Other(other) => write!(f, "{other}"),
+        }
+    }
+}
+
+impl FromStr for EntrySource {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        Ok(match s {
+            "" | "cli" => EntrySource::Cli,
+            "template" => EntrySource::Template,
+            "recur" => EntrySource::Recurring,
+            "auto-clockout" => EntrySource::AutoClockOut,
+            "batch" => EntrySource::Batch,
+            "kiosk" => EntrySource::Kiosk,
+            "backfill" => EntrySource::Backfill,
+            other => EntrySource::Other(other.to_string()),
+        })
+    }
+}
+
+impl Serialize for EntrySource {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for EntrySource {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
 pub fn build_reader(cli_args: &Cli) -> Result<Reader<File>> {
     check_data_file(cli_args)?;
     build_reader_inner(cli_args)
@@ -59,29 +184,498 @@ pub fn build_reader(cli_args: &Cli) -> Result<Reader<File>> {
 
 fn build_reader_inner(cli_args: &Cli) -> Result<Reader<File>> {
     let data_file = cli_args.get_output_file();
+    // rows without a header are in the order of the current header because
+    // they can only have been appended by this version
+    let has_headers = header_state(cli_args)? != HeaderState::Missing;
     ReaderBuilder::new()
-        .has_headers(true)
+        .has_headers(has_headers)
         .from_path(&data_file)
         .wrap_err(ERR_READ_CSV(&data_file))
         .suggestion(SUGG_REPORT_ISSUE)
 }
 
-fn check_data_file(cli_args: &Cli) -> Result<()> {
+/// Returns an error for every row of the data file which can't be parsed.
+pub fn malformed_rows(cli_args: &Cli) -> Result<Vec<csv::Error>> {
+    // the errors are only collected if there are any, which needs the rows
+    // to be read in order so that the errors have the right positions
+    let data = read_data_file(cli_args)?;
+    if rows_valid(cli_args, &data[..], &data, 0)? {
+        return Ok(Vec::new());
+    }
+    let rows = count_rows(cli_args)?;
+    let mut reader = build_reader_inner(cli_args)?;
+
+    let de = reader.deserialize::<Entry>();
+
+    Ok(de
+        .progress_with(progress::rows(rows, "Checking entries"))
+        .filter_map(Result::err)
+        .collect())
+}
+
+fn read_data_file(cli_args: &Cli) -> Result<Vec<u8>> {
+    let data_file = cli_args.get_output_file();
+    fs::read(&data_file)
+        .wrap_err(ERR_READ_CSV(&data_file))
+        .suggestion(SUGG_PROPER_PERMS(&data_file))
+}
+
+fn open_data_file(cli_args: &Cli) -> Result<File> {
+    let data_file = cli_args.get_output_file();
+    File::open(&data_file)
+        .wrap_err(ERR_READ_CSV(&data_file))
+        .suggestion(SUGG_PROPER_PERMS(&data_file))
+}
+
+/// Checks if every row in `rows`, which is the data file after the first `from`
+/// bytes, can be parsed, stopping at the first one which can't. Unlike
+/// [`malformed_rows`], the rows are checked in parallel. `from` must be at the
+/// start of a row, and `data` reads the data file from the start.
+fn rows_valid(cli_args: &Cli, data: impl Read, rows: &[u8], from: usize) -> Result<bool> {
+    // every row must have as many fields as the first one, like in `build_reader_inner`
+    let mut reader = ReaderBuilder::new().has_headers(false).from_reader(data);
+    let mut first = StringRecord::new();
+    match reader.read_record(&mut first) {
+        Ok(true) => {}
+        Ok(false) => return Ok(true),
+        Err(_) => return Ok(false),
+    }
+    let (headers, start) = match header_state(cli_args)? {
+        HeaderState::Missing => (None, 0),
+        _ => (Some(&first), reader.position().byte() as usize),
+    };
+    let rows = &rows[start.saturating_sub(from)..];
+
+    let lines = rows.iter().filter(|&&b| b == b'\n').count();
+    let bar = progress::rows(lines as u64, "Checking entries");
+    Ok(split_rows(rows).into_par_iter().all(|chunk| {
+        let mut reader = ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .from_reader(chunk);
+        let mut record = StringRecord::new();
+        let mut checked = 0;
+        let valid = loop {
+            match reader.read_record(&mut record) {
+                Ok(true) => checked += 1,
+                Ok(false) => break true,
+                Err(_) => break false,
+            }
+            if record.len() != first.len() || record.deserialize::<Entry>(headers).is_err() {
+                break false;
+            }
+        };
+        bar.inc(checked);
+        valid
+    }))
+}
+
+/// Splits CSV rows into chunks of about [`CHUNK_SIZE`] bytes. They are only split
+/// at line breaks outside of quotes, since a quoted field can contain line breaks.
+fn split_rows(rows: &[u8]) -> Vec<&[u8]> {
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    let mut quoted = false;
+    for (i, &b) in rows.iter().enumerate() {
+        match b {
+            b'"' => quoted = !quoted,
+            b'\n' if !quoted && i + 1 - start >= CHUNK_SIZE => {
+                chunks.push(&rows[start..=i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    if start < rows.len() {
+        chunks.push(&rows[start..]);
+    }
+    chunks
+}
+
+/// Reads the entries which can be parsed, skipping the malformed rows.
+pub fn read_valid_entries(cli_args: &Cli) -> Result<Vec<Entry>> {
+    let rows = count_rows(cli_args)?;
     let mut reader = build_reader_inner(cli_args)?;
 
     let de = reader.deserialize::<Entry>();
 
-    let errs = de.filter_map(Result::err).collect::<Vec<_>>();
+    Ok(de
+        .progress_with(progress::rows(rows, "Reading entries"))
+        .filter_map(Result::ok)
+        .collect())
+}
+
+/// How much of the data file was valid when it was last checked, so that only the
+/// rows appended since then have to be checked. Saved in `validated.json`.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+struct Validated {
+    /// The length of the checked rows in bytes, up to the last line break
+    offset: usize,
+    /// The SHA-256 hash of the checked rows, to notice when they are changed
+    sha256: String,
+}
+
+impl Validated {
+    /// Hashes the checked rows if `data` still starts with them, leaving `data`
+    /// after them. Only the checked rows are read.
+    fn hash_prefix(&self, data: &mut impl Read) -> io::Result<Option<Sha256>> {
+        let mut hasher = Sha256::new();
+        let len = io::copy(&mut data.take(self.offset as u64), &mut hasher)?;
+        let unchanged =
+            len == self.offset as u64 && format!("{:x}", hasher.clone().finalize()) == self.sha256;
+        Ok(unchanged.then_some(hasher))
+    }
+}
+
+fn check_data_file(cli_args: &Cli) -> Result<()> {
+    let data_file = cli_args.get_output_file();
+    let mut file = open_data_file(cli_args)?;
+    let path = cli_args.get_validated_file();
+    // the marker only saves time, so a broken one is the same as none
+    let validated = read_json_file::<Validated>(&path).unwrap_or_default();
+    let (mut hasher, from) = match validated.hash_prefix(&mut file) {
+        Ok(Some(hasher)) => (hasher, validated.offset),
+        _ => {
+            file.seek(SeekFrom::Start(0))
+                .wrap_err(ERR_READ_CSV(&data_file))?;
+            (Sha256::new(), 0)
+        }
+    };
+    trace!(from, "checking data file");
+
+    let mut rows = Vec::new();
+    file.read_to_end(&mut rows)
+        .wrap_err(ERR_READ_CSV(&data_file))
+        .suggestion(SUGG_PROPER_PERMS(&data_file))?;
+    let valid = match from {
+        0 => rows_valid(cli_args, &rows[..], &rows, from)?,
+        _ => rows_valid(cli_args, open_data_file(cli_args)?, &rows, from)?,
+    };
+
+    if valid {
+        // only whole rows are marked as checked, up to the last line break
+        let len = rows.iter().rposition(|&b| b == b'\n').map_or(0, |i| i + 1);
+        hasher.update(&rows[..len]);
+        let now = Validated {
+            offset: from + len,
+            sha256: format!("{:x}", hasher.finalize()),
+        };
+        if now != validated {
+            if let Err(e) = write_json_file(&path, &now) {
+                debug!("failed to save the validated marker: {e}");
+            }
+        }
+        return Ok(());
+    }
+
+    let errs = malformed_rows(cli_args)?;
 
     if !errs.is_empty() {
         error!("Malformed CSV entries:");
         for err in errs {
             error!("{err}");
         }
-        return Err(eyre!(
-            "There are malformed entries in the CSV file. Please fix them manually and try again."
-        ));
+        return Err(ErrorCode::MalformedCsv.report(
+            "There are malformed entries in the CSV file. Please fix them manually and try again.",
+        ))
+        .suggestion("Run 'punchcard doctor --recover' to only keep the rows which can be parsed");
+    }
+
+    Ok(())
+}
+
+/// Identifies a version of the data file, similar to an HTTP ETag.
+///
+/// Commands record the revision before reading the data file and pass it along
+/// when writing, so that changes made by another process in between are detected
+/// instead of being silently overwritten.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Revision {
+    len: u64,
+    modified: SystemTime,
+}
+
+impl Revision {
+    /// Gets the current revision of the data file, or `None` if it doesn't exist.
+    pub fn current(cli_args: &Cli) -> Result<Option<Self>> {
+        let data_file = cli_args.get_output_file();
+        if !data_file.exists() {
+            return Ok(None);
+        }
+        let metadata = fs::metadata(&data_file).wrap_err(ERR_READ_CSV(&data_file))?;
+        Ok(Some(Self {
+            len: metadata.len(),
+            modified: metadata.modified().wrap_err(ERR_READ_CSV(&data_file))?,
+        }))
+    }
+
+    /// Returns an error if the data file is no longer at the expected revision.
+    pub fn ensure_current(expected: Option<&Self>, cli_args: &Cli) -> Result<()> {
+        let current = Self::current(cli_args)?;
+        if current.as_ref() != expected {
+            debug!(?expected, ?current, "data file revision changed");
+            return Err(ErrorCode::DataFileChanged.report(
+                "The data file was modified by another process while this command was running",
+            ))
+            .suggestion("Run the command again");
+        }
+        Ok(())
+    }
+}
+
+impl Display for Revision {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let modified = self
+            .modified
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        write!(f, "{:x}-{:x}", self.len, modified)
+    }
+}
+
+pub fn read_entries(cli_args: &Cli) -> Result<Vec<Entry>> {
+    let mut reader = build_reader(cli_args)?;
+    let rows = count_rows(cli_args)?;
+    // all entries will be Ok because the build_reader method throws
+    // an error if there are any malformed entries
+    Ok(reader
+        .deserialize::<Entry>()
+        .progress_with(progress::rows(rows, "Reading entries"))
+        .filter_map(Result::ok)
+        .collect())
+}
+
+/// Appends a single entry to the end of the data file, creating it if necessary.
+///
+/// If the data file was written by an older version with fewer columns, it is
+/// migrated to the current header first.
+///
+/// For a supervised kiosk user, an entry which isn't at the current time is
+/// proposed as an amendment instead. Returns whether the entry was written,
+/// or `false` if it was only proposed.
+///
+/// `revision` must be the revision of the data file when it was last read.
+pub fn append_entry(cli_args: &Cli, entry: &Entry, revision: Option<&Revision>) -> Result<bool> {
+    let data_file = cli_args.get_output_file();
+
+    Revision::ensure_current(revision, cli_args)?;
+
+    if amend::needs_approval(cli_args, entry) {
+        amend::propose(cli_args, Vec::new(), vec![entry.clone()])?;
+        return Ok(false);
+    }
+
+    migrate_data_file(cli_args)?;
+
+    // a blank file (e.g. created by hand) is started over so it gets a header
+    let has_headers = header_state(cli_args)? == HeaderState::Blank;
+    // otherwise the entry would be joined onto the last row
+    let needs_newline = !has_headers && !ends_with_newline(cli_args)?;
+
+    signal::uninterrupted(|| {
+        let mut file = File::options()
+            .create(true)
+            .append(!has_headers)
+            .write(true)
+            .truncate(has_headers)
+            .open(&data_file)
+            .wrap_err(ERR_OPEN_CSV(&data_file))
+            .suggestion(SUGG_PROPER_PERMS(&data_file))?;
+
+        if needs_newline {
+            file.write_all(b"\n")
+                .wrap_err(ERR_WRITE_CSV(&data_file))
+                .suggestion(SUGG_PROPER_PERMS(&data_file))?;
+        }
+
+        let mut writer = WriterBuilder::default()
+            .has_headers(has_headers)
+            .from_writer(file);
+
+        writer
+            .serialize(entry)
+            .and_then(|_| Ok(writer.flush()?))
+            .wrap_err(ERR_WRITE_CSV(&data_file))
+            .suggestion(SUGG_PROPER_PERMS(&data_file))
+    })?;
+
+    Ok(true)
+}
+
+/// Replaces the contents of the data file with the given entries.
+///
+/// The entries are written to a temporary file first which is then moved
+/// over the data file, so an interrupted write never leaves a partial file.
+///
+/// For a supervised kiosk user, the change is proposed as an amendment instead.
+///
+/// `revision` must be the revision of the data file when the entries were read.
+pub fn write_entries(cli_args: &Cli, entries: &[Entry], revision: Option<&Revision>) -> Result<()> {
+    let data_file = cli_args.get_output_file();
+
+    if cli_args.supervised {
+        Revision::ensure_current(revision, cli_args)?;
+        let old = match data_file.exists() {
+            true => read_entries(cli_args)?,
+            false => Vec::new(),
+        };
+        return amend::propose_rewrite(cli_args, old, entries);
+    }
+    let temp_file = data_file.with_extension("csv.tmp");
+    // an interrupted write is rolled back by removing the temporary file
+    let _cleanup = signal::remove_on_interrupt(&temp_file);
+
+    {
+        let mut writer = WriterBuilder::default()
+            .has_headers(false)
+            .from_path(&temp_file)
+            .wrap_err(ERR_OPEN_CSV(&temp_file))
+            .suggestion(SUGG_PROPER_PERMS(&temp_file))?;
+
+        writer
+            .write_record(CSV_HEADER)
+            .wrap_err(ERR_WRITE_CSV(&temp_file))?;
+
+        let bar = progress::rows(entries.len() as u64, "Writing entries");
+        for entry in entries.iter().progress_with(bar) {
+            writer
+                .serialize(entry)
+                .wrap_err(ERR_WRITE_CSV(&temp_file))?;
+        }
+
+        writer.flush().wrap_err(ERR_WRITE_CSV(&temp_file))?;
+    }
+
+    if let Err(e) = Revision::ensure_current(revision, cli_args) {
+        fs::remove_file(&temp_file).ok();
+        return Err(e);
+    }
+
+    signal::uninterrupted(|| fs::rename(&temp_file, &data_file))
+        .wrap_err(ERR_WRITE_CSV(&data_file))
+        .suggestion(SUGG_PROPER_PERMS(&data_file))?;
+
+    Ok(())
+}
+
+/// Checks if the data file has any rows besides the header.
+///
+/// A data file which doesn't exist, is empty, or only has a header has no entries.
+pub fn has_entries(cli_args: &Cli) -> Result<bool> {
+    let data_file = cli_args.get_output_file();
+    if !data_file.exists() {
+        return Ok(false);
+    }
+    let mut rows = match header_state(cli_args)? {
+        HeaderState::Missing => 1,
+        _ => 0,
+    };
+    let file = File::open(&data_file).wrap_err(ERR_READ_CSV(&data_file))?;
+    for line in BufReader::new(file).lines() {
+        if !line.wrap_err(ERR_READ_CSV(&data_file))?.trim().is_empty() {
+            rows += 1;
+        }
+        if rows > 1 {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Counts the rows of the data file without parsing them, to size progress bars.
+pub fn count_rows(cli_args: &Cli) -> Result<u64> {
+    let data_file = cli_args.get_output_file();
+    if !data_file.exists() {
+        return Ok(0);
+    }
+    let file = File::open(&data_file).wrap_err(ERR_READ_CSV(&data_file))?;
+    let mut reader = BufReader::new(file);
+    let mut lines = 0;
+    loop {
+        let buf = reader.fill_buf().wrap_err(ERR_READ_CSV(&data_file))?;
+        if buf.is_empty() {
+            break;
+        }
+        lines += buf.iter().filter(|&&b| b == b'\n').count() as u64;
+        let len = buf.len();
+        reader.consume(len);
     }
+    Ok(match header_state(cli_args)? {
+        HeaderState::Missing => lines,
+        _ => lines.saturating_sub(1),
+    })
+}
 
+/// Rewrites the data file with the current header if it was written by an
+/// older version, or if the header is missing entirely.
+pub fn migrate_data_file(cli_args: &Cli) -> Result<()> {
+    if matches!(
+        header_state(cli_args)?,
+        HeaderState::Outdated | HeaderState::Missing
+    ) {
+        debug!("migrating data file to the current header");
+        let revision = Revision::current(cli_args)?;
+        let entries = read_entries(cli_args)?;
+        write_entries(cli_args, &entries, revision.as_ref())?;
+    }
     Ok(())
 }
+
+/// What the first line of the data file looks like.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeaderState {
+    /// The data file doesn't exist, or only contains whitespace
+    Blank,
+    /// The first line is the current header
+    Current,
+    /// The first line is the header of an older version
+    Outdated,
+    /// The first line is an entry, e.g. because the file was created empty
+    Missing,
+}
+
+pub fn header_state(cli_args: &Cli) -> Result<HeaderState> {
+    let data_file = cli_args.get_output_file();
+    if !data_file.exists() {
+        return Ok(HeaderState::Blank);
+    }
+    let file = File::open(&data_file).wrap_err(ERR_READ_CSV(&data_file))?;
+    let mut first_line = String::new();
+    BufReader::new(file)
+        .read_line(&mut first_line)
+        .wrap_err(ERR_READ_CSV(&data_file))?;
+
+    let first_line = first_line.trim_end();
+
+    Ok(if first_line.trim().is_empty() {
+        HeaderState::Blank
+    } else if first_line == CSV_HEADER.join(",") {
+        HeaderState::Current
+    } else if first_line.starts_with(CSV_HEADER[0]) {
+        HeaderState::Outdated
+    } else {
+        HeaderState::Missing
+    })
+}
+
+/// Checks if the data file is empty or ends with a line break.
+pub fn ends_with_newline(cli_args: &Cli) -> Result<bool> {
+    let data_file = cli_args.get_output_file();
+    if !data_file.exists() {
+        return Ok(true);
+    }
+    let mut file = File::open(&data_file).wrap_err(ERR_READ_CSV(&data_file))?;
+    if file
+        .seek(SeekFrom::End(0))
+        .wrap_err(ERR_READ_CSV(&data_file))?
+        == 0
+    {
+        return Ok(true);
+    }
+    let mut last = [0];
+    file.seek(SeekFrom::End(-1))
+        .and_then(|_| file.read_exact(&mut last))
+        .wrap_err(ERR_READ_CSV(&data_file))?;
+    Ok(last[0] == b'\n')
+}
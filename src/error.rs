@@ -0,0 +1,129 @@
+// Copyright (C) 2023 Campbell M. Cole
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+// eyre messages are meant for humans and change whenever the wording is
+// improved, so wrappers which need to react to a failure get a stable code
+// and exit status instead
+
+use std::fmt::Display;
+
+use clap::ValueEnum;
+use color_eyre::Report;
+use thiserror::Error;
+
+/// A failure that scripts and GUIs may want to handle differently from others.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    /// The command failed for a reason without a dedicated code.
+    Unknown,
+    /// Clocking in while clocked in, or out while clocked out.
+    AlreadyClocked,
+    /// The entry would come before an existing entry, or leave an in without an out.
+    Continuity,
+    /// The data file contains rows that could not be parsed.
+    MalformedCsv,
+    /// The data file was changed by another process during the command.
+    DataFileChanged,
+    /// A template, recurring shift, or other named item does not exist.
+    NotFound,
+    /// A file checked by `verify` doesn't match any receipt.
+    Unverified,
+}
+
+impl ErrorCode {
+    /// The identifier printed in JSON output, which never changes once released.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Unknown => "E_UNKNOWN",
+            Self::AlreadyClocked => "E_ALREADY_CLOCKED",
+            Self::Continuity => "E_CONTINUITY",
+            Self::MalformedCsv => "E_MALFORMED_CSV",
+            Self::DataFileChanged => "E_DATA_FILE_CHANGED",
+            Self::NotFound => "E_NOT_FOUND",
+            Self::Unverified => "E_UNVERIFIED",
+        }
+    }
+
+    /// The exit status of the process. `2` is left to clap for usage errors.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::Unknown => 1,
+            Self::AlreadyClocked => 10,
+            Self::Continuity => 11,
+            Self::MalformedCsv => 12,
+            Self::DataFileChanged => 13,
+            Self::NotFound => 14,
+            Self::Unverified => 15,
+        }
+    }
+
+    /// Creates a report carrying this code, to be returned in place of `eyre!`.
+    #[track_caller]
+    pub fn report(self, message: impl Display) -> Report {
+        Report::new(CodedError {
+            code: self,
+            message: message.to_string(),
+        })
+    }
+
+    /// Finds the code attached anywhere in the chain of `report`.
+    pub fn of(report: &Report) -> Self {
+        report
+            .chain()
+            .find_map(|e| e.downcast_ref::<CodedError>())
+            .map(|e| e.code)
+            .unwrap_or(Self::Unknown)
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("{message}")]
+pub struct CodedError {
+    pub code: ErrorCode,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum OutputFormat {
+    /// Human-readable output
+    #[default]
+    Text,
+    /// Print errors as JSON objects with a stable `code`
+    Json,
+}
+
+/// Prints `report` in the given format and returns the exit status to use.
+pub fn report_error(report: &Report, format: OutputFormat) -> i32 {
+    let code = ErrorCode::of(report);
+    match format {
+        OutputFormat::Text => eprintln!("Error: {report:?}"),
+        OutputFormat::Json => {
+            let json = serde_json::json!({
+                "error": {
+                    "code": code.code(),
+                    "message": report.root_cause().to_string(),
+                    // the outermost context first, e.g. "Failed to clock in"
+                    "context": report
+                        .chain()
+                        .take(report.chain().len() - 1)
+                        .map(|e| e.to_string())
+                        .collect::<Vec<_>>(),
+                }
+            });
+            println!("{json}");
+        }
+    }
+    code.exit_code()
+}
@@ -0,0 +1,133 @@
+// Copyright (C) 2023 Campbell M. Cole
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+// where an entry was made is kept as a `location:<site>` tag, so it shows up
+// everywhere tags do and needs no change to the data file. the site is given
+// with `--location`, or detected from the hostname or wifi network when the
+// `location` config asks for it. the machine itself can also be recorded as a
+// `device:<hostname>` tag, to tell which copy of a synced data file an entry
+// came from
+
+use std::process::Command;
+
+use crate::{
+    config::{Config, Location, LocationCapture},
+    prelude::*,
+};
+
+pub const LOCATION_TAG_PREFIX: &str = "location:";
+pub const DEVICE_TAG_PREFIX: &str = "device:";
+
+/// The location in a list of tags, if there is one.
+pub fn location_of(tags: &[String]) -> Option<&str> {
+    tags.iter()
+        .find_map(|tag| tag.strip_prefix(LOCATION_TAG_PREFIX))
+}
+
+/// The tag recording that an entry was made at `location`.
+pub fn location_tag(location: &str) -> String {
+    // tags are separated by semicolons in the data file
+    format!("{LOCATION_TAG_PREFIX}{}", location.trim().replace(';', "_"))
+}
+
+/// Tags a clock-in with the location detected as configured, unless it
+/// already has one.
+pub fn add_location_tag(config: &Location, entry: &mut Entry) {
+    if entry.entry_type != EntryType::ClockIn || location_of(&entry.tags).is_some() {
+        return;
+    }
+    let detected = match config.capture {
+        LocationCapture::Hostname => hostname(),
+        LocationCapture::Wifi => wifi_network(),
+    };
+    let Some(detected) = detected else {
+        debug!("Could not detect the {:?} for the location", config.capture);
+        return;
+    };
+    let site = config.names.get(&detected).unwrap_or(&detected);
+    entry.tags.0.push(location_tag(site));
+}
+
+/// The device in a list of tags, if there is one.
+pub fn device_of(tags: &[String]) -> Option<&str> {
+    tags.iter()
+        .find_map(|tag| tag.strip_prefix(DEVICE_TAG_PREFIX))
+}
+
+/// Tags an entry with the hostname of this machine, replacing the device
+/// it was given, e.g. by a template made on another machine.
+pub fn add_device_tag(entry: &mut Entry) {
+    let Some(hostname) = hostname() else {
+        debug!("Could not detect the hostname for the device");
+        return;
+    };
+    entry.tags.0.retain(|t| !t.starts_with(DEVICE_TAG_PREFIX));
+    entry
+        .tags
+        .0
+        .push(format!("{DEVICE_TAG_PREFIX}{}", hostname.replace(';', "_")));
+}
+
+/// Tags an entry as configured.
+pub fn add_configured_tags(config: &Config, entry: &mut Entry) {
+    if let Some(location) = &config.location {
+        add_location_tag(location, entry);
+    }
+    if config.record_device {
+        add_device_tag(entry);
+    }
+}
+
+/// Runs a command, returning its trimmed output if it succeeded.
+fn output_of(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string()).filter(|s| !s.is_empty())
+}
+
+pub fn hostname() -> Option<String> {
+    std::fs::read_to_string("/proc/sys/kernel/hostname")
+        .ok()
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .or_else(|| output_of("hostname", &[]))
+}
+
+/// The SSID of the wifi network this machine is connected to.
+fn wifi_network() -> Option<String> {
+    if cfg!(target_os = "macos") {
+        // prints e.g. "Current Wi-Fi Network: Office"
+        let output = output_of("networksetup", &["-getairportnetwork", "en0"])?;
+        return output.split_once(": ").map(|(_, ssid)| ssid.to_string());
+    }
+    if cfg!(windows) {
+        // the `SSID` line, not to be confused with the `BSSID` line
+        let output = output_of("netsh", &["wlan", "show", "interfaces"])?;
+        return output.lines().find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            (key.trim() == "SSID").then(|| value.trim().to_string())
+        });
+    }
+    output_of("iwgetid", &["-r"]).or_else(|| {
+        // prints a line like "yes:Office" for the active connection
+        let output = output_of("nmcli", &["-t", "-f", "active,ssid", "dev", "wifi"])?;
+        output
+            .lines()
+            .find_map(|line| line.strip_prefix("yes:"))
+            .map(str::to_string)
+    })
+}
@@ -0,0 +1,76 @@
+// Copyright (C) 2023 Campbell M. Cole
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+// the terminal only shows errors by default, so the log files are what's
+// left to look at when something went wrong in a timer or a script
+
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::{fmt::format::FmtSpan, EnvFilter, Layer, Registry};
+
+use crate::{
+    config::{read_config, LogRotation},
+    prelude::*,
+};
+
+/// A layer which writes JSON logs to rotating files, if enabled in the config.
+///
+/// Problems with the config or the log folder are printed as warnings instead of
+/// failing, so a broken log setup never prevents clocking in or out.
+pub fn file_layer(cli_args: &Cli) -> Option<Box<dyn Layer<Registry> + Send + Sync>> {
+    use owo_colors::OwoColorize;
+
+    let settings = read_config(cli_args).ok()?.log_file?;
+
+    let filter = match EnvFilter::try_new(&settings.level) {
+        Ok(filter) => filter,
+        Err(err) => {
+            eprintln!(
+                "{} {err}",
+                format!("Invalid log level '{}' in the config:", settings.level).yellow()
+            );
+            return None;
+        }
+    };
+
+    let rotation = match settings.rotation {
+        LogRotation::Hourly => Rotation::HOURLY,
+        LogRotation::Daily => Rotation::DAILY,
+        LogRotation::Never => Rotation::NEVER,
+    };
+
+    let appender = RollingFileAppender::builder()
+        .rotation(rotation)
+        .filename_prefix("punchcard")
+        .filename_suffix("log")
+        .max_log_files(settings.keep.max(1))
+        .build(cli_args.get_log_folder());
+
+    let appender = match appender {
+        Ok(appender) => appender,
+        Err(err) => {
+            eprintln!("{} {err}", "Failed to open the log file:".yellow());
+            return None;
+        }
+    };
+
+    Some(
+        tracing_subscriber::fmt::layer()
+            .json()
+            .with_writer(appender)
+            .with_span_events(FmtSpan::CLOSE)
+            .with_filter(filter)
+            .boxed(),
+    )
+}
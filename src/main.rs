@@ -17,11 +17,41 @@ use std::{fs, path::PathBuf};
 
 use crate::csv::EntryType;
 use chrono_tz::Tz;
-use clap::{CommandFactory, Parser, Subcommand};
+use clap::{builder::BoolishValueParser, CommandFactory, Parser, Subcommand};
 use color_eyre::{eyre::Context, Help, Result};
 #[cfg(feature = "generate_test_data")]
 use command::generate::GenerateDataArgs;
-use command::{clock::ClockEntryArgs, report::ReportSettings};
+use command::{
+    absence::AbsenceArgs,
+    approve::ApproveArgs,
+    backfill::BackfillArgs,
+    batch::BatchArgs,
+    clock::{ClockEntryArgs, ClockInArgs, ClockToggleArgs},
+    config::ConfigArgs,
+    doctor::DoctorArgs,
+    events::EventsArgs,
+    export::ExportArgs,
+    fix_last::FixLastArgs,
+    git::GitArgs,
+    import::ImportArgs,
+    kiosk::KioskArgs,
+    list::ListArgs,
+    oncall::OncallArgs,
+    open::OpenArgs,
+    project::ProjectArgs,
+    recur::RecurArgs,
+    report::ReportSettings,
+    shell_hook::ShellHookArgs,
+    standup::StandupArgs,
+    stats::StatsArgs,
+    status::StatusArgs,
+    template::{StartArgs, TemplateArgs},
+    timer::{RemindArgs, TimerArgs},
+    url::HandleUrlArgs,
+    util::UtilArgs,
+    verify::VerifyArgs,
+};
+use error::OutputFormat;
 use prelude::SUGG_PROPER_PERMS;
 use tracing_error::ErrorLayer;
 use tracing_subscriber::{fmt, prelude::*, EnvFilter};
@@ -32,18 +62,40 @@ extern crate serde;
 #[macro_use]
 extern crate tracing;
 
-#[cfg(not(target_env = "msvc"))]
-use jemallocator::Jemalloc;
+#[cfg(all(feature = "jemalloc", feature = "mimalloc"))]
+compile_error!("the `jemalloc` and `mimalloc` features can't be enabled at the same time");
 
-#[cfg(not(target_env = "msvc"))]
+#[cfg(all(feature = "jemalloc", not(target_env = "msvc")))]
 #[global_allocator]
-static GLOBAL: Jemalloc = Jemalloc;
+static GLOBAL: jemallocator::Jemalloc = jemallocator::Jemalloc;
 
+#[cfg(all(feature = "mimalloc", not(feature = "jemalloc")))]
+#[global_allocator]
+static GLOBAL: mimalloc::MiMalloc = mimalloc::MiMalloc;
+
+pub mod alias;
+pub mod amend;
+pub mod clipboard;
 pub mod command;
 pub mod common;
+pub mod config;
 pub mod csv;
+pub mod error;
+pub mod location;
+pub mod log;
+pub mod notify;
+pub mod pager;
+pub mod palette;
 mod prelude;
+pub mod progress;
+pub mod receipt;
+pub mod shift;
+#[cfg(feature = "signing")]
+pub mod sign;
+pub mod signal;
 pub mod table;
+#[cfg(feature = "otel")]
+pub mod telemetry;
 pub mod types;
 
 fn default_timezone() -> Tz {
@@ -56,13 +108,40 @@ fn default_data_folder() -> PathBuf {
     dirs::data_dir().expect("Could not locate a suitable data directory. Please use the PUNCHCARD_DATA_FOLDER environment variable, or set the '--data-folder' option.").join("punchcard")
 }
 
+/// The version with the details of the build, printed by `--version` but not `-V`.
+const LONG_VERSION: &str = concat!(
+    env!("CARGO_PKG_VERSION"),
+    " (",
+    env!("PUNCHCARD_GIT_HASH"),
+    ")\n",
+    env!("PUNCHCARD_RUSTC_VERSION"),
+    "\ntarget: ",
+    env!("PUNCHCARD_TARGET"),
+    "\nfeatures: ",
+    env!("PUNCHCARD_FEATURES"),
+);
+
 #[derive(Debug, Parser)]
-#[command(author, version, about, long_about = None)]
+#[command(author, version, long_version = LONG_VERSION, about, long_about = None)]
 pub struct Cli {
     #[clap(short, long, env = "PUNCHCARD_DATA_FOLDER", default_value_os_t = default_data_folder())]
     pub data_folder: PathBuf,
     #[clap(short, long, env = "PUNCHCARD_TIMEZONE", default_value_t = default_timezone())]
     pub timezone: Tz,
+    /// Use the hours of this kiosk user instead of your own
+    #[clap(long)]
+    pub user: Option<String>,
+    /// Whether changes to past entries must be approved by a supervisor,
+    /// which is the case for kiosk users once the kiosk has a supervisor PIN
+    #[clap(skip)]
+    pub supervised: bool,
+    /// How to print errors, `json` gives each failure a stable code for scripts
+    #[clap(long, global = true, value_enum, default_value_t = OutputFormat::Text)]
+    pub output: OutputFormat,
+    /// Use colors which can be told apart with color blindness, and add
+    /// symbols to everything which is otherwise only shown by its color
+    #[clap(long, global = true, env = "PUNCHCARD_ACCESSIBLE", value_parser = BoolishValueParser::new())]
+    pub accessible: bool,
     #[clap(subcommand)]
     pub operation: Operation,
 }
@@ -71,6 +150,71 @@ impl Cli {
     pub fn get_output_file(&self) -> PathBuf {
         self.data_folder.join("hours.csv")
     }
+
+    pub fn get_templates_file(&self) -> PathBuf {
+        self.data_folder.join("templates.json")
+    }
+
+    pub fn get_recurring_file(&self) -> PathBuf {
+        self.data_folder.join("recurring.json")
+    }
+
+    pub fn get_projects_file(&self) -> PathBuf {
+        self.data_folder.join("projects.json")
+    }
+
+    pub fn get_config_file(&self) -> PathBuf {
+        self.data_folder.join("config.toml")
+    }
+
+    pub fn get_log_folder(&self) -> PathBuf {
+        self.data_folder.join("logs")
+    }
+
+    pub fn get_amendments_file(&self) -> PathBuf {
+        self.data_folder.join("amendments.json")
+    }
+
+    pub fn get_payroll_file(&self) -> PathBuf {
+        self.data_folder.join("payroll.json")
+    }
+
+    pub fn get_snapshots_file(&self) -> PathBuf {
+        self.data_folder.join("snapshots.json")
+    }
+
+    pub fn get_receipts_file(&self) -> PathBuf {
+        self.data_folder.join("receipts.jsonl")
+    }
+
+    pub fn get_shell_hook_file(&self) -> PathBuf {
+        self.data_folder.join("shell-hook.json")
+    }
+
+    pub fn get_oncall_file(&self) -> PathBuf {
+        self.data_folder.join("oncall.json")
+    }
+
+    pub fn get_absences_file(&self) -> PathBuf {
+        self.data_folder.join("absences.json")
+    }
+
+    pub fn get_kiosk_file(&self) -> PathBuf {
+        self.data_folder.join("kiosk.json")
+    }
+
+    pub fn get_quarantine_folder(&self) -> PathBuf {
+        self.data_folder.join("quarantine")
+    }
+
+    pub fn get_validated_file(&self) -> PathBuf {
+        self.data_folder.join("validated.json")
+    }
+
+    /// The data folder of a kiosk user.
+    pub fn get_user_folder(&self, id: &str) -> PathBuf {
+        self.data_folder.join("users").join(id)
+    }
 }
 
 #[derive(Debug, Subcommand)]
@@ -79,8 +223,11 @@ pub enum Operation {
     ///
     /// Adds a clock-in entry to the data file with the current time,
     /// or the time given with the '-o' flag.
+    ///
+    /// Use '--for' to timebox the session with a desktop notification,
+    /// and '--auto-out' to also clock out when the time is up.
     #[command(name = "in")]
-    ClockIn(ClockEntryArgs),
+    ClockIn(ClockInArgs),
     /// Clock out
     ///
     /// Adds a clock-out entry to the data file with the current time,
@@ -92,7 +239,7 @@ pub enum Operation {
     /// Clocks in or out depending on what was done last. Override
     /// the time used with the '-o' flag.
     #[command(name = "toggle")]
-    ClockToggle(ClockEntryArgs),
+    ClockToggle(ClockToggleArgs),
     /// Check the current status
     ///
     /// Prints whether or not you are clocked in right now, and
@@ -101,7 +248,190 @@ pub enum Operation {
     /// the time checked, so you can check if you were/will be clocked
     /// in/out at a certain time.
     #[command(name = "status")]
-    ClockStatus(ClockEntryArgs),
+    ClockStatus(StatusArgs),
+    /// List the entries in the data file
+    ///
+    /// Prints every entry along with its project, tags, and how it was
+    /// created. Use '--month' and '--source' to narrow the list down.
+    #[command(name = "list")]
+    ListEntries(ListArgs),
+    /// Change the time of the last entry
+    ///
+    /// Moves the last entry later or earlier, e.g. 'punchcard fix-last -15m'
+    /// when you forgot to clock out until later. Without a correction, the
+    /// entry is shown and you are asked for one.
+    #[command(name = "fix-last")]
+    FixLast(FixLastArgs),
+    /// Clock in using a shift template
+    ///
+    /// Clocks in with the project and tags of the given template. If the
+    /// template has a duration, a clock-out entry is also added at the
+    /// end of the shift.
+    #[command(name = "start")]
+    StartTemplate(StartArgs),
+    /// Manage shift templates
+    ///
+    /// Templates store the project, tags, and duration of recurring
+    /// activities so they can be started with 'punchcard start <name>'.
+    #[command(name = "template")]
+    Template(TemplateArgs),
+    /// Manage recurring shifts
+    ///
+    /// Recurring shifts describe fixed schedules (e.g. every Tuesday and
+    /// Thursday from 18:00 to 20:00) which can be added to the data file
+    /// for a whole month at once with 'punchcard recur apply'.
+    #[command(name = "recur")]
+    Recur(RecurArgs),
+    /// Manage project settings
+    ///
+    /// Projects are created implicitly by clocking in with '--project',
+    /// this only stores extra settings such as the color used in tables.
+    #[command(name = "project")]
+    Project(ProjectArgs),
+    /// Track the periods you are on call
+    ///
+    /// On-call periods run alongside your shifts. Clocking in while on call
+    /// tags the shift as an incident, and 'punchcard oncall report' shows
+    /// the hours on call and the incident hours worked during them.
+    #[command(name = "oncall")]
+    Oncall(OncallArgs),
+    /// Record vacations and other absences
+    ///
+    /// Reports mark the days and weeks with an absence, e.g. with
+    /// '(vacation)', and leave those days out of '--utilization' and the
+    /// targets of copyable reports.
+    #[command(name = "absence")]
+    Absence(AbsenceArgs),
+    /// Schedule a one-shot reminder
+    ///
+    /// Shows a desktop notification after the given time. The reminder is
+    /// canceled automatically if you clock in or out before it fires,
+    /// unless '--always' is given.
+    #[command(name = "remind")]
+    Remind(RemindArgs),
+    /// Add many entries at once from stdin
+    ///
+    /// Each line is `<in|out> <timestamp> [project] [#tag...]`, for example
+    /// `in 2024-03-01T09:00 acme #meeting`. Blank lines and lines starting
+    /// with `#` are ignored. The whole batch is validated before anything is
+    /// written, so either every entry is added or none are.
+    #[command(name = "batch")]
+    Batch(BatchArgs),
+    /// Enter the hours of a whole week at once
+    ///
+    /// Asks for the shifts worked on each day of the week, e.g. for a week
+    /// which was tracked on paper, or reads them from a file with
+    /// '--from-file'. Like a batch, nothing is added unless every shift fits
+    /// between the existing entries.
+    #[command(name = "backfill")]
+    Backfill(BackfillArgs),
+    /// Import the hours tracked with another tool
+    ///
+    /// The entries are added the same way as with 'punchcard batch', so
+    /// nothing is imported unless every shift fits between the existing ones.
+    #[command(name = "import")]
+    Import(ImportArgs),
+    /// Print the changes to your hours as JSON lines
+    ///
+    /// 'punchcard events --follow' keeps running and prints every entry
+    /// added or removed and every amendment proposed or resolved as it
+    /// happens, one JSON object per line.
+    #[command(name = "events")]
+    Events(EventsArgs),
+    /// Run an operation described by a `punchcard://` URL
+    ///
+    /// Gives automation apps (e.g. Apple Shortcuts or Tasker over SSH) a
+    /// single entry point. The action is one of `in`, `out`, `toggle`,
+    /// `status`, or `start`, and the query can contain `project`, `tag`
+    /// (repeatable), `location`, `travel`, `note`, `offset`, `for`, `auto-out`,
+    /// `notify`, `calendar`, `recent`, and `name`.
+    ///
+    /// Example: punchcard handle-url "punchcard://in?project=acme&tag=meeting"
+    #[command(name = "handle-url")]
+    HandleUrl(HandleUrlArgs),
+    /// Summarize the last day worked for a standup
+    ///
+    /// Prints the time spent on each project during the last day worked
+    /// before today, with the notes of its shifts, as a bullet list which
+    /// can be pasted into a chat. Use '--copy' to also copy it.
+    #[command(name = "standup")]
+    Standup(StandupArgs),
+    /// Check the data file for problems
+    ///
+    /// Looks for a missing or outdated header, a last row without a line
+    /// break, rows which can't be parsed, and entries which are not later
+    /// than the entry before them. Use '--fix' to repair the problems
+    /// which can be fixed automatically.
+    #[command(name = "doctor")]
+    Doctor(DoctorArgs),
+    /// Show information about the data file
+    ///
+    /// Prints the location and size of the data file, how many entries it
+    /// has and the dates they cover, whether its header is up to date, and
+    /// how many problems 'punchcard doctor' would report. Please include
+    /// this when reporting a bug.
+    #[command(name = "info")]
+    Info,
+    /// Run a shared punch clock for several workers
+    ///
+    /// Shows a full screen kiosk where each worker types their ID to clock
+    /// in or out. A supervisor can unlock edits with a PIN. Each worker's
+    /// hours are kept separately, use '--user' to see them with any other
+    /// command, e.g. 'punchcard --user 42 report'.
+    #[command(name = "kiosk")]
+    Kiosk(KioskArgs),
+    /// Approve changes made by kiosk users
+    ///
+    /// Once the kiosk has a supervisor PIN, changes to past entries made
+    /// with '--user' are saved as amendments instead of being written to
+    /// the data file. Without arguments, the pending amendments are listed.
+    #[command(name = "approve")]
+    Approve(ApproveArgs),
+    /// Export the hours for other software
+    ///
+    /// 'punchcard export payroll --preset gusto' writes the hours of the
+    /// previous month as a CSV file which the payroll system can import.
+    #[command(name = "export")]
+    Export(ExportArgs),
+    /// Check that an exported file wasn't changed
+    ///
+    /// Compares the file with the receipts kept by the '--receipt' flag
+    /// of 'punchcard report' and 'punchcard export'.
+    #[command(name = "verify")]
+    Verify(VerifyArgs),
+    /// Show statistics learned from your history
+    ///
+    /// 'punchcard stats pattern' shows when you usually start and finish
+    /// on each weekday.
+    #[command(name = "stats")]
+    Stats(StatsArgs),
+    /// Track hours per git repository and branch
+    ///
+    /// 'punchcard git hook install' tags clock-ins made from within the
+    /// repository with its name and branch, and starts a new shift when
+    /// switching branches while clocked in.
+    #[command(name = "git")]
+    Git(GitArgs),
+    /// Print a script which offers to clock in on the first prompt of the day
+    ///
+    /// Add 'eval "$(punchcard shell-hook zsh)"' to your shell's startup file.
+    /// Set 'shell-hook.auto-clock-in' in the config to clock in without asking.
+    #[command(name = "shell-hook")]
+    ShellHook(ShellHookArgs),
+    /// Open the data folder, or edit the data file
+    ///
+    /// 'punchcard open --editor' opens the data file in your editor, and only
+    /// saves the changes if 'punchcard doctor' finds no problems with them.
+    #[command(name = "open")]
+    Open(OpenArgs),
+    /// Manage the config file
+    ///
+    /// Settings are read from `config.toml` in the data folder.
+    #[command(name = "config")]
+    Config(ConfigArgs),
+    /// Helpers for scripts and shell integrations
+    #[command(name = "util")]
+    Util(UtilArgs),
     /// Interpret the times and generate a report
     ///
     /// Processes the entries in the data file and generates a table.
@@ -111,7 +441,7 @@ pub enum Operation {
     /// The daily report shows the total hours worked each day this week.
     /// The weekly report shows the total hours worked each week this month.
     #[command(name = "report")]
-    GenerateReport(ReportSettings),
+    GenerateReport(Box<ReportSettings>),
     /// Generate completions for the given shell
     ///
     /// Prints completions to stdout. You will need to pipe these
@@ -124,45 +454,146 @@ pub enum Operation {
     #[cfg(feature = "generate_test_data")]
     /// Generate test data
     GenerateData(GenerateDataArgs),
+    /// Show a notification at a given time (used internally for timeboxes)
+    #[command(name = "timer", hide = true)]
+    Timer(TimerArgs),
 }
 
 fn main() -> Result<()> {
     dotenvy::dotenv().ok();
+    let mut cli_args = Cli::parse_from(alias::expand(std::env::args_os().collect()));
+    // reading the config for the log file can fail, which needs the hook
+    color_eyre::install()?;
+
+    if let Err(report) = command::kiosk::select_user(&mut cli_args) {
+        std::process::exit(error::report_error(&report, cli_args.output));
+    }
+
+    let mut layers = Vec::new();
+    layers.extend(log::file_layer(&cli_args));
+    #[cfg(feature = "otel")]
+    layers.extend(telemetry::otlp_layer());
+
     tracing_subscriber::registry()
-        .with(fmt::layer().with_target(true))
-        .with(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("error")))
+        .with(layers)
+        .with(fmt::layer().with_target(true).with_filter(
+            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("error")),
+        ))
         .with(ErrorLayer::default())
         .init();
-    color_eyre::install()?;
+    signal::install();
 
-    let cli_args = Cli::parse();
+    let result = run(&cli_args);
+
+    #[cfg(feature = "otel")]
+    telemetry::shutdown();
+
+    if let Err(report) = result {
+        std::process::exit(error::report_error(&report, cli_args.output));
+    }
 
+    Ok(())
+}
+
+fn run(cli_args: &Cli) -> Result<()> {
     let data_folder = &cli_args.data_folder;
     if !data_folder.exists() {
         fs::create_dir_all(data_folder)
             .wrap_err("Failed to create data folder")
             .suggestion(SUGG_PROPER_PERMS(data_folder))?;
     }
-
     match &cli_args.operation {
-        Operation::ClockIn(args) => command::clock::add_entry(&cli_args, EntryType::ClockIn, args)
-            .wrap_err("Failed to clock in")?,
-        Operation::ClockOut(args) => {
-            command::clock::add_entry(&cli_args, EntryType::ClockOut, args)
-                .wrap_err("Failed to clock out")?
+        Operation::ClockIn(args) => {
+            command::clock::clock_in(cli_args, args).wrap_err("Failed to clock in")?
         }
-        Operation::ClockStatus(args) => command::status::get_clock_status(&cli_args, args)
+        Operation::ClockOut(args) => command::clock::add_entry(cli_args, EntryType::ClockOut, args)
+            .wrap_err("Failed to clock out")?,
+        Operation::ClockStatus(args) => command::status::get_clock_status(cli_args, args)
             .wrap_err("Failed to check clock status")?,
-        Operation::ClockToggle(args) => command::clock::toggle_clock(&cli_args, args)
+        Operation::ClockToggle(args) => command::clock::toggle_clock(cli_args, args)
             .wrap_err("Failed to toggle clock status")?,
-        Operation::GenerateReport(args) => command::report::generate_report(&cli_args, args)
+        Operation::ListEntries(args) => {
+            command::list::list_entries(cli_args, args).wrap_err("Failed to list entries")?
+        }
+        Operation::StartTemplate(args) => command::template::start_template(cli_args, args)
+            .wrap_err("Failed to start template")?,
+        Operation::Template(args) => command::template::manage_templates(cli_args, args)
+            .wrap_err("Failed to manage templates")?,
+        Operation::Project(args) => command::project::manage_projects(cli_args, args)
+            .wrap_err("Failed to manage projects")?,
+        Operation::Recur(args) => command::recur::manage_recurring(cli_args, args)
+            .wrap_err("Failed to manage recurring shifts")?,
+        Operation::Absence(args) => command::absence::manage_absences(cli_args, args)
+            .wrap_err("Failed to manage absences")?,
+        Operation::Oncall(args) => command::oncall::manage_oncall(cli_args, args)
+            .wrap_err("Failed to manage on-call periods")?,
+        Operation::Remind(args) => command::timer::schedule_reminder(cli_args, args)
+            .wrap_err("Failed to schedule reminder")?,
+        Operation::FixLast(args) => {
+            command::fix_last::fix_last(cli_args, args).wrap_err("Failed to fix the last entry")?
+        }
+        Operation::Batch(args) => {
+            command::batch::apply_batch(cli_args, args).wrap_err("Failed to apply batch")?
+        }
+        Operation::Backfill(args) => {
+            command::backfill::backfill(cli_args, args).wrap_err("Failed to backfill")?
+        }
+        Operation::Import(args) => {
+            command::import::import(cli_args, args).wrap_err("Failed to import hours")?
+        }
+        Operation::Events(args) => {
+            command::events::print_events(cli_args, args).wrap_err("Failed to print events")?
+        }
+        Operation::Standup(args) => command::standup::print_standup(cli_args, args)
+            .wrap_err("Failed to summarize the last day")?,
+        Operation::HandleUrl(args) => {
+            command::url::handle_url(cli_args, args).wrap_err("Failed to handle URL")?
+        }
+        Operation::Doctor(args) => {
+            command::doctor::run_doctor(cli_args, args).wrap_err("Failed to check data file")?
+        }
+        Operation::Info => {
+            command::info::print_info(cli_args).wrap_err("Failed to show data file info")?
+        }
+        Operation::Kiosk(args) => {
+            command::kiosk::manage_kiosk(cli_args, args).wrap_err("Failed to run kiosk")?
+        }
+        Operation::Approve(args) => {
+            command::approve::approve(cli_args, args).wrap_err("Failed to approve amendments")?
+        }
+        Operation::Export(args) => {
+            command::export::export(cli_args, args).wrap_err("Failed to export hours")?
+        }
+        Operation::Verify(args) => {
+            command::verify::verify(cli_args, args).wrap_err("Failed to verify file")?
+        }
+        Operation::Stats(args) => {
+            command::stats::show_stats(cli_args, args).wrap_err("Failed to show statistics")?
+        }
+        Operation::Git(args) => {
+            command::git::manage_git(cli_args, args).wrap_err("Failed to run git integration")?
+        }
+        Operation::ShellHook(args) => {
+            command::shell_hook::shell_hook(cli_args, args).wrap_err("Failed to run shell hook")?
+        }
+        Operation::Open(args) => command::open::open(cli_args, args).wrap_err("Failed to open")?,
+        Operation::Config(args) => {
+            command::config::manage_config(cli_args, args).wrap_err("Failed to manage config")?
+        }
+        Operation::Util(args) => {
+            command::util::run_util(cli_args, args).wrap_err("Failed to run utility")?
+        }
+        Operation::GenerateReport(args) => command::report::generate_report(cli_args, args)
             .wrap_err("Failed to generate report")?,
         Operation::GenerateCompletions { shell } => {
             shell.generate(&mut Cli::command(), &mut std::io::stdout());
         }
         #[cfg(feature = "generate_test_data")]
-        Operation::GenerateData(args) => command::generate::generate_test_entries(&cli_args, args)
+        Operation::GenerateData(args) => command::generate::generate_test_entries(cli_args, args)
             .wrap_err("Failed to generate test entries")?,
+        Operation::Timer(args) => {
+            command::timer::run_timer(cli_args, args).wrap_err("Failed to run timer")?
+        }
     }
 
     Ok(())
@@ -0,0 +1,58 @@
+// Copyright (C) 2023 Campbell M. Cole
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::process::{Command, Stdio};
+
+use crate::prelude::*;
+
+/// Shows a desktop notification using the notification tool of the platform.
+///
+/// On macOS this uses `osascript`, everywhere else it uses `notify-send`.
+pub fn send_notification(summary: &str, body: &str) -> Result<()> {
+    let mut command = if cfg!(target_os = "macos") {
+        let mut command = Command::new("osascript");
+        command.arg("-e").arg(format!(
+            "display notification {} with title {}",
+            applescript_string(body),
+            applescript_string(summary)
+        ));
+        command
+    } else {
+        let mut command = Command::new("notify-send");
+        command.args(["--app-name", "punchcard", summary, body]);
+        command
+    };
+
+    let status = command
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .wrap_err("Failed to send desktop notification")
+        .suggestion(if cfg!(target_os = "macos") {
+            "Ensure `osascript` is available"
+        } else {
+            "Ensure `notify-send` is installed (usually provided by libnotify)"
+        })?;
+
+    if !status.success() {
+        return Err(eyre!("The notification command exited with {status}"));
+    }
+
+    Ok(())
+}
+
+fn applescript_string(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
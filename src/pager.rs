@@ -0,0 +1,72 @@
+// Copyright (C) 2023 Campbell M. Cole
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+// tables which don't fit in the terminal are shown in a pager instead of
+// flooding the scrollback, like git does. the pager is $PAGER or `less`, and
+// the table is printed as it is if it can't be started
+
+use std::{
+    env,
+    io::{self, IsTerminal, Write},
+    process::{Command, Stdio},
+};
+
+/// Prints `text`, in a pager if `paged` and it's taller than the terminal.
+pub fn print_paged(text: &str, paged: bool) {
+    if paged && is_too_tall(text) {
+        match page(text) {
+            Ok(()) => return,
+            Err(err) => debug!("failed to start the pager: {err}"),
+        }
+    }
+    println!("{text}");
+}
+
+fn is_too_tall(text: &str) -> bool {
+    if !io::stdout().is_terminal() {
+        return false;
+    }
+    // the prompt takes up a row as well
+    crossterm::terminal::size().is_ok_and(|(_, rows)| text.lines().count() >= rows as usize)
+}
+
+fn page(text: &str) -> io::Result<()> {
+    let pager = env::var("PAGER")
+        .ok()
+        .filter(|p| !p.trim().is_empty())
+        .unwrap_or_else(|| String::from("less"));
+    // pagers are often set with arguments, e.g. 'less -S'
+    let mut words = pager.split_whitespace();
+    let program = words.next().unwrap_or_default();
+
+    let mut child = Command::new(program)
+        .args(words)
+        // keep the colors, and don't page what fits on the screen after all
+        .env(
+            "LESS",
+            env::var("LESS").unwrap_or_else(|_| String::from("FRX")),
+        )
+        .stdin(Stdio::piped())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // the pager can be quit before it has read everything
+        match writeln!(stdin, "{text}") {
+            Err(err) if err.kind() != io::ErrorKind::BrokenPipe => return Err(err),
+            _ => {}
+        }
+    }
+    child.wait()?;
+    Ok(())
+}
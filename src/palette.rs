@@ -0,0 +1,69 @@
+// Copyright (C) 2023 Campbell M. Cole
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+// the colors which carry meaning, such as green for clocked in and red for
+// clocked out. '--accessible' replaces them with colors from the Okabe-Ito
+// palette, which can be told apart with any kind of color blindness, and adds
+// symbols so that nothing depends on the color alone
+
+use owo_colors::DynColors;
+
+use crate::{csv::EntryType, table::color::Color};
+
+const ORANGE: (u8, u8, u8) = (230, 159, 0);
+const SKY_BLUE: (u8, u8, u8) = (86, 180, 233);
+const BLUISH_GREEN: (u8, u8, u8) = (0, 158, 115);
+const YELLOW: (u8, u8, u8) = (240, 228, 66);
+const BLUE: (u8, u8, u8) = (0, 114, 178);
+
+/// The color of clock-ins or clock-outs.
+pub fn entry_color(entry_type: EntryType, accessible: bool) -> DynColors {
+    use owo_colors::AnsiColors;
+    match (entry_type, accessible) {
+        (EntryType::ClockIn, false) => DynColors::Ansi(AnsiColors::Green),
+        (EntryType::ClockOut, false) => DynColors::Ansi(AnsiColors::Red),
+        (EntryType::ClockIn, true) => rgb(SKY_BLUE),
+        (EntryType::ClockOut, true) => rgb(ORANGE),
+    }
+}
+
+/// The name of an entry type, with a symbol in front of it if accessible.
+pub fn entry_label(entry_type: EntryType, accessible: bool) -> String {
+    match (entry_type, accessible) {
+        (_, false) => entry_type.to_string(),
+        (EntryType::ClockIn, true) => format!("▶ {entry_type}"),
+        (EntryType::ClockOut, true) => format!("■ {entry_type}"),
+    }
+}
+
+/// The colors of the columns of a table, unless others are given.
+pub fn table_colors(accessible: bool) -> Vec<Color> {
+    if !accessible {
+        return vec![
+            Color::DarkGreen,
+            Color::DarkYellow,
+            Color::DarkRed,
+            Color::DarkBlue,
+            Color::DarkCyan,
+        ];
+    }
+    [ORANGE, SKY_BLUE, BLUISH_GREEN, YELLOW, BLUE]
+        .map(|(r, g, b)| Color::Rgb { r, g, b })
+        .to_vec()
+}
+
+fn rgb((r, g, b): (u8, u8, u8)) -> DynColors {
+    DynColors::Rgb(r, g, b)
+}
@@ -25,6 +25,7 @@ pub use chrono_tz::OffsetName;
 pub use clap::{Args, Subcommand};
 
 pub use crate::common::*;
-pub use crate::csv::{Entry, EntryType};
+pub use crate::csv::{Entry, EntrySource, EntryType};
+pub use crate::error::ErrorCode;
 pub use crate::types::*;
 pub use crate::Cli;
@@ -0,0 +1,62 @@
+// Copyright (C) 2023 Campbell M. Cole
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+// progress bars for the operations which can take a while on a large data
+// file. they are drawn on stderr so they never end up in piped output, and
+// only when stderr is a terminal and there are enough rows for the bar to be
+// visible for more than a moment
+
+use std::{
+    io::{self, IsTerminal},
+    time::Duration,
+};
+
+use indicatif::{ProgressBar, ProgressFinish, ProgressStyle};
+
+/// Below this many rows an operation is quick enough that a bar would only flicker.
+const MIN_ROWS: u64 = 50_000;
+
+/// A bar counting `rows` rows, which is hidden if it wouldn't be useful.
+pub fn rows(rows: u64, message: &'static str) -> ProgressBar {
+    if !visible(rows) {
+        return ProgressBar::hidden();
+    }
+    let style =
+        ProgressStyle::with_template("{msg} {wide_bar} {human_pos}/{human_len} rows ({eta})")
+            .expect("progress template is valid");
+    ProgressBar::new(rows)
+        .with_style(style)
+        .with_message(message)
+        .with_finish(ProgressFinish::AndClear)
+}
+
+/// A spinner for work over `rows` rows which doesn't report its progress.
+pub fn spinner(rows: u64, message: &'static str) -> ProgressBar {
+    if !visible(rows) {
+        return ProgressBar::hidden();
+    }
+    let style = ProgressStyle::with_template("{spinner} {msg} ({elapsed})")
+        .expect("progress template is valid");
+    let spinner = ProgressBar::new_spinner()
+        .with_style(style)
+        .with_message(message)
+        .with_finish(ProgressFinish::AndClear);
+    spinner.enable_steady_tick(Duration::from_millis(100));
+    spinner
+}
+
+fn visible(rows: u64) -> bool {
+    rows >= MIN_ROWS && io::stderr().is_terminal()
+}
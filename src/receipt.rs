@@ -0,0 +1,141 @@
+// Copyright (C) 2023 Campbell M. Cole
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+// receipts prove that an exported file wasn't changed after it was made. the
+// hash of everything written is appended to `receipts.jsonl` in the data
+// folder, which 'punchcard verify' checks files against
+
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, BufRead, BufReader, Write},
+};
+
+use sha2::{Digest, Sha256};
+
+use crate::prelude::*;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Receipt {
+    /// The SHA-256 of the file, in hex
+    pub sha256: String,
+    /// Where the file was written, or `-` for stdout
+    pub file: String,
+    pub created_at: DateTime<Local>,
+    /// The command line which wrote the file
+    pub command: String,
+}
+
+/// Passes everything written on to `inner` while hashing it.
+pub struct HashingWriter<W: Write> {
+    inner: W,
+    hasher: Sha256,
+}
+
+impl<W: Write> HashingWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            hasher: Sha256::new(),
+        }
+    }
+
+    /// The hash of everything written so far, in hex.
+    pub fn hex_digest(&self) -> String {
+        format!("{:x}", self.hasher.clone().finalize())
+    }
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+pub fn hash_file(path: &std::path::Path) -> Result<String> {
+    let mut file = fs::File::open(path)
+        .wrap_err_with(|| format!("Failed to open {}", path.display()))
+        .suggestion(SUGG_PROPER_PERMS(path))?;
+    let mut hasher = HashingWriter::new(io::sink());
+    io::copy(&mut file, &mut hasher)
+        .wrap_err_with(|| format!("Failed to read {}", path.display()))?;
+    Ok(hasher.hex_digest())
+}
+
+pub fn read_receipts(cli_args: &Cli) -> Result<Vec<Receipt>> {
+    let path = cli_args.get_receipts_file();
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let file = fs::File::open(&path)
+        .wrap_err(ERR_READ_JSON(&path))
+        .suggestion(SUGG_PROPER_PERMS(&path))?;
+    BufReader::new(file)
+        .lines()
+        .filter(|line| !matches!(line, Ok(line) if line.trim().is_empty()))
+        .map(|line| {
+            serde_json::from_str(&line?)
+                .wrap_err(ERR_READ_JSON(&path))
+                .suggestion(SUGG_REPORT_ISSUE)
+        })
+        .collect()
+}
+
+/// Appends a receipt for a file which was just written, and prints its hash.
+///
+/// The hash goes to stderr when the file itself was written to stdout.
+pub fn record_receipt(cli_args: &Cli, destination: &Destination, sha256: String) -> Result<()> {
+    let receipt = Receipt {
+        sha256,
+        file: match destination {
+            Destination::Stdout => "-".into(),
+            Destination::File(path) => fs::canonicalize(path)
+                .unwrap_or_else(|_| path.clone())
+                .display()
+                .to_string(),
+        },
+        created_at: Local::now(),
+        command: command_line(),
+    };
+
+    let path = cli_args.get_receipts_file();
+    let mut line = serde_json::to_string(&receipt).wrap_err(ERR_WRITE_JSON(&path))?;
+    line.push('\n');
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| file.write_all(line.as_bytes()))
+        .wrap_err(ERR_WRITE_JSON(&path))
+        .suggestion(SUGG_PROPER_PERMS(&path))?;
+
+    {
+        use owo_colors::{DynColors, OwoColorize};
+        let gray = DynColors::Rgb(128, 128, 128);
+        let message = format!("{} {}", "SHA-256:".color(gray), receipt.sha256.bold());
+        match destination {
+            Destination::Stdout => eprintln!("{message}"),
+            Destination::File(_) => println!("{message}"),
+        }
+    }
+
+    Ok(())
+}
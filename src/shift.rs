@@ -0,0 +1,148 @@
+// Copyright (C) 2023 Campbell M. Cole
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+// the reports use polars to interpret the data file, but some commands
+// only need a handful of totals and it's much cheaper to compute them here
+
+use chrono::{Datelike, Duration, NaiveDate};
+
+use crate::{csv::read_entries, prelude::*};
+
+/// A clock-in entry and the clock-out entry that follows it, if there is one.
+#[derive(Debug, Clone)]
+pub struct Shift {
+    pub start: Entry,
+    pub end: Option<Entry>,
+}
+
+impl Shift {
+    /// The end of the shift, or `now` if it hasn't ended yet.
+    pub fn end_or(&self, now: DateTime<Local>) -> DateTime<Local> {
+        self.end.as_ref().map(|e| e.timestamp).unwrap_or(now)
+    }
+
+    pub fn duration(&self, now: DateTime<Local>) -> Duration {
+        self.end_or(now) - self.start.timestamp
+    }
+
+    /// The project of the shift, which is usually only given when clocking in.
+    pub fn project(&self) -> Option<&str> {
+        self.start
+            .project
+            .as_deref()
+            .or_else(|| self.end.as_ref().and_then(|e| e.project.as_deref()))
+    }
+
+    /// Whether the shift was spent travelling. Like the project, this is
+    /// usually only given when clocking in.
+    pub fn is_travel(&self) -> bool {
+        self.start.tags.is_travel() || self.end.as_ref().is_some_and(|e| e.tags.is_travel())
+    }
+
+    /// The notes of the clock-in and the clock-out, in that order.
+    pub fn notes(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(&self.start)
+            .chain(&self.end)
+            .filter_map(|e| e.note.as_deref())
+    }
+
+    /// The part of this shift which falls between `from` and `to`.
+    pub fn overlap(
+        &self,
+        from: DateTime<Local>,
+        to: DateTime<Local>,
+        now: DateTime<Local>,
+    ) -> Duration {
+        let start = self.start.timestamp.max(from);
+        let end = self.end_or(now).min(to);
+        (end - start).max(Duration::zero())
+    }
+}
+
+/// Reads the data file and pairs up its entries into shifts.
+pub fn read_shifts(cli_args: &Cli) -> Result<Vec<Shift>> {
+    if !cli_args.get_output_file().exists() {
+        return Ok(Vec::new());
+    }
+    let mut entries = read_entries(cli_args)?;
+    entries.sort_by_key(|e| e.timestamp);
+    Ok(to_shifts(entries))
+}
+
+/// Pairs up the clock-in and clock-out entries. `entries` must be sorted by timestamp.
+///
+/// Clock-out entries without a preceding clock-in are ignored, the same way
+/// the reports ignore them.
+pub fn to_shifts(entries: Vec<Entry>) -> Vec<Shift> {
+    let mut shifts = Vec::new();
+    let mut current: Option<Entry> = None;
+
+    for entry in entries {
+        match entry.entry_type {
+            EntryType::ClockIn => {
+                if let Some(start) = current.take() {
+                    shifts.push(Shift { start, end: None });
+                }
+                current = Some(entry);
+            }
+            EntryType::ClockOut => {
+                if let Some(start) = current.take() {
+                    shifts.push(Shift {
+                        start,
+                        end: Some(entry),
+                    });
+                }
+            }
+        }
+    }
+
+    if let Some(start) = current {
+        shifts.push(Shift { start, end: None });
+    }
+
+    shifts
+}
+
+/// The total time worked between `from` and `to`, counting the current shift up to `now`.
+pub fn total_between(
+    shifts: &[Shift],
+    from: DateTime<Local>,
+    to: DateTime<Local>,
+    now: DateTime<Local>,
+) -> Duration {
+    shifts
+        .iter()
+        .map(|s| s.overlap(from, to.min(now), now))
+        .fold(Duration::zero(), |acc, d| acc + d)
+}
+
+/// The start of the day containing `time`.
+pub fn start_of_day(time: DateTime<Local>) -> DateTime<Local> {
+    start_of_date(time.date_naive()).unwrap_or(time)
+}
+
+/// The start of `date`, or `None` if midnight was skipped by a DST transition.
+pub fn start_of_date(date: NaiveDate) -> Option<DateTime<Local>> {
+    let midnight = date
+        .and_hms_opt(0, 0, 0)
+        .expect("midnight is always a valid time");
+    Local.from_local_datetime(&midnight).earliest()
+}
+
+/// The start of the week (Monday) containing `time`.
+pub fn start_of_week(time: DateTime<Local>) -> DateTime<Local> {
+    let days_from_monday = time.weekday().num_days_from_monday();
+    start_of_day(time - Duration::days(days_from_monday as i64))
+}
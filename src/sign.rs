@@ -0,0 +1,93 @@
+// Copyright (C) 2023 Campbell M. Cole
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+// detached signatures are made by the signing tools themselves, so that the
+// keys never have to be read by punchcard and the tools can prompt for their
+// passwords as they usually do
+
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use crate::{
+    config::{read_config, SigningTool},
+    prelude::*,
+};
+
+/// Signs a file which was just exported if a key is set in the config,
+/// writing the signature next to it.
+pub fn sign_export(cli_args: &Cli, destination: &Destination) -> Result<()> {
+    let Destination::File(path) = destination else {
+        return Ok(());
+    };
+    let Some(signing) = read_config(cli_args)?.signing else {
+        return Ok(());
+    };
+
+    let (signature, mut command) = match signing.tool {
+        SigningTool::Minisign => {
+            let signature = with_suffix(path, ".minisig");
+            let mut command = Command::new("minisign");
+            command
+                .arg("-S")
+                .arg("-s")
+                .arg(&signing.key)
+                .arg("-m")
+                .arg(path)
+                .arg("-x")
+                .arg(&signature);
+            (signature, command)
+        }
+        SigningTool::Gpg => {
+            let signature = with_suffix(path, ".asc");
+            let mut command = Command::new("gpg");
+            command
+                .args(["--yes", "--armor", "--detach-sign", "--local-user"])
+                .arg(&signing.key)
+                .arg("--output")
+                .arg(&signature)
+                .arg(path);
+            (signature, command)
+        }
+    };
+
+    let tool = command.get_program().to_string_lossy().into_owned();
+    let status = command
+        .status()
+        .wrap_err_with(|| format!("Failed to run {tool}"))
+        .with_suggestion(|| format!("Make sure {tool} is installed and in your PATH"))?;
+    if !status.success() {
+        return Err(eyre!("{tool} failed to sign {} ({status})", path.display()));
+    }
+
+    {
+        use owo_colors::{DynColors, OwoColorize};
+        let gray = DynColors::Rgb(128, 128, 128);
+        println!(
+            "{} {}",
+            "Signature:".color(gray),
+            signature.display().bold()
+        );
+    }
+
+    Ok(())
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(suffix);
+    PathBuf::from(path)
+}
@@ -0,0 +1,112 @@
+// Copyright (C) 2023 Campbell M. Cole
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+// on ctrl-c the process is killed before any destructors run, so temp
+// files would be left behind and a write to the data file could be cut off
+// halfway. instead the signals are handled by a dedicated thread which
+// cleans up before exiting
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+/// Paths to remove if the process is interrupted.
+static CLEANUP: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+/// Held while the data file is being changed, so an interruption waits for the change to finish.
+static CRITICAL: Mutex<()> = Mutex::new(());
+
+/// Starts handling SIGINT and SIGTERM. Must be called before any other threads are started.
+#[cfg(unix)]
+pub fn install() {
+    use std::mem::MaybeUninit;
+
+    // SAFETY: the signal set is initialized by sigemptyset before it is used,
+    // and blocking signals in this thread is inherited by every thread started
+    // afterwards, so only the handler thread receives them. child processes
+    // are unaffected because std resets the signal mask when spawning
+    let set = unsafe {
+        let mut set = MaybeUninit::<libc::sigset_t>::uninit();
+        libc::sigemptyset(set.as_mut_ptr());
+        libc::sigaddset(set.as_mut_ptr(), libc::SIGINT);
+        libc::sigaddset(set.as_mut_ptr(), libc::SIGTERM);
+        let set = set.assume_init();
+        if libc::pthread_sigmask(libc::SIG_BLOCK, &set, std::ptr::null_mut()) != 0 {
+            warn!("failed to block signals, interruptions won't be cleaned up");
+            return;
+        }
+        set
+    };
+
+    std::thread::spawn(move || {
+        let mut signal = 0;
+        // SAFETY: both pointers are valid for the duration of the call
+        if unsafe { libc::sigwait(&set, &mut signal) } != 0 {
+            return;
+        }
+        debug!(signal, "interrupted, cleaning up");
+        // wait for a change to the data file to finish. the lock is poisoned
+        // if the change panicked, in which case there's nothing to wait for
+        let _critical = CRITICAL.lock();
+        clean_up();
+        std::process::exit(128 + signal);
+    });
+}
+
+#[cfg(not(unix))]
+pub fn install() {}
+
+fn clean_up() {
+    let paths = CLEANUP.lock().map(|p| p.clone()).unwrap_or_default();
+    for path in paths {
+        let result = if path.is_dir() {
+            std::fs::remove_dir_all(&path)
+        } else {
+            std::fs::remove_file(&path)
+        };
+        if let Err(e) = result {
+            debug!(?path, %e, "failed to clean up");
+        }
+    }
+}
+
+/// Removes the file or directory at `path` if the process is interrupted
+/// before the returned guard is dropped.
+#[must_use = "the path is only cleaned up until the guard is dropped"]
+pub fn remove_on_interrupt(path: &Path) -> CleanupGuard {
+    if let Ok(mut paths) = CLEANUP.lock() {
+        paths.push(path.to_path_buf());
+    }
+    CleanupGuard(path.to_path_buf())
+}
+
+pub struct CleanupGuard(PathBuf);
+
+impl Drop for CleanupGuard {
+    fn drop(&mut self) {
+        if let Ok(mut paths) = CLEANUP.lock() {
+            if let Some(idx) = paths.iter().rposition(|p| p == &self.0) {
+                paths.remove(idx);
+            }
+        }
+    }
+}
+
+/// Runs `f` without being interrupted part way through.
+pub fn uninterrupted<T>(f: impl FnOnce() -> T) -> T {
+    let _critical = CRITICAL.lock();
+    f()
+}
@@ -24,48 +24,279 @@ use comfy_table::{
     modifiers::{UTF8_ROUND_CORNERS, UTF8_SOLID_INNER_BORDERS},
     Cell, ColumnConstraint, ContentArrangement, Table, Width,
 };
-use polars::prelude::*;
+#[cfg(feature = "reports")]
+use polars::prelude::DataFrame;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
 use crate::{
+    config::{read_config, TableConfig},
     prelude::*,
-    table::{color::Color, style::TableStyle},
+    table::{
+        color::Color,
+        rows_from::{cell_date, RowsFrom},
+        style::TableStyle,
+    },
 };
 
 use self::settings::TableSettings;
 
 pub mod cell_alignment;
 pub mod color;
+pub mod rows_from;
 pub mod settings;
 pub mod style;
+pub mod wrap;
 
-pub struct DataFrameDisplay<'a>(&'a DataFrame, &'a TableSettings);
+/// The table settings from the config, which apply to every table.
+///
+/// A broken config is reported by the commands which need it, tables can be
+/// drawn without it.
+pub fn table_config(cli_args: &Cli) -> TableConfig {
+    read_config(cli_args)
+        .map(|config| config.table)
+        .unwrap_or_default()
+}
+
+/// The width of the terminal from $COLUMNS, which shells set but don't always export.
+fn columns() -> Option<u16> {
+    std::env::var("COLUMNS")
+        .ok()
+        .and_then(|c| c.parse().ok())
+        .filter(|&c| c > 0)
+}
+
+/// Something which can be shown as a table.
+pub trait TableSource {
+    fn height(&self) -> usize;
+    fn width(&self) -> usize;
+    /// The name of the column and the name of its data type.
+    fn column(&self, col: usize) -> (Cow<'_, str>, Cow<'_, str>);
+    fn cell(&self, row: usize, col: usize) -> Cow<'_, str>;
+}
+
+#[cfg(feature = "reports")]
+impl TableSource for DataFrame {
+    fn height(&self) -> usize {
+        DataFrame::height(self)
+    }
+
+    fn width(&self) -> usize {
+        DataFrame::width(self)
+    }
+
+    fn column(&self, col: usize) -> (Cow<'_, str>, Cow<'_, str>) {
+        let series = &self.get_columns()[col];
+        (series.name().into(), series.dtype().to_string().into())
+    }
+
+    fn cell(&self, row: usize, col: usize) -> Cow<'_, str> {
+        self.get_columns()[col].str_value(row).unwrap()
+    }
+}
+
+/// A table of cells which are already formatted, for commands which
+/// don't need a data frame to build their tables.
+#[derive(Debug, Default)]
+pub struct StringTable {
+    columns: Vec<(String, &'static str)>,
+    rows: Vec<Vec<String>>,
+}
+
+impl StringTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a column. `data_type` is only shown in the header, e.g. `str`.
+    ///
+    /// Every column must have the same number of cells.
+    pub fn with_column(
+        mut self,
+        name: impl Into<String>,
+        data_type: &'static str,
+        cells: Vec<String>,
+    ) -> Self {
+        if self.columns.is_empty() {
+            self.rows = cells.into_iter().map(|cell| vec![cell]).collect();
+        } else {
+            assert_eq!(
+                self.rows.len(),
+                cells.len(),
+                "all columns must have the same length"
+            );
+            for (row, cell) in self.rows.iter_mut().zip(cells) {
+                row.push(cell);
+            }
+        }
+        self.columns.push((name.into(), data_type));
+        self
+    }
+
+    /// Renames the column called `name`, if there is one.
+    pub fn rename_column(&mut self, name: &str, new_name: &str) {
+        if let Some((column, _)) = self.columns.iter_mut().find(|(c, _)| c == name) {
+            *column = new_name.into();
+        }
+    }
+
+    /// Keeps the rows where `mask` is true.
+    pub fn retain_rows(&mut self, mask: &[bool]) {
+        let mut mask = mask.iter();
+        self.rows.retain(|_| mask.next().copied().unwrap_or(false));
+    }
+}
+
+impl TableSource for StringTable {
+    fn height(&self) -> usize {
+        self.rows.len()
+    }
+
+    fn width(&self) -> usize {
+        self.columns.len()
+    }
+
+    fn column(&self, col: usize) -> (Cow<'_, str>, Cow<'_, str>) {
+        let (name, data_type) = &self.columns[col];
+        (name.as_str().into(), (*data_type).into())
+    }
+
+    fn cell(&self, row: usize, col: usize) -> Cow<'_, str> {
+        self.rows[row][col].as_str().into()
+    }
+}
+
+pub struct DataFrameDisplay<'a> {
+    df: &'a dyn TableSource,
+    settings: &'a TableSettings,
+    config: TableConfig,
+    accessible: bool,
+    row_colors: Vec<Option<Color>>,
+    caption: Option<String>,
+}
 
 impl<'a> DataFrameDisplay<'a> {
-    pub fn new(df: &'a DataFrame, settings: &'a TableSettings) -> Self {
-        Self(df, settings)
+    pub fn new(
+        df: &'a dyn TableSource,
+        settings: &'a TableSettings,
+        config: TableConfig,
+        accessible: bool,
+    ) -> Self {
+        Self {
+            df,
+            settings,
+            config,
+            accessible,
+            row_colors: Vec::new(),
+            caption: None,
+        }
+    }
+
+    /// Colors whole rows instead of using the column colors.
+    ///
+    /// `row_colors` is indexed by row in the full data frame. Rows without
+    /// an entry (or with `None`) keep the column colors.
+    pub fn with_row_colors(mut self, row_colors: Vec<Option<Color>>) -> Self {
+        self.row_colors = row_colors;
+        self
+    }
+
+    /// Describes what the table covers (e.g. the period of a report).
+    /// Only shown when the footer is enabled.
+    pub fn with_caption(mut self, caption: impl Into<String>) -> Self {
+        self.caption = Some(caption.into());
+        self
+    }
+
+    /// The index of the last row dated on or before `date`, using the first
+    /// column which contains dates. Falls back to the first row.
+    fn row_around(&self, date: chrono::NaiveDate) -> usize {
+        if self.df.height() == 0 {
+            return 0;
+        }
+        let Some(column) =
+            (0..self.df.width()).find(|&col| cell_date(&self.df.cell(0, col)).is_some())
+        else {
+            return 0;
+        };
+        (0..self.df.height())
+            .filter(|&i| {
+                cell_date(&self.df.cell(i, column))
+                    .map(|d| d <= date)
+                    .unwrap_or(false)
+            })
+            .last()
+            .unwrap_or(0)
+    }
+
+    fn footer(&self, shown: usize, total: usize) -> String {
+        let mut footer = format!("Showing {shown} of {total} rows");
+        if shown < total {
+            footer.push_str(&format!(
+                " ({} hidden, use '--max-n-rows all' to show them)",
+                total - shown
+            ));
+        }
+        if let Some(caption) = &self.caption {
+            footer.push_str(&format!(" · {caption}"));
+        }
+        if self.settings.no_color {
+            footer
+        } else {
+            use owo_colors::{DynColors, OwoColorize};
+            footer.color(DynColors::Rgb(128, 128, 128)).to_string()
+        }
     }
 }
 
-fn make_str_val(v: &str, truncate: usize) -> String {
-    let v_trunc = &v[..v
+/// Truncates a cell to `truncate` columns of the terminal. CJK characters and
+/// most emoji take up two columns, and combining characters take up none.
+pub(crate) fn make_str_val(v: &str, truncate: usize) -> String {
+    let mut width = 0;
+    let end = v
         .char_indices()
-        .take(truncate)
-        .last()
-        .map(|(i, c)| i + c.len_utf8())
-        .unwrap_or(0)];
-    if v == v_trunc {
+        .find(|&(_, c)| {
+            width += c.width().unwrap_or(0);
+            width > truncate
+        })
+        .map_or(v.len(), |(i, _)| i);
+    let v = if end == v.len() {
         v.to_string()
     } else {
-        format!("{v_trunc}…")
+        // a zero width joiner would join the last emoji with the ellipsis
+        format!("{}…", v[..end].trim_end_matches(ZERO_WIDTH_JOINER))
+    };
+    // without isolating them, terminals which support bidirectional text swap
+    // the cells of a row which are right-to-left, along with the borders between
+    if v.chars().any(is_rtl) {
+        format!("{FIRST_STRONG_ISOLATE}{v}{POP_DIRECTIONAL_ISOLATE}")
+    } else {
+        v
     }
 }
 
+const ZERO_WIDTH_JOINER: char = '\u{200d}';
+const FIRST_STRONG_ISOLATE: char = '\u{2068}';
+const POP_DIRECTIONAL_ISOLATE: char = '\u{2069}';
+
+/// Checks if a character is from a script which is written right-to-left,
+/// such as Hebrew or Arabic.
+fn is_rtl(c: char) -> bool {
+    matches!(c,
+        '\u{0590}'..='\u{08ff}'
+        | '\u{fb1d}'..='\u{fdff}'
+        | '\u{fe70}'..='\u{feff}'
+        | '\u{10800}'..='\u{10fff}'
+        | '\u{1e800}'..='\u{1efff}'
+    )
+}
+
 fn prepare_row(
     row: Vec<Cow<'_, str>>,
     n_first: usize,
     n_last: usize,
     str_truncate: usize,
     colors: &[Color],
+    row_color: Option<Color>,
 ) -> Vec<Cell> {
     let reduce_columns = n_first + n_last < row.len();
     let mut row_str = Vec::with_capacity(n_first + n_last + reduce_columns as usize);
@@ -81,28 +312,20 @@ fn prepare_row(
     let it = row_str.into_iter().enumerate();
     if colors.is_empty() {
         it.map(|(_, s)| Cell::new(s)).collect()
+    } else if let Some(color) = row_color {
+        it.map(|(_, s)| Cell::new(s).fg(color.into())).collect()
     } else {
-        it.map(|(x, s)| Cell::new(s).fg(colors[x].into())).collect()
+        // reuse the colors if there are more columns than colors
+        it.map(|(x, s)| Cell::new(s).fg(colors[x % colors.len()].into()))
+            .collect()
     }
 }
 
 impl<'a> Display for DataFrameDisplay<'a> {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        let mut df = self.0;
-        // we have to have this here because we can't return a reference
-        // to tdf because it's a local variable, so we need to store it
-        // somewhere and hold a reference to it, and rust doesn't realize
-        // the reference to ref_holder is being used
-        #[allow(unused_assignments)]
-        let mut ref_holder = None;
-        let settings = &self.1;
-        let default_colors = vec![
-            Color::DarkGreen,
-            Color::DarkYellow,
-            Color::DarkRed,
-            Color::DarkBlue,
-            Color::DarkCyan,
-        ];
+        let df = self.df;
+        let settings = self.settings;
+        let default_colors = crate::palette::table_colors(self.accessible);
         let column_colors = if !settings.no_color {
             settings
                 .column_colors
@@ -120,26 +343,39 @@ impl<'a> Display for DataFrameDisplay<'a> {
             Vec::new()
         };
 
+        // only the rows from `offset` to `offset + height` are shown, and
+        // the row colors are indexed by the rows of the full table
+        let full_height = df.height();
+        let mut offset = 0;
+        let mut height = full_height;
+
         if let NumRows::Some(num_rows) = &settings.max_n_rows {
-            let tdf = df.tail(Some(*num_rows));
-            ref_holder = Some(tdf);
-            df = ref_holder.as_ref().unwrap();
+            if full_height > *num_rows {
+                offset = match settings.rows_from {
+                    RowsFrom::Start => 0,
+                    RowsFrom::End => full_height - num_rows,
+                    RowsFrom::Around(date) => {
+                        let center = self.row_around(date);
+                        center
+                            .saturating_sub(num_rows / 2)
+                            .min(full_height - num_rows)
+                    }
+                };
+                height = *num_rows;
+            }
         }
 
-        let height = df.height();
-        assert!(
-            df.get_columns().iter().all(|s| s.len() == height),
-            "all columns must have the same length"
-        );
+        let row_color = |i: usize| self.row_colors.get(offset + i).copied().flatten();
+        let row = |i: usize| {
+            (0..df.width())
+                .map(|col| df.cell(offset + i, col))
+                .collect::<Vec<_>>()
+        };
 
         let max_n_cols = match settings.max_n_cols {
             NumCols::All => df.width(),
             NumCols::Some(n) => n,
         };
-        let max_n_rows = match settings.max_n_rows {
-            NumRows::All => height,
-            NumRows::Some(n) => n,
-        };
 
         let (n_first, n_last) = if df.width() > max_n_cols {
             ((max_n_cols + 1) / 2, max_n_cols / 2)
@@ -149,9 +385,9 @@ impl<'a> Display for DataFrameDisplay<'a> {
         let reduce_columns = n_first + n_last < df.width();
         let mut names = Vec::with_capacity(n_first + n_last + reduce_columns as usize);
 
-        let field_to_str = |f: &Field| {
-            let mut name = make_str_val(f.name(), settings.string_truncate);
-            let lower_bounds = name.len().clamp(5, 12);
+        let field_to_str = |(name, data_type): (Cow<'_, str>, Cow<'_, str>)| {
+            let mut name = make_str_val(&name, settings.string_truncate);
+            let lower_bounds = name.width().clamp(5, 12);
 
             if settings.hide_column_names {
                 name = String::new();
@@ -160,9 +396,9 @@ impl<'a> Display for DataFrameDisplay<'a> {
             let column_data_type = if settings.hide_data_types {
                 String::new()
             } else if settings.inline_data_types | settings.hide_column_names {
-                format!("{}", f.data_type())
+                format!("{data_type}")
             } else {
-                format!("\n{}", f.data_type())
+                format!("\n{data_type}")
             };
             let mut column_separator = "\n---";
             if settings.hide_column_separator
@@ -181,10 +417,8 @@ impl<'a> Display for DataFrameDisplay<'a> {
         let tbl_lower_bounds = |l: usize| ColumnConstraint::LowerBoundary(Width::Fixed(l as u16));
 
         let mut constraints = Vec::with_capacity(n_first + n_last + reduce_columns as usize);
-        let fields = df.fields();
-
-        for field in fields[0..n_first].iter() {
-            let (s, l) = field_to_str(field);
+        for col in 0..n_first {
+            let (s, l) = field_to_str(df.column(col));
             names.push(s);
             constraints.push(tbl_lower_bounds(l));
         }
@@ -192,16 +426,19 @@ impl<'a> Display for DataFrameDisplay<'a> {
             names.push("…".into());
             constraints.push(tbl_lower_bounds(3));
         }
-        for field in fields[df.width() - n_last..].iter() {
-            let (s, l) = field_to_str(field);
+        for col in df.width() - n_last..df.width() {
+            let (s, l) = field_to_str(df.column(col));
             names.push(s);
             constraints.push(tbl_lower_bounds(l));
         }
 
         let mut table = Table::new();
         table
-            .load_preset(settings.style.get_style())
-            .set_content_arrangement(ContentArrangement::Dynamic);
+            .load_preset(settings.style.get_style(self.config.border.as_deref()))
+            .set_content_arrangement(match settings.fit_content {
+                true => ContentArrangement::Disabled,
+                false => settings.wrap.get(),
+            });
 
         if settings.rounded_corners && settings.style.is_utf8() {
             table.apply_modifier(UTF8_ROUND_CORNERS);
@@ -211,65 +448,19 @@ impl<'a> Display for DataFrameDisplay<'a> {
             table.apply_modifier(UTF8_SOLID_INNER_BORDERS);
         }
 
-        if max_n_rows > 0 {
-            if height > max_n_rows + 1 {
-                let mut rows = Vec::with_capacity(std::cmp::max(max_n_rows, 2));
-                for i in 0..std::cmp::max(max_n_rows / 2, 1) {
-                    let row = df
-                        .get_columns()
-                        .iter()
-                        .map(|s| s.str_value(i).unwrap())
-                        .collect();
-                    rows.push(prepare_row(
-                        row,
-                        n_first,
-                        n_last,
-                        settings.string_truncate,
-                        &column_colors,
-                    ));
-                }
-                let dots = rows[0].iter().map(|_| Cell::new("…")).collect();
-                rows.push(dots);
-                if max_n_rows > 1 {
-                    for i in (height - (max_n_rows + 1) / 2)..height {
-                        let row = df
-                            .get_columns()
-                            .iter()
-                            .map(|s| s.str_value(i).unwrap())
-                            .collect();
-                        rows.push(prepare_row(
-                            row,
-                            n_first,
-                            n_last,
-                            settings.string_truncate,
-                            &column_colors,
-                        ));
-                    }
-                }
-                table.add_rows(rows);
-            } else {
-                for i in 0..height {
-                    if df.width() > 0 {
-                        let row = df
-                            .get_columns()
-                            .iter()
-                            .map(|s| s.str_value(i).unwrap())
-                            .collect();
-                        table.add_row(prepare_row(
-                            row,
-                            n_first,
-                            n_last,
-                            settings.string_truncate,
-                            &column_colors,
-                        ));
-                    } else {
-                        break;
-                    }
-                }
+        // the rows which don't fit in '--max-n-rows' were already left out, and
+        // a table without columns has no cells to put the rows in
+        if df.width() > 0 {
+            for i in 0..height {
+                table.add_row(prepare_row(
+                    row(i),
+                    n_first,
+                    n_last,
+                    settings.string_truncate,
+                    &column_colors,
+                    row_color(i),
+                ));
             }
-        } else if height > 0 {
-            let dots: Vec<String> = df.get_columns().iter().map(|_| "…".to_string()).collect();
-            table.add_row(dots);
         }
 
         if !(settings.hide_column_names && settings.hide_data_types) {
@@ -287,7 +478,7 @@ impl<'a> Display for DataFrameDisplay<'a> {
 
         if matches!(settings.style, TableStyle::AsciiMarkdown) {
             table.set_width(u16::MAX);
-        } else if let Some(w) = settings.width {
+        } else if let Some(w) = settings.width.or(self.config.width).or_else(columns) {
             table.set_width(w);
         } else if !table.is_tty() {
             table.set_width(100);
@@ -299,6 +490,10 @@ impl<'a> Display for DataFrameDisplay<'a> {
 
         write!(f, "{table}")?;
 
+        if settings.footer {
+            write!(f, "\n{}", self.footer(height, full_height))?;
+        }
+
         Ok(())
     }
 }
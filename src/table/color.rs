@@ -16,6 +16,7 @@
 use std::{fmt::Display, str::FromStr};
 
 use owo_colors::DynColors;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 use crate::prelude::*;
 
@@ -128,6 +129,18 @@ impl FromStr for Color {
             }
         } else if let Ok(num) = s.parse::<u8>() {
             Ok(Color::AnsiValue(num))
+        } else if let Some(rgb) = s.strip_prefix("rgb(").and_then(|s| s.strip_suffix(')')) {
+            // the same format as `Display` so colors can be round-tripped
+            let [r, g, b] = rgb
+                .split(',')
+                .map(|c| c.trim().parse::<u8>())
+                .collect::<std::result::Result<Vec<_>, _>>()?[..]
+            else {
+                return Err(eyre!("Invalid rgb color: {}", s));
+            };
+            Ok(Color::Rgb { r, g, b })
+        } else if let Some(num) = s.strip_prefix("ansi(").and_then(|s| s.strip_suffix(')')) {
+            Ok(Color::AnsiValue(num.trim().parse()?))
         } else {
             match s.to_ascii_lowercase().as_str() {
                 "reset" => Ok(Color::Reset),
@@ -152,3 +165,16 @@ impl FromStr for Color {
         }
     }
 }
+
+impl Serialize for Color {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Color {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
@@ -0,0 +1,79 @@
+// Copyright (C) 2023 Campbell M. Cole
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::{fmt::Display, str::FromStr};
+
+use chrono::NaiveDate;
+use thiserror::Error;
+
+/// Which rows to show when a table has more rows than '--max-n-rows'.
+#[cfg_attr(test, derive(PartialEq))]
+#[derive(Debug, Clone, Copy, Default)]
+pub enum RowsFrom {
+    Start,
+    #[default]
+    End,
+    /// The rows surrounding the row for this date
+    Around(NaiveDate),
+}
+
+#[cfg_attr(test, derive(PartialEq))]
+#[derive(Debug, Error)]
+pub enum RowsFromError {
+    #[error("Invalid date '{0}', expected a date like 2024-03-01")]
+    InvalidDate(String),
+    #[error("Unknown value '{0}'. Must be \"start\", \"end\", or \"around <date>\"")]
+    Unknown(String),
+}
+
+impl FromStr for RowsFrom {
+    type Err = RowsFromError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        match s.to_ascii_lowercase().as_str() {
+            "start" => return Ok(RowsFrom::Start),
+            "end" => return Ok(RowsFrom::End),
+            _ => {}
+        }
+        let Some(date) = s
+            .strip_prefix("around")
+            .map(|date| date.trim_start_matches([' ', ':', '=']))
+        else {
+            return Err(RowsFromError::Unknown(s.into()));
+        };
+        NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map(RowsFrom::Around)
+            .map_err(|_| RowsFromError::InvalidDate(date.into()))
+    }
+}
+
+impl Display for RowsFrom {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RowsFrom::Start => write!(f, "start"),
+            RowsFrom::End => write!(f, "end"),
+            RowsFrom::Around(date) => write!(f, "around {}", date.format("%Y-%m-%d")),
+        }
+    }
+}
+
+/// Reads the date out of a table cell, e.g. `05 October 2026`
+/// or `09:00:00 AM 05 October 2026`.
+pub fn cell_date(cell: &str) -> Option<NaiveDate> {
+    let words = cell.split_whitespace().collect::<Vec<_>>();
+    let date = words.get(words.len().checked_sub(3)?..)?.join(" ");
+    NaiveDate::parse_from_str(&date, "%d %B %Y").ok()
+}
@@ -17,11 +17,14 @@ use clap::{ArgAction, Args};
 
 use crate::prelude::{NumCols, NumRows};
 
-use super::{cell_alignment::CellAlignment, color::Color, style::TableStyle};
+use super::{
+    cell_alignment::CellAlignment, color::Color, rows_from::RowsFrom, style::TableStyle, wrap::Wrap,
+};
 
 #[derive(Debug, Clone, Args)]
 pub struct TableSettings {
-    /// The maximum number of characters to display in a string column.
+    /// The maximum width of a string column, in terminal columns. CJK characters
+    /// and most emoji are two columns wide.
     #[clap(short = 't', long, default_value_t = 32)]
     pub string_truncate: usize,
     /// The maximum number of columns to display (or 'all').
@@ -30,6 +33,10 @@ pub struct TableSettings {
     /// The maximum number of rows to display (or 'all').
     #[clap(short = 'r', long, default_value_t = NumRows::Some(10))]
     pub max_n_rows: NumRows,
+    /// Which rows to show when there are more than '--max-n-rows':
+    /// 'start', 'end', or 'around <date>' (e.g. 'around 2024-03-01').
+    #[clap(long, default_value_t = RowsFrom::End)]
+    pub rows_from: RowsFrom,
     /// Hide the column names.
     #[clap(short = 'n', long, default_value_t = false)]
     pub hide_column_names: bool,
@@ -54,9 +61,16 @@ pub struct TableSettings {
     /// Text alignment within cells.
     #[clap(short = 'a', long, value_enum, default_value_t = CellAlignment::Center)]
     pub cell_alignment: CellAlignment,
-    /// The maximum width of the table (defaults to TTY width)
+    /// The maximum width of the table. Defaults to `table.width` in the config,
+    /// then $COLUMNS, then the width of the terminal, or 100 without one
     #[clap(short = 'w', long, default_value = None)]
     pub width: Option<u16>,
+    /// Never wrap the cells, so the table is as wide as its content
+    #[clap(long, default_value_t = false, conflicts_with_all = ["width", "wrap"])]
+    pub fit_content: bool,
+    /// How the cells wrap to fit the width of the table
+    #[clap(long, value_enum, default_value_t = Wrap::Dynamic)]
+    pub wrap: Wrap,
     /// The color of the header cells on the table
     #[clap(long, default_value_t = Color::DarkMagenta)]
     pub header_color: Color,
@@ -66,4 +80,7 @@ pub struct TableSettings {
     /// Completely disable emitting ANSI escape codes. Useful for piping to other programs. Enabled automatically for copyable reports.
     #[clap(long, action = ArgAction::SetTrue)]
     pub no_color: bool,
+    /// Show a footer below the table with the number of rows shown and what the table covers.
+    #[clap(long, default_value_t = false)]
+    pub footer: bool,
 }
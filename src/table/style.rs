@@ -32,10 +32,13 @@ pub enum TableStyle {
     Utf8BordersOnly,
     Utf8HorizontalOnly,
     Nothing,
+    /// The characters of `table.border` in the config, or `ascii-full` without them
+    Custom,
 }
 
 impl TableStyle {
-    pub fn get_style(&self) -> &'static str {
+    /// The comfy-table preset of the style. `border` is used for the custom style.
+    pub fn get_style<'a>(&self, border: Option<&'a str>) -> &'a str {
         use comfy_table::presets::*;
         match self {
             TableStyle::AsciiFull => ASCII_FULL,
@@ -51,6 +54,7 @@ impl TableStyle {
             TableStyle::Utf8BordersOnly => UTF8_BORDERS_ONLY,
             TableStyle::Utf8HorizontalOnly => UTF8_HORIZONTAL_ONLY,
             TableStyle::Nothing => NOTHING,
+            TableStyle::Custom => border.unwrap_or(ASCII_FULL),
         }
     }
 
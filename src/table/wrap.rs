@@ -0,0 +1,38 @@
+// Copyright (C) 2023 Campbell M. Cole
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use clap::ValueEnum;
+use comfy_table::ContentArrangement;
+
+// the ways comfy_table can fit a table to the width, with ValueEnum so that
+// it can be used in clap. not wrapping at all is '--fit-content'
+
+#[derive(Debug, Clone, Copy, Default, ValueEnum)]
+pub enum Wrap {
+    /// Wrap the cells as much as needed to fit the width
+    #[default]
+    Dynamic,
+    /// Wrap the cells to fit the width, and stretch the table to all of it
+    FullWidth,
+}
+
+impl Wrap {
+    pub fn get(&self) -> ContentArrangement {
+        match self {
+            Wrap::Dynamic => ContentArrangement::Dynamic,
+            Wrap::FullWidth => ContentArrangement::DynamicFullWidth,
+        }
+    }
+}
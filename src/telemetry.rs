@@ -0,0 +1,52 @@
+// Copyright (C) 2023 Campbell M. Cole
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+// only built with the `otel` feature. the spans are the same ones written to
+// the log file, so anything with #[instrument] is exported
+
+use opentelemetry::KeyValue;
+use opentelemetry_sdk::{trace::config, Resource};
+use tracing_subscriber::{Layer, Registry};
+
+/// A layer which exports spans over OTLP, if `OTEL_EXPORTER_OTLP_ENDPOINT` is set.
+///
+/// Spans are exported as soon as they close, so nothing is lost when the
+/// process exits right after the command finishes.
+pub fn otlp_layer() -> Option<Box<dyn Layer<Registry> + Send + Sync>> {
+    use owo_colors::OwoColorize;
+
+    std::env::var_os("OTEL_EXPORTER_OTLP_ENDPOINT")?;
+
+    let tracer = opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(opentelemetry_otlp::new_exporter().http())
+        .with_trace_config(
+            config().with_resource(Resource::new([KeyValue::new("service.name", "punchcard")])),
+        )
+        .install_simple();
+
+    match tracer {
+        Ok(tracer) => Some(tracing_opentelemetry::layer().with_tracer(tracer).boxed()),
+        Err(err) => {
+            eprintln!("{} {err}", "Failed to set up the OTLP exporter:".yellow());
+            None
+        }
+    }
+}
+
+/// Waits for the spans which haven't been exported yet.
+pub fn shutdown() {
+    opentelemetry::global::shutdown_tracer_provider();
+}
@@ -17,8 +17,19 @@ use std::path::PathBuf;
 
 use chrono::Duration;
 
+use crate::command::absence::{absence_marker, Absence, AbsenceKind};
+use crate::config::parse_config;
+use crate::csv::EntrySource;
+use crate::table::{
+    color::Color,
+    make_str_val,
+    rows_from::{cell_date, RowsFrom, RowsFromError},
+};
 use crate::types::{
-    BiDuration, BiDurationParseError, Destination, Month, ParseMonthError, Quantity, QuantityError,
+    BiDuration, BiDurationParseError, ColumnLabel, ColumnLabelError, CompareOp, Correction,
+    CorrectionError, DateRange, DateRangeError, Destination, FilterExpr, FilterExprError, Month,
+    ParseMonthError, ParseWeekError, PunchcardUrl, PunchcardUrlError, Quantity, QuantityError,
+    SortKey, SortKeyError, Tags, Week,
 };
 
 #[test]
@@ -36,6 +47,25 @@ fn test_parse_biduration() {
     }
 }
 
+#[test]
+fn test_parse_correction() {
+    let cases = [
+        ("+15m", Ok(Correction::Shift(Duration::minutes(15)))),
+        ("-1h 30m", Ok(Correction::Shift(-Duration::minutes(90)))),
+        (
+            "14:30",
+            Ok(Correction::TimeOfDay(
+                chrono::NaiveTime::from_hms_opt(14, 30, 0).unwrap(),
+            )),
+        ),
+        ("later", Err(CorrectionError::Unknown("later".into()))),
+    ];
+
+    for (input, expected) in cases {
+        assert_eq!(input.parse::<Correction>(), expected);
+    }
+}
+
 #[test]
 fn test_format_biduration() {
     // the output format always contains `in` or `ago`
@@ -78,6 +108,21 @@ fn test_format_biduration_hours() {
     }
 }
 
+#[test]
+fn test_format_biduration_compact() {
+    let cases = [
+        (Duration::minutes(134), "2h14m"),
+        (Duration::minutes(-65), "1h05m"),
+        (Duration::hours(8), "8h"),
+        (Duration::seconds(29), "0m"),
+        (Duration::seconds(90), "2m"),
+    ];
+
+    for (input, expected) in cases {
+        assert_eq!(BiDuration::new(input).to_compact_string(), expected);
+    }
+}
+
 #[test]
 fn test_parse_num_rows() {
     let cases = [
@@ -120,3 +165,899 @@ fn test_parse_month() {
         assert_eq!(input.parse::<Month>(), expected);
     }
 }
+
+#[test]
+fn test_parse_week() {
+    let monday = chrono::NaiveDate::from_ymd_opt(2024, 3, 4).unwrap();
+    let cases = [
+        ("last", Ok(Week::Previous)),
+        ("Current", Ok(Week::Current)),
+        ("2024-03-07", Ok(Week::Of(monday + Duration::days(3)))),
+        (
+            "tomorrow",
+            Err(ParseWeekError::UnknownWeek("tomorrow".into())),
+        ),
+    ];
+
+    for (input, expected) in cases {
+        assert_eq!(input.parse::<Week>(), expected);
+    }
+    assert_eq!(
+        Week::Previous.monday(monday + Duration::days(6)),
+        monday - Duration::weeks(1)
+    );
+    assert_eq!(Week::Of(monday + Duration::days(3)).monday(monday), monday);
+}
+
+#[test]
+fn test_parse_date_range() {
+    let date = |day| chrono::NaiveDate::from_ymd_opt(2024, 7, day).unwrap();
+    let cases = [
+        (
+            "2024-07-01..2024-07-14",
+            Ok(DateRange {
+                from: date(1),
+                to: date(14),
+            }),
+        ),
+        (
+            "2024-07-03",
+            Ok(DateRange {
+                from: date(3),
+                to: date(3),
+            }),
+        ),
+        ("2024-07-14..2024-07-01", Err(DateRangeError::Backwards)),
+        (
+            "2024-07-01..soon",
+            Err(DateRangeError::InvalidDate("soon".into())),
+        ),
+    ];
+
+    for (input, expected) in cases {
+        assert_eq!(input.parse::<DateRange>(), expected);
+    }
+}
+
+#[test]
+fn test_absence_marker() {
+    let date = |day| chrono::NaiveDate::from_ymd_opt(2024, 7, day).unwrap();
+    let absences = [
+        Absence {
+            days: "2024-07-01..2024-07-03".parse().unwrap(),
+            kind: AbsenceKind::Vacation,
+        },
+        Absence {
+            days: "2024-07-05".parse().unwrap(),
+            kind: AbsenceKind::Sick,
+        },
+    ];
+    // the periods end before the day given as their end
+    let cases = [
+        ((date(1), date(2)), Some(" (vacation)")),
+        ((date(1), date(8)), Some(" (vacation, sick)")),
+        ((date(4), date(5)), None),
+        ((date(8), date(15)), None),
+    ];
+
+    for ((from, to), expected) in cases {
+        assert_eq!(
+            absence_marker(&absences, from, to).as_deref(),
+            expected,
+            "{from}..{to}"
+        );
+    }
+}
+
+#[test]
+fn test_serde_tags() {
+    let cases = [
+        ("\"\"", Tags(vec![])),
+        ("\"meeting\"", Tags(vec!["meeting".into()])),
+        (
+            "\"meeting;standup\"",
+            Tags(vec!["meeting".into(), "standup".into()]),
+        ),
+    ];
+
+    for (input, expected) in cases {
+        let tags = serde_json::from_str::<Tags>(input).unwrap();
+        assert_eq!(tags, expected);
+        assert_eq!(serde_json::to_string(&tags).unwrap(), input);
+    }
+}
+
+#[test]
+fn test_serde_biduration() {
+    for input in ["30m", "1h 30m ago"] {
+        let duration = input.parse::<BiDuration>().unwrap();
+        let json = serde_json::to_string(&duration).unwrap();
+        assert_eq!(serde_json::from_str::<BiDuration>(&json).unwrap(), duration);
+    }
+}
+
+#[test]
+fn test_parse_entry_source() {
+    let cases = [
+        ("", EntrySource::Cli),
+        ("cli", EntrySource::Cli),
+        ("template", EntrySource::Template),
+        ("recur", EntrySource::Recurring),
+        ("auto-clockout", EntrySource::AutoClockOut),
+        ("batch", EntrySource::Batch),
+        ("kiosk", EntrySource::Kiosk),
+        ("backfill", EntrySource::Backfill),
+        ("import:toggl", EntrySource::Other("import:toggl".into())),
+    ];
+
+    for (input, expected) in cases {
+        let source = input.parse::<EntrySource>().unwrap();
+        assert_eq!(source, expected);
+        if !input.is_empty() {
+            assert_eq!(source.to_string(), input);
+        }
+    }
+}
+
+#[test]
+fn test_parse_punchcard_url() {
+    let url = |action: &str, params: &[(&str, &str)]| PunchcardUrl {
+        action: action.to_string(),
+        params: params
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect(),
+    };
+    let cases = [
+        ("punchcard://toggle", Ok(url("toggle", &[]))),
+        ("PUNCHCARD://Out/", Ok(url("out", &[]))),
+        (
+            "punchcard://in?project=acme%20corp&tag=a&tag=b+c",
+            Ok(url(
+                "in",
+                &[("project", "acme corp"), ("tag", "a"), ("tag", "b c")],
+            )),
+        ),
+        (
+            "punchcard://toggle?notify",
+            Ok(url("toggle", &[("notify", "")])),
+        ),
+        ("https://in", Err(PunchcardUrlError::WrongScheme)),
+        (
+            "punchcard://?project=acme",
+            Err(PunchcardUrlError::MissingAction),
+        ),
+        (
+            "punchcard://in?project=%FF",
+            Err(PunchcardUrlError::InvalidEncoding),
+        ),
+    ];
+
+    for (input, expected) in cases {
+        assert_eq!(input.parse::<PunchcardUrl>(), expected);
+    }
+}
+
+#[test]
+fn test_color_round_trip() {
+    let cases = [
+        ("darkgreen", "darkgreen"),
+        ("#ff8800", "rgb(255,136,0)"),
+        ("rgb(1, 2, 3)", "rgb(1,2,3)"),
+        ("42", "ansi(42)"),
+        ("ansi(42)", "ansi(42)"),
+    ];
+
+    for (input, expected) in cases {
+        let color = input.parse::<Color>().unwrap();
+        assert_eq!(color.to_string(), expected);
+        assert_eq!(expected.parse::<Color>().unwrap().to_string(), expected);
+    }
+}
+
+#[test]
+fn test_parse_rows_from() {
+    let date = chrono::NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+    let cases = [
+        ("start", Ok(RowsFrom::Start)),
+        ("END", Ok(RowsFrom::End)),
+        ("around 2024-03-01", Ok(RowsFrom::Around(date))),
+        ("around:2024-03-01", Ok(RowsFrom::Around(date))),
+        (
+            "around March",
+            Err(RowsFromError::InvalidDate("March".into())),
+        ),
+        ("middle", Err(RowsFromError::Unknown("middle".into()))),
+    ];
+
+    for (input, expected) in cases {
+        assert_eq!(input.parse::<RowsFrom>(), expected);
+    }
+
+    assert_eq!(cell_date("01 March 2024"), Some(date));
+    assert_eq!(cell_date("09:00:00 AM 01 March 2024"), Some(date));
+    assert_eq!(cell_date("8 hours"), None);
+}
+
+#[test]
+fn test_truncate_cells() {
+    // cells are truncated to a number of terminal columns, not characters
+    let cases = [
+        ("acme", 4, "acme"),
+        ("acme corp", 4, "acme…"),
+        ("日本語の名前", 6, "日本語…"),
+        ("日本語の名前", 5, "日本…"),
+        ("café\u{301} bar", 4, "café\u{301}…"),
+        ("🎉🎉 party", 3, "🎉…"),
+        ("👨\u{200d}👩\u{200d}👧", 3, "👨…"),
+        // right-to-left text is isolated from the rest of the row
+        ("שלום", 4, "\u{2068}שלום\u{2069}"),
+    ];
+
+    for (input, truncate, expected) in cases {
+        assert_eq!(make_str_val(input, truncate), expected);
+    }
+}
+
+#[test]
+fn test_render_wide_text() {
+    use clap::{Parser, ValueEnum};
+
+    use crate::table::{settings::TableSettings, style::TableStyle, DataFrameDisplay, StringTable};
+
+    #[derive(Parser)]
+    struct Args {
+        #[command(flatten)]
+        table: TableSettings,
+    }
+
+    // right-to-left text, combining characters, and emoji (some of them joined
+    // into one) are all measured differently than plain ASCII
+    let table = StringTable::new()
+        .with_column(
+            "Project",
+            "str",
+            vec![
+                "acme".into(),
+                "日本語のプロジェクト".into(),
+                "פרויקט".into(),
+                "مشروع جديد للعميل".into(),
+                "cafe\u{301}".into(),
+                "🎉 launch".into(),
+            ],
+        )
+        .with_column(
+            "Note",
+            "str",
+            vec![
+                "fixed the build".into(),
+                "会議".into(),
+                "תיקון באג 42".into(),
+                "اجتماع".into(),
+                "re\u{301}sume\u{301} review".into(),
+                "👩\u{200d}💻 pairing with 👨\u{200d}👩\u{200d}👧".into(),
+            ],
+        )
+        .with_column(
+            "Hours",
+            "str",
+            ["2h", "1h 30m", "45m", "3h", "20m", "4h"]
+                .map(String::from)
+                .to_vec(),
+        );
+
+    let config = crate::config::TableConfig {
+        border: Some("##==#-##:.#########".into()),
+        width: None,
+    };
+    for style in TableStyle::value_variants() {
+        let name = style.to_possible_value().unwrap().get_name().to_string();
+        let args = Args::parse_from([
+            "punchcard",
+            "--no-color",
+            "-w",
+            "80",
+            "-t",
+            "12",
+            "-s",
+            &name,
+        ]);
+        let rendered =
+            DataFrameDisplay::new(&table, &args.table, config.clone(), false).to_string();
+        insta::assert_snapshot!(format!("table_{name}"), rendered);
+    }
+}
+
+#[test]
+fn test_parse_sort_key() {
+    let key = |column: &str, descending| SortKey {
+        column: column.into(),
+        descending,
+    };
+    let cases = [
+        ("Total Hours", Ok(key("Total Hours", false))),
+        ("Total Hours:desc", Ok(key("Total Hours", true))),
+        ("week of:ASC", Ok(key("week of", false))),
+        (
+            "Total Hours:up",
+            Err(SortKeyError::UnknownDirection("up".into())),
+        ),
+        (":desc", Err(SortKeyError::MissingColumn)),
+    ];
+
+    for (input, expected) in cases {
+        assert_eq!(input.parse::<SortKey>(), expected);
+    }
+}
+
+#[test]
+fn test_parse_column_label() {
+    let label = |column: &str, label: &str| ColumnLabel {
+        column: column.into(),
+        label: label.into(),
+    };
+    let cases = [
+        (
+            "Total Hours=Gesamtstunden",
+            Ok(label("Total Hours", "Gesamtstunden")),
+        ),
+        (" Week Of = Woche ab ", Ok(label("Week Of", "Woche ab"))),
+        ("Total Hours", Err(ColumnLabelError::MissingEquals)),
+        ("=Gesamtstunden", Err(ColumnLabelError::MissingColumn)),
+        ("Total Hours= ", Err(ColumnLabelError::MissingLabel)),
+    ];
+
+    for (input, expected) in cases {
+        assert_eq!(input.parse::<ColumnLabel>(), expected);
+    }
+}
+
+#[test]
+fn test_parse_filter_expr() {
+    let filter = |column: &str, op, value: &str| FilterExpr {
+        column: column.into(),
+        op,
+        value: value.into(),
+    };
+    let cases = [
+        (
+            "Total Hours > 35h",
+            Ok(filter("Total Hours", CompareOp::Gt, "35h")),
+        ),
+        (
+            "Number of Shifts>=2",
+            Ok(filter("Number of Shifts", CompareOp::GtEq, "2")),
+        ),
+        (
+            "Week Of == 2024-03-04",
+            Ok(filter("Week Of", CompareOp::Eq, "2024-03-04")),
+        ),
+        ("Total Hours", Err(FilterExprError::MissingOperator)),
+        (
+            "!= 3",
+            Err(FilterExprError::MissingColumn(CompareOp::NotEq)),
+        ),
+        (
+            "Total Hours <",
+            Err(FilterExprError::MissingValue(CompareOp::Lt)),
+        ),
+    ];
+
+    for (input, expected) in cases {
+        assert_eq!(input.parse::<FilterExpr>(), expected);
+    }
+}
+
+#[test]
+fn test_parse_config_errors() {
+    let cases = [
+        ("budget-alerts = [80]", None),
+        (
+            "budget-alert = [80]",
+            Some("line 1, column 1, at `budget-alert`: unknown field `budget-alert`, expected one of `schedule`, `weekly-summary`, `budget-alerts`, `log-file`, `signing`, `shell-hook`, `location`, `record-device`, `note-prompt`, `narrative`, `browser`, `labels`, `table`, `aliases` (did you mean `budget-alerts`?)"),
+        ),
+        (
+            "[log-file]\nrotation = \"dayly\"",
+            Some("line 2, column 12, at `log-file.rotation`: unknown variant `dayly`, expected one of `hourly`, `daily`, `never` (did you mean `daily`?)"),
+        ),
+        (
+            "[schedule]\ndays = [\"mon\"]\nstart = \"9am\"\nend = \"17:00\"",
+            Some("line 3, column 9, at `schedule.start`: invalid time of day `9am`, expected e.g. `17:00`"),
+        ),
+        (
+            "[schedule",
+            Some("line 1, column 10: invalid table header, expected `.`, `]`"),
+        ),
+    ];
+
+    for (input, expected) in cases {
+        assert_eq!(parse_config(input).err().as_deref(), expected);
+    }
+}
+
+#[test]
+fn test_recur_collides() {
+    use chrono::{Local, TimeZone};
+
+    use crate::command::recur::collides;
+    use crate::csv::{Entry, EntryType};
+
+    let at = |hour| Local.with_ymd_and_hms(2024, 1, 8, hour, 0, 0).unwrap();
+    let entries = [
+        Entry::new(EntryType::ClockIn, at(9), EntrySource::Recurring),
+        Entry::new(EntryType::ClockOut, at(12), EntrySource::Recurring),
+    ];
+
+    let cases = [
+        ((6, 9), false),
+        ((12, 15), false),
+        ((13, 15), false),
+        ((8, 10), true),
+        ((10, 11), true),
+        ((11, 13), true),
+        ((9, 12), true),
+        ((8, 13), true),
+    ];
+
+    for ((start, end), expected) in cases {
+        assert_eq!(
+            collides(&entries, at(start), at(end)),
+            expected,
+            "{start}-{end}"
+        );
+    }
+}
+
+#[test]
+fn test_alias_expand() {
+    use std::ffi::OsString;
+
+    let data_folder = temp_dir::TempDir::new().unwrap();
+    std::fs::write(
+        data_folder.path().join("config.toml"),
+        "[aliases]\nlunch = \"out --note 'lunch break'\"\n",
+    )
+    .unwrap();
+    let folder = data_folder.path().to_str().unwrap();
+
+    let (attached, long) = (format!("-d{folder}"), format!("--data-folder={folder}"));
+    let expanded = ["out", "--note", "lunch break"];
+    let cases = [
+        (vec!["-d", folder], true),
+        (vec![&attached[..]], true),
+        (vec![&long[..]], true),
+        (vec!["--data-folder", folder], true),
+        (vec![], false),
+    ];
+
+    for (options, is_expanded) in cases {
+        let args = [&["punchcard"], &options[..], &["lunch", "-o", "5m"]].concat();
+        let expected = match is_expanded {
+            true => [&["punchcard"], &options[..], &expanded, &["-o", "5m"]].concat(),
+            false => args.clone(),
+        };
+        let args = args.into_iter().map(OsString::from).collect();
+        let expected: Vec<OsString> = expected.into_iter().map(OsString::from).collect();
+        assert_eq!(crate::alias::expand(args), expected, "{options:?}");
+    }
+}
+
+#[test]
+fn test_config_set_keeps_comments() {
+    use clap::Parser;
+
+    use crate::command::config::manage_config;
+    use crate::{Cli, Operation};
+
+    const CONFIG: &str = "\
+record-device = true
+
+# when I'm usually at work
+[schedule]
+days = [\"mon\", \"tue\"] # not on fridays
+start = \"09:00\" # the bus is late anyway
+end = \"17:00\"
+";
+
+    let cases = [
+        (
+            "set schedule.start 08:30",
+            CONFIG.replace("\"09:00\"", "\"08:30\""),
+        ),
+        (
+            "set budget-alerts [50,100]",
+            // settings outside of a table have to come before the tables
+            CONFIG.replace("true\n", "true\nbudget-alerts = [50,100]\n"),
+        ),
+        (
+            "unset record-device",
+            CONFIG.replace("record-device = true\n", ""),
+        ),
+    ];
+
+    for (args, expected) in cases {
+        let data_folder = temp_dir::TempDir::new().unwrap();
+        let config_file = data_folder.path().join("config.toml");
+        std::fs::write(&config_file, CONFIG).unwrap();
+        let folder = data_folder.path().to_str().unwrap();
+        let args = format!("punchcard -d {folder} config {args}");
+        let cli_args = Cli::parse_from(args.split(' '));
+        let Operation::Config(config_args) = &cli_args.operation else {
+            unreachable!("the arguments are a config command");
+        };
+        manage_config(&cli_args, config_args).unwrap();
+
+        let contents = std::fs::read_to_string(config_file).unwrap();
+        assert_eq!(contents, expected, "{args}");
+    }
+}
+
+#[cfg(feature = "reports")]
+#[test]
+fn test_report_engines_agree() {
+    use chrono::{Datelike, Local};
+    use clap::Parser;
+
+    use crate::command::report::{build_report, parse_report_args};
+    use crate::Cli;
+
+    const HOURS: &str = "\
+entry_type,timestamp,project,tags,source
+in,2024-02-26T22:30:00.000000000+0000,alpha,a;b,cli
+out,2024-02-27T03:15:30.000000000+0000,,,cli
+in,2024-02-28T13:00:00.000000000+0000,beta,,template
+out,2024-03-01T01:20:00.000000000+0000,,,auto-clockout
+in,2024-03-03T04:00:00.000000000+0000,,b,cli
+out,2024-03-03T06:45:00.000000000+0000,alpha,,cli
+in,2024-03-10T06:30:00.000000000+0000,beta,a,cli
+out,2024-03-10T08:30:00.000000000+0000,,,cli
+in,2024-04-01T03:59:00.000000000+0000,,,cli
+out,2024-04-01T04:01:00.000000000+0000,,,cli
+";
+
+    let data_folder = temp_dir::TempDir::new().unwrap();
+    // the months of a report are always in the current year
+    let year = Local::now().year().to_string();
+    std::fs::write(
+        data_folder.path().join("hours.csv"),
+        HOURS.replace("2024", &year),
+    )
+    .unwrap();
+
+    let cases = [
+        "weekly -m all",
+        "weekly -m march",
+        "weekly -m march -s",
+        "-g day weekly -m march -s",
+        "-g month -g project weekly -m all",
+        "-g tag -g week weekly -m all",
+        "-g project --source template weekly -m all",
+        "-g day --project alpha -e weekly -m all",
+    ];
+
+    for timezone in ["UTC", "America/New_York", "Asia/Kolkata"] {
+        let cli_args = Cli::parse_from([
+            "punchcard",
+            "-d",
+            data_folder.path().to_str().unwrap(),
+            "-t",
+            timezone,
+            "status",
+        ]);
+        for case in cases {
+            let build = |engine: &str| {
+                let args = format!("--engine {engine} {case}");
+                let args = args.split(' ').map(String::from).collect::<Vec<_>>();
+                build_report(&cli_args, &parse_report_args(&args).unwrap()).unwrap()
+            };
+            let (polars, simple) = (build("polars"), build("simple"));
+            let (polars, simple) = (polars.table(), simple.table());
+
+            assert!(polars.height() > 0, "{timezone}: {case}");
+            assert_eq!(polars.height(), simple.height(), "{timezone}: {case}");
+            assert_eq!(polars.width(), simple.width(), "{timezone}: {case}");
+            for col in 0..polars.width() {
+                assert_eq!(polars.column(col), simple.column(col), "{timezone}: {case}");
+                for row in 0..polars.height() {
+                    assert_eq!(
+                        polars.cell(row, col),
+                        simple.cell(row, col),
+                        "{timezone}: {case}"
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_payroll_overtime() {
+    use chrono::{Local, NaiveDate, TimeZone};
+
+    use crate::command::export::payroll_days;
+    use crate::csv::{Entry, EntryType};
+    use crate::shift::to_shifts;
+    use crate::types::TRAVEL_TAG;
+
+    let at = |day: u32, hour: u32| Local.with_ymd_and_hms(2024, 3, day, hour, 0, 0).unwrap();
+    // 9 hours every weekday of the week of 4 March, and one shift the Monday after.
+    // the last 2 hours of the Friday and the Monday are travel time
+    let shifts = [
+        (4, 8, 17, false),
+        (5, 8, 17, false),
+        (6, 8, 17, false),
+        (7, 8, 17, false),
+        (8, 8, 15, false),
+        (8, 15, 17, true),
+        (11, 8, 15, false),
+        (11, 15, 17, true),
+    ];
+    let entries = shifts
+        .into_iter()
+        .flat_map(|(day, start, end, travel)| {
+            let mut clock_in = Entry::new(EntryType::ClockIn, at(day, start), EntrySource::Cli);
+            if travel {
+                clock_in.tags.0.push(TRAVEL_TAG.to_string());
+            }
+            [
+                clock_in,
+                Entry::new(EntryType::ClockOut, at(day, end), EntrySource::Cli),
+            ]
+        })
+        .collect();
+    let shifts = to_shifts(entries);
+    let date = |day: u32| NaiveDate::from_ymd_opt(2024, 3, day).unwrap();
+
+    let cases = [
+        // (from, to, overtime after, [(day, regular, overtime, travel)])
+        (
+            4,
+            11,
+            40,
+            vec![
+                (4, 9, 0, 0),
+                (5, 9, 0, 0),
+                (6, 9, 0, 0),
+                (7, 9, 0, 0),
+                // travel within the regular hours is taken out of them
+                (8, 2, 5, 2),
+                (11, 7, 0, 2),
+            ],
+        ),
+        // the hours before the period still count towards the week
+        (7, 10, 40, vec![(7, 9, 0, 0), (8, 2, 5, 2)]),
+        (6, 6, 10, vec![(6, 0, 9, 0)]),
+        // travel beyond the threshold stays overtime
+        (8, 8, 1, vec![(8, 0, 9, 0)]),
+        (9, 10, 40, vec![]),
+    ];
+
+    for (from, to, overtime_after, expected) in cases {
+        let days = payroll_days(
+            &shifts,
+            date(from),
+            date(to),
+            Duration::hours(overtime_after),
+            at(31, 0),
+        );
+        let days = days
+            .iter()
+            .map(|d| {
+                (
+                    d.date,
+                    d.regular.num_hours() as u32,
+                    d.overtime.num_hours() as u32,
+                    d.travel.num_hours() as u32,
+                )
+            })
+            .collect::<Vec<_>>();
+        let expected = expected
+            .into_iter()
+            .map(|(day, regular, overtime, travel)| (date(day), regular, overtime, travel))
+            .collect::<Vec<_>>();
+        assert_eq!(days, expected, "{from} to {to}");
+    }
+}
+
+#[test]
+fn test_parse_org_clocks() {
+    use crate::command::import::org::parse;
+
+    let cases = [
+        // (file, [(type, time, project, tags)])
+        (
+            "* Acme :client:\n** TODO [#A] Fix bug :urgent:\nCLOCK: [2024-03-01 Fri 09:00]--[2024-03-01 Fri 12:30] =>  3:30\n",
+            vec![
+                ("in", "2024-03-01 09:00", Some("Fix bug"), vec!["client", "urgent"]),
+                ("out", "2024-03-01 12:30", None, vec![]),
+            ],
+        ),
+        // a running clock is only a clock-in
+        (
+            "* Acme\n  CLOCK: <2024-03-01 Fri 09:00>\n",
+            vec![("in", "2024-03-01 09:00", Some("Acme"), vec![])],
+        ),
+        // the headings of exported files without a project
+        (
+            "* [2024-03-01 Fri]\n** No project\nCLOCK: [2024-03-01 Fri 09:00]\n",
+            vec![("in", "2024-03-01 09:00", None, vec![])],
+        ),
+        ("CLOCK: [2024-03-01 Fri 09:00]\n", vec![("in", "2024-03-01 09:00", None, vec![])]),
+        ("* Acme\n*bold* text\n", vec![]),
+    ];
+
+    for (file, expected) in cases {
+        let entries = parse(file).unwrap();
+        let entries = entries
+            .iter()
+            .map(|e| {
+                (
+                    e.entry_type.to_string(),
+                    e.timestamp.format("%Y-%m-%d %H:%M").to_string(),
+                    e.project.clone(),
+                    e.tags.0.clone(),
+                )
+            })
+            .collect::<Vec<_>>();
+        let expected = expected
+            .into_iter()
+            .map(|(t, time, project, tags)| {
+                (
+                    t.to_string(),
+                    time.to_string(),
+                    project.map(String::from),
+                    tags.into_iter().map(String::from).collect::<Vec<_>>(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(entries, expected, "{file}");
+    }
+
+    for file in [
+        "CLOCK: [2024-13-01 Fri 09:00]",
+        "CLOCK: 2024-03-01 09:00",
+        "CLOCK: [2024-03-01 Fri 09:00]--[2024-03-01 Fri 08:00] => -1:00",
+    ] {
+        assert!(parse(file).is_err(), "{file}");
+    }
+}
+
+#[test]
+fn test_parse_timewarrior_intervals() {
+    use crate::command::import::timewarrior::parse;
+
+    let cases = [
+        // (line, project, tags, whether it has a clock-out)
+        (
+            "inc 20240301T090000Z - 20240301T120000Z # acme meeting",
+            Some("acme"),
+            vec!["meeting"],
+            true,
+        ),
+        (
+            r#"inc 20240301T090000Z - 20240301T120000Z # "acme corp" "say \"hi\"" # "notes""#,
+            Some("acme corp"),
+            vec![r#"say "hi""#],
+            true,
+        ),
+        (
+            "inc 20240301T090000Z - 20240301T120000Z",
+            None,
+            vec![],
+            true,
+        ),
+        ("inc 20240301T090000Z # acme", Some("acme"), vec![], false),
+    ];
+
+    for (line, project, tags, closed) in cases {
+        let entries = parse(line).unwrap();
+        assert_eq!(entries[0].project.as_deref(), project, "{line}");
+        assert_eq!(entries[0].tags.0, tags, "{line}");
+        assert_eq!(entries.len(), if closed { 2 } else { 1 }, "{line}");
+    }
+
+    for line in [
+        "inc 20240301 - 20240301T120000Z",
+        "exc 20240301T090000Z",
+        "inc 20240301T120000Z - 20240301T090000Z",
+    ] {
+        assert!(parse(line).is_err(), "{line}");
+    }
+}
+
+#[test]
+fn test_batch_ties() {
+    use clap::Parser;
+
+    use crate::command::batch::{add_entries, parse_line};
+    use crate::csv::{read_entries, EntryType};
+    use crate::Cli;
+
+    const HOURS: &str = "\
+entry_type,timestamp,project,tags,source
+in,2024-03-01T12:00:00.000000000+0000,,,cli
+out,2024-03-01T17:00:00.000000000+0000,,,cli
+";
+
+    let cases = [
+        // a shift that ends when an existing one starts
+        [
+            "in 2024-03-01T09:00:00+00:00",
+            "out 2024-03-01T12:00:00+00:00",
+        ],
+        // a shift that starts when an existing one ends
+        [
+            "in 2024-03-01T17:00:00+00:00",
+            "out 2024-03-01T18:00:00+00:00",
+        ],
+    ];
+
+    for lines in cases {
+        let data_folder = temp_dir::TempDir::new().unwrap();
+        std::fs::write(data_folder.path().join("hours.csv"), HOURS).unwrap();
+        let cli_args = Cli::parse_from([
+            "punchcard",
+            "-d",
+            data_folder.path().to_str().unwrap(),
+            "status",
+        ]);
+
+        let batch = lines.map(|line| parse_line(line).unwrap().unwrap());
+        add_entries(&cli_args, batch.to_vec(), false).unwrap();
+
+        let entries = read_entries(&cli_args).unwrap();
+        assert_eq!(entries.len(), 4, "{lines:?}");
+        for (idx, pair) in entries.windows(2).enumerate() {
+            assert!(pair[0].timestamp < pair[1].timestamp, "{lines:?}");
+            let expected = if idx % 2 == 0 {
+                EntryType::ClockIn
+            } else {
+                EntryType::ClockOut
+            };
+            assert_eq!(pair[0].entry_type, expected, "{lines:?}");
+        }
+    }
+}
+
+#[test]
+fn test_merge_projects() {
+    use clap::Parser;
+
+    use crate::command::project::{manage_projects, read_projects};
+    use crate::{Cli, Operation};
+
+    const PROJECTS: &str = r#"{"alpha":{"color":"red"},"beta":{"color":"blue"}}"#;
+
+    let cases = [
+        // (projects to merge, target, color of the target afterwards)
+        ("alpha beta", "gamma", "red"),
+        ("beta alpha", "gamma", "blue"),
+        ("alpha beta", "beta", "blue"),
+    ];
+
+    for (sources, into, color) in cases {
+        let data_folder = temp_dir::TempDir::new().unwrap();
+        std::fs::write(data_folder.path().join("projects.json"), PROJECTS).unwrap();
+        let folder = data_folder.path().to_str().unwrap();
+        let args = format!("punchcard -d {folder} project merge {sources} --into {into}");
+        let cli_args = Cli::parse_from(args.split(' '));
+        let Operation::Project(project_args) = &cli_args.operation else {
+            unreachable!("the arguments are a project command");
+        };
+        manage_projects(&cli_args, project_args).unwrap();
+
+        let projects = read_projects(&cli_args).unwrap();
+        let names = projects.keys().map(String::as_str).collect::<Vec<_>>();
+        assert_eq!(names, [into], "{sources} -> {into}");
+        let target = projects[into].color.as_ref().unwrap();
+        assert_eq!(target.to_string(), color, "{sources} -> {into}");
+    }
+}
+
+#[test]
+fn test_cli_arguments_are_consistent() {
+    use clap::CommandFactory;
+
+    // clap only checks for clashing flags when a subcommand is used
+    crate::Cli::command().debug_assert();
+}
@@ -20,6 +20,7 @@ use std::{
 };
 
 use chrono::{DateTime, Duration, Local, OutOfRangeError, TimeZone};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use thiserror::Error;
 
 /// A wrapper around the `humantime` crate which allows parsing negative durations.
@@ -101,6 +102,17 @@ impl BiDuration {
         s
     }
 
+    /// Formats the absolute duration rounded to the minute, e.g. `2h14m`, for narrow columns.
+    pub fn to_compact_string(&self) -> String {
+        let (std_duration, _) = self.to_std_duration();
+        let minutes = (std_duration.as_secs() + 30) / 60;
+        match (minutes / 60, minutes % 60) {
+            (0, minutes) => format!("{minutes}m"),
+            (hours, 0) => format!("{hours}h"),
+            (hours, minutes) => format!("{hours}h{minutes:02}m"),
+        }
+    }
+
     pub fn to_friendly_relative_string(&self) -> String {
         let absolute = self.to_friendly_absolute_string();
         if **self < Duration::zero() {
@@ -136,6 +148,21 @@ impl Deref for BiDuration {
     }
 }
 
+// serialized using the same format that is accepted on the command line
+// so that stored durations can be read and edited by hand
+impl Serialize for BiDuration {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_friendly_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for BiDuration {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
 impl<T: TimeZone> Add<DateTime<T>> for BiDuration {
     type Output = DateTime<T>;
     fn add(self, rhs: DateTime<T>) -> Self::Output {
@@ -0,0 +1,58 @@
+// Copyright (C) 2023 Campbell M. Cole
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::str::FromStr;
+
+use thiserror::Error;
+
+/// A name to show instead of a report column, e.g. `Total Hours=Gesamtstunden`.
+#[cfg_attr(test, derive(PartialEq))]
+#[derive(Debug, Clone)]
+pub struct ColumnLabel {
+    pub column: String,
+    pub label: String,
+}
+
+#[cfg_attr(test, derive(PartialEq))]
+#[derive(Debug, Error)]
+pub enum ColumnLabelError {
+    #[error("Missing '=' between the column and its label")]
+    MissingEquals,
+    #[error("Missing column name")]
+    MissingColumn,
+    #[error("Missing label")]
+    MissingLabel,
+}
+
+impl FromStr for ColumnLabel {
+    type Err = ColumnLabelError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (column, label) = s.split_once('=').ok_or(ColumnLabelError::MissingEquals)?;
+
+        let (column, label) = (column.trim(), label.trim());
+        if column.is_empty() {
+            return Err(ColumnLabelError::MissingColumn);
+        }
+        if label.is_empty() {
+            return Err(ColumnLabelError::MissingLabel);
+        }
+
+        Ok(ColumnLabel {
+            column: column.into(),
+            label: label.into(),
+        })
+    }
+}
@@ -0,0 +1,80 @@
+// Copyright (C) 2023 Campbell M. Cole
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::str::FromStr;
+
+use chrono::{DateTime, Duration, Local, NaiveTime, TimeZone};
+use thiserror::Error;
+
+use crate::common::{parse_local_datetime, parse_time_of_day};
+
+/// A change to the time of an entry, e.g. `+15m`, `-1h`, `14:30`, or
+/// `2024-03-01T09:00`.
+#[cfg_attr(test, derive(PartialEq))]
+#[derive(Debug, Clone)]
+pub enum Correction {
+    /// Move the entry later or earlier by this much
+    Shift(Duration),
+    /// Move the entry to this time on the same day
+    TimeOfDay(NaiveTime),
+    /// Move the entry to this exact time
+    At(DateTime<Local>),
+}
+
+#[cfg_attr(test, derive(PartialEq))]
+#[derive(Debug, Error)]
+pub enum CorrectionError {
+    #[error("Expected '+15m', '-1h', a time such as '14:30', or a timestamp such as '2024-03-01T09:00', found '{0}'")]
+    Unknown(String),
+    #[error("Invalid duration: {0}")]
+    InvalidDuration(#[from] humantime::DurationError),
+    #[error("Out of range: {0}")]
+    OutOfRange(#[from] chrono::OutOfRangeError),
+}
+
+impl Correction {
+    /// The corrected time, or `None` if it doesn't exist in the local timezone.
+    pub fn apply(&self, timestamp: DateTime<Local>) -> Option<DateTime<Local>> {
+        match self {
+            Self::Shift(duration) => Some(timestamp + *duration),
+            Self::TimeOfDay(time) => Local
+                .from_local_datetime(&timestamp.date_naive().and_time(*time))
+                .single(),
+            Self::At(at) => Some(*at),
+        }
+    }
+}
+
+impl FromStr for Correction {
+    type Err = CorrectionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Some(duration) = s.strip_prefix('+') {
+            let duration = Duration::from_std(humantime::parse_duration(duration.trim())?)?;
+            return Ok(Self::Shift(duration));
+        }
+        if let Some(duration) = s.strip_prefix('-') {
+            let duration = Duration::from_std(humantime::parse_duration(duration.trim())?)?;
+            return Ok(Self::Shift(-duration));
+        }
+        if let Ok(time) = parse_time_of_day(s) {
+            return Ok(Self::TimeOfDay(time));
+        }
+        parse_local_datetime(s)
+            .map(Self::At)
+            .ok_or_else(|| CorrectionError::Unknown(s.to_string()))
+    }
+}
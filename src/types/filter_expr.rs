@@ -0,0 +1,104 @@
+// Copyright (C) 2023 Campbell M. Cole
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::{fmt::Display, str::FromStr};
+
+use thiserror::Error;
+
+#[cfg_attr(test, derive(PartialEq))]
+#[derive(Debug, Clone, Copy)]
+pub enum CompareOp {
+    Lt,
+    LtEq,
+    Gt,
+    GtEq,
+    Eq,
+    NotEq,
+}
+
+impl Display for CompareOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            CompareOp::Lt => "<",
+            CompareOp::LtEq => "<=",
+            CompareOp::Gt => ">",
+            CompareOp::GtEq => ">=",
+            CompareOp::Eq => "=",
+            CompareOp::NotEq => "!=",
+        };
+        write!(f, "{s}")
+    }
+}
+
+/// A comparison between a report column and a value, e.g. `Total Hours > 35h`.
+///
+/// The value is kept as a string because how it is parsed depends on the type of the column.
+#[cfg_attr(test, derive(PartialEq))]
+#[derive(Debug, Clone)]
+pub struct FilterExpr {
+    pub column: String,
+    pub op: CompareOp,
+    pub value: String,
+}
+
+#[cfg_attr(test, derive(PartialEq))]
+#[derive(Debug, Error)]
+pub enum FilterExprError {
+    #[error("Missing comparison operator. Must be one of <, <=, >, >=, =, !=")]
+    MissingOperator,
+    #[error("Missing column name before '{0}'")]
+    MissingColumn(CompareOp),
+    #[error("Missing value after '{0}'")]
+    MissingValue(CompareOp),
+}
+
+impl FromStr for FilterExpr {
+    type Err = FilterExprError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // two character operators must be checked first so `>=` isn't read as `>`
+        const OPERATORS: [(&str, CompareOp); 7] = [
+            ("<=", CompareOp::LtEq),
+            (">=", CompareOp::GtEq),
+            ("!=", CompareOp::NotEq),
+            ("==", CompareOp::Eq),
+            ("<", CompareOp::Lt),
+            (">", CompareOp::Gt),
+            ("=", CompareOp::Eq),
+        ];
+
+        let (idx, token, op) = OPERATORS
+            .iter()
+            .filter_map(|(token, op)| s.find(token).map(|idx| (idx, *token, *op)))
+            .min_by_key(|(idx, _, _)| *idx)
+            .ok_or(FilterExprError::MissingOperator)?;
+
+        let column = s[..idx].trim();
+        let value = s[idx + token.len()..].trim();
+
+        if column.is_empty() {
+            return Err(FilterExprError::MissingColumn(op));
+        }
+        if value.is_empty() {
+            return Err(FilterExprError::MissingValue(op));
+        }
+
+        Ok(FilterExpr {
+            column: column.into(),
+            op,
+            value: value.into(),
+        })
+    }
+}
@@ -19,8 +19,32 @@ pub use biduration::*;
 mod quantity;
 pub use quantity::*;
 
+mod date_range;
+pub use date_range::*;
+
 mod destination;
 pub use destination::*;
 
 mod month;
 pub use month::*;
+
+mod week;
+pub use week::*;
+
+mod filter_expr;
+pub use filter_expr::*;
+
+mod sort_key;
+pub use sort_key::*;
+
+mod correction;
+pub use correction::*;
+
+mod column_label;
+pub use column_label::*;
+
+mod tags;
+pub use tags::*;
+
+mod url;
+pub use url::*;
@@ -0,0 +1,62 @@
+// Copyright (C) 2023 Campbell M. Cole
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::str::FromStr;
+
+use thiserror::Error;
+
+/// A column to sort a report by, e.g. `Total Hours:desc`.
+#[cfg_attr(test, derive(PartialEq))]
+#[derive(Debug, Clone)]
+pub struct SortKey {
+    pub column: String,
+    pub descending: bool,
+}
+
+#[cfg_attr(test, derive(PartialEq))]
+#[derive(Debug, Error)]
+pub enum SortKeyError {
+    #[error("Missing column name")]
+    MissingColumn,
+    #[error("Unknown sort direction '{0}'. Must be \"asc\" or \"desc\"")]
+    UnknownDirection(String),
+}
+
+impl FromStr for SortKey {
+    type Err = SortKeyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (column, direction) = match s.rsplit_once(':') {
+            Some((column, direction)) => (column, Some(direction.trim())),
+            None => (s, None),
+        };
+
+        let descending = match direction.map(str::to_ascii_lowercase).as_deref() {
+            None | Some("asc") => false,
+            Some("desc") => true,
+            Some(_) => return Err(SortKeyError::UnknownDirection(direction.unwrap().into())),
+        };
+
+        let column = column.trim();
+        if column.is_empty() {
+            return Err(SortKeyError::MissingColumn);
+        }
+
+        Ok(SortKey {
+            column: column.into(),
+            descending,
+        })
+    }
+}
@@ -0,0 +1,79 @@
+// Copyright (C) 2023 Campbell M. Cole
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::{fmt::Display, ops::Deref};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+pub const TAG_SEPARATOR: char = ';';
+
+/// The tag of shifts spent travelling, which reports total separately
+/// because some employers pay them at a different rate.
+pub const TRAVEL_TAG: &str = "travel";
+
+/// A list of tags attached to an entry.
+///
+/// Tags are stored in a single CSV column separated by semicolons, so
+/// the file stays readable and polars can treat the column as a plain string.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Tags(pub Vec<String>);
+
+impl Tags {
+    pub fn contains(&self, tag: &str) -> bool {
+        self.0.iter().any(|t| t == tag)
+    }
+
+    pub fn is_travel(&self) -> bool {
+        self.contains(TRAVEL_TAG)
+    }
+}
+
+impl Deref for Tags {
+    type Target = Vec<String>;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl From<Vec<String>> for Tags {
+    fn from(value: Vec<String>) -> Self {
+        Self(value)
+    }
+}
+
+impl Display for Tags {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0.join(&TAG_SEPARATOR.to_string()))
+    }
+}
+
+impl Serialize for Tags {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for Tags {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Ok(Self(
+            s.split(TAG_SEPARATOR)
+                .map(str::trim)
+                .filter(|t| !t.is_empty())
+                .map(String::from)
+                .collect(),
+        ))
+    }
+}
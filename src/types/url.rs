@@ -0,0 +1,99 @@
+// Copyright (C) 2023 Campbell M. Cole
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::str::FromStr;
+
+use percent_encoding::percent_decode_str;
+use thiserror::Error;
+
+pub const URL_SCHEME: &str = "punchcard://";
+
+/// A `punchcard://<action>?<key>=<value>&...` URL.
+///
+/// Keys can be repeated (e.g. `tag=a&tag=b`). Keys and values are percent-decoded,
+/// and `+` is decoded as a space like in HTML forms.
+#[cfg_attr(test, derive(PartialEq))]
+#[derive(Debug, Clone)]
+pub struct PunchcardUrl {
+    pub action: String,
+    pub params: Vec<(String, String)>,
+}
+
+#[cfg_attr(test, derive(PartialEq))]
+#[derive(Debug, Error)]
+pub enum PunchcardUrlError {
+    #[error("URL must start with \"{URL_SCHEME}\"")]
+    WrongScheme,
+    #[error("URL does not contain an action")]
+    MissingAction,
+    #[error("URL contains invalid UTF-8 after decoding")]
+    InvalidEncoding,
+}
+
+impl PunchcardUrl {
+    /// The last value given for `key`
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .rev()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Every value given for `key`, in order
+    pub fn get_all(&self, key: &str) -> Vec<String> {
+        self.params
+            .iter()
+            .filter(|(k, _)| k == key)
+            .map(|(_, v)| v.clone())
+            .collect()
+    }
+}
+
+fn decode(s: &str) -> Result<String, PunchcardUrlError> {
+    percent_decode_str(&s.replace('+', " "))
+        .decode_utf8()
+        .map(|s| s.into_owned())
+        .map_err(|_| PunchcardUrlError::InvalidEncoding)
+}
+
+impl FromStr for PunchcardUrl {
+    type Err = PunchcardUrlError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rest = s
+            .get(..URL_SCHEME.len())
+            .filter(|scheme| scheme.eq_ignore_ascii_case(URL_SCHEME))
+            .map(|_| &s[URL_SCHEME.len()..])
+            .ok_or(PunchcardUrlError::WrongScheme)?;
+
+        let (action, query) = rest.split_once('?').unwrap_or((rest, ""));
+        let action = decode(action.trim_end_matches('/'))?.to_lowercase();
+        if action.is_empty() {
+            return Err(PunchcardUrlError::MissingAction);
+        }
+
+        let params = query
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+                Ok((decode(key)?, decode(value)?))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(PunchcardUrl { action, params })
+    }
+}
@@ -0,0 +1,62 @@
+// Copyright (C) 2023 Campbell M. Cole
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::str::FromStr;
+
+use chrono::{Datelike, Duration, NaiveDate};
+use thiserror::Error;
+
+/// A week to enter hours for, e.g. `last` or `2024-03-04`.
+#[cfg_attr(test, derive(PartialEq))]
+#[derive(Debug, Clone, Copy)]
+pub enum Week {
+    Current,
+    Previous,
+    /// The week containing this date
+    Of(NaiveDate),
+}
+
+#[cfg_attr(test, derive(PartialEq))]
+#[derive(Debug, Error)]
+pub enum ParseWeekError {
+    #[error("Unknown week '{0}'. Expected 'current', 'last', or a date such as '2024-03-04'")]
+    UnknownWeek(String),
+}
+
+impl Week {
+    /// The Monday the week starts on.
+    pub fn monday(&self, today: NaiveDate) -> NaiveDate {
+        let date = match self {
+            Week::Current => today,
+            Week::Previous => today - Duration::weeks(1),
+            Week::Of(date) => *date,
+        };
+        date - Duration::days(date.weekday().num_days_from_monday() as i64)
+    }
+}
+
+impl FromStr for Week {
+    type Err = ParseWeekError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "current" | "this" => Ok(Week::Current),
+            "previous" | "last" => Ok(Week::Previous),
+            _ => NaiveDate::parse_from_str(s, "%Y-%m-%d")
+                .map(Week::Of)
+                .map_err(|_| ParseWeekError::UnknownWeek(s.into())),
+        }
+    }
+}